@@ -0,0 +1,60 @@
+use std::hash::Hash;
+
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// Returns the `(old index, new index)` pairs of lines kept unchanged between
+/// `old` and `new`, taken from a longest common subsequence.
+///
+/// The subsequence is found with Myers' diff, which runs in linear space and skips the
+/// common prefix and suffix, so long files with few changes stay cheap.
+pub fn lcs_line_pairs<T: Hash + Ord>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    let mut pairs = vec![];
+    for op in capture_diff_slices(Algorithm::Myers, old, new) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            pairs.extend((0..len).map(|k| (old_index + k, new_index + k)));
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lcs_line_pairs_identical() {
+        let lines = ["a", "b", "c"];
+        assert_eq!(lcs_line_pairs(&lines, &lines), vec![(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_lcs_line_pairs_insert_and_delete() {
+        let old = ["a", "b", "c", "d"];
+        let new = ["a", "x", "c", "d", "e"];
+        assert_eq!(lcs_line_pairs(&old, &new), vec![(0, 0), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn test_lcs_line_pairs_empty() {
+        let old: [&str; 0] = [];
+        let new = ["a"];
+        assert_eq!(lcs_line_pairs(&old, &new), vec![]);
+        assert_eq!(lcs_line_pairs(&new, &old), vec![]);
+    }
+
+    #[test]
+    fn test_lcs_line_pairs_long() {
+        let old = (0..20_000).map(|i| i.to_string()).collect::<Vec<_>>();
+        let mut new = old.clone();
+        new[10_000] = "changed".to_string();
+        new.insert(15_000, "inserted".to_string());
+        let pairs = lcs_line_pairs(&old, &new);
+        assert_eq!(pairs.len(), 19_999);
+        assert!(pairs.iter().all(|(i, j)| old[*i] == new[*j]));
+    }
+}
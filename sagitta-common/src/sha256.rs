@@ -1,3 +1,5 @@
+use std::io::Read;
+
 use sha2::{Digest, Sha256};
 
 pub fn calc_sha256_from_slice(data: &[u8]) -> String {
@@ -6,3 +8,32 @@ pub fn calc_sha256_from_slice(data: &[u8]) -> String {
     let result = hasher.finalize();
     format!("{:x}", result)
 }
+
+/// Hashes everything read from `reader`, a chunk at a time.
+pub fn calc_sha256_from_reader<R: Read>(mut reader: R) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    let result = hasher.finalize();
+    Ok(format!("{:x}", result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calc_sha256_from_reader() {
+        let data = (0..200_000u32).map(|i| i as u8).collect::<Vec<_>>();
+        assert_eq!(
+            calc_sha256_from_reader(&data[..]).unwrap(),
+            calc_sha256_from_slice(&data)
+        );
+    }
+}
@@ -1,12 +1,12 @@
-use std::{process::Command, time::SystemTime};
+use std::{collections::BTreeMap, os::fd::AsRawFd, process::Command, time::SystemTime};
 
 use sagitta::fs::{run_fs, SagittaConfig};
 use sagitta_common::clock::Clock;
-use sagitta_local_api_schema::v1::sync::V1SyncRequest;
+use sagitta_local_api_schema::v1::sync::{V1SyncRequest, V1SyncResponse};
 use sagitta_local_system_workspace::LocalSystemWorkspaceManager;
 use sagitta_remote_api_client::SagittaApiClient;
 use sagitta_remote_api_schema::v2::{
-    commit::V2CommitRequest,
+    commit::{V2CommitRequest, V2CommitResponse},
     create_workspace::{V2CreateWorkspaceRequest, V2CreateWorkspaceResponse},
     get_workspaces::V2GetWorkspacesRequest,
     sync_files_with_workspace::{
@@ -72,6 +72,7 @@ fn test_2() {
         let workspace_id = client
             .v2_create_workspace(V2CreateWorkspaceRequest {
                 name: "workspace1".to_string(),
+                branch_name: "trunk".to_string(),
             })
             .unwrap();
         let workspace_id = match workspace_id {
@@ -99,23 +100,34 @@ fn test_2() {
         client
             .v2_sync_files_with_workspace(V2SyncFilesWithWorkspaceRequest {
                 workspace_id: workspace_id.clone(),
+                sync_id: None,
                 items: vec![
                     V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
                         file_path: vec!["hello.txt".to_string()],
                         blob_id: hello_blob_id.clone(),
                         permission: 0o644,
+                        xattrs: BTreeMap::new(),
+                        modified_at: None,
                     },
                     V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
                         file_path: vec!["hello_dir".to_string(), "hello2.txt".to_string()],
                         blob_id: hello2_blob_id.clone(),
                         permission: 0o644,
+                        xattrs: BTreeMap::new(),
+                        modified_at: None,
                     },
                 ],
             })
             .unwrap();
 
         // commit
-        client.v2_commit(V2CommitRequest { workspace_id }).unwrap();
+        client
+            .v2_commit(V2CommitRequest {
+                workspace_id,
+                keep_workspace: false,
+                paths: None,
+            })
+            .unwrap();
     }
 
     let out0 = Command::new("ls")
@@ -193,6 +205,7 @@ fn test_3() {
     client
         .v2_create_workspace(V2CreateWorkspaceRequest {
             name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
         })
         .unwrap();
     let workspaces = client.v2_get_workspaces(V2GetWorkspacesRequest {}).unwrap();
@@ -233,6 +246,7 @@ fn test_4() {
         let workspace_id = client
             .v2_create_workspace(V2CreateWorkspaceRequest {
                 name: "workspace2".to_string(),
+                branch_name: "trunk".to_string(),
             })
             .unwrap();
         let workspace_id = match workspace_id {
@@ -260,23 +274,34 @@ fn test_4() {
         client
             .v2_sync_files_with_workspace(V2SyncFilesWithWorkspaceRequest {
                 workspace_id: workspace_id.clone(),
+                sync_id: None,
                 items: vec![
                     V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
                         file_path: vec!["hello.txt".to_string()],
                         blob_id: hello_blob_id.clone(),
                         permission: 0o644,
+                        xattrs: BTreeMap::new(),
+                        modified_at: None,
                     },
                     V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
                         file_path: vec!["hello_dir".to_string(), "hello2.txt".to_string()],
                         blob_id: hello2_blob_id.clone(),
                         permission: 0o644,
+                        xattrs: BTreeMap::new(),
+                        modified_at: None,
                     },
                 ],
             })
             .unwrap();
 
         // commit
-        client.v2_commit(V2CommitRequest { workspace_id }).unwrap();
+        client
+            .v2_commit(V2CommitRequest {
+                workspace_id,
+                keep_workspace: false,
+                paths: None,
+            })
+            .unwrap();
     }
 
     let local_system_workspace_base_path = tempdir().unwrap().as_ref().to_path_buf();
@@ -308,6 +333,7 @@ fn test_4() {
     let workspace_id = client
         .v2_create_workspace(V2CreateWorkspaceRequest {
             name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
         })
         .unwrap();
     let workspace1_id = match workspace_id {
@@ -447,6 +473,7 @@ fn test_5() {
     client
         .v2_create_workspace(V2CreateWorkspaceRequest {
             name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
         })
         .unwrap();
 
@@ -518,6 +545,7 @@ fn test_6() {
     let create_workspace1_res = client
         .v2_create_workspace(V2CreateWorkspaceRequest {
             name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
         })
         .unwrap();
 
@@ -528,6 +556,8 @@ fn test_6() {
         port: 8088,
         local_system_workspace_base_path: local_system_workspace_base_path.clone(),
         remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
     };
     runtime.spawn(async {
         sagitta_local_server::api::run_local_api_server(local_server_config).await;
@@ -591,6 +621,7 @@ fn test_6() {
     };
     let sync_res = local_api_client.v1_sync(V1SyncRequest {
         workspace_id: workspace_id.clone(),
+        dry_run: false,
     });
     insta::assert_debug_snapshot!(sync_res);
 
@@ -642,6 +673,7 @@ fn test_7() {
     let create_workspace1_res = client
         .v2_create_workspace(V2CreateWorkspaceRequest {
             name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
         })
         .unwrap();
 
@@ -652,6 +684,8 @@ fn test_7() {
         port: 8090,
         local_system_workspace_base_path: local_system_workspace_base_path.clone(),
         remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
     };
     runtime.spawn(async {
         sagitta_local_server::api::run_local_api_server(local_server_config).await;
@@ -723,6 +757,7 @@ fn test_7() {
 
     let sync_res = local_api_client.v1_sync(V1SyncRequest {
         workspace_id: workspace_id.clone(),
+        dry_run: false,
     });
     insta::assert_debug_snapshot!(sync_res);
 
@@ -742,6 +777,7 @@ fn test_7() {
 
     let sync_res_2 = local_api_client.v1_sync(V1SyncRequest {
         workspace_id: workspace_id.clone(),
+        dry_run: false,
     });
     insta::assert_debug_snapshot!(sync_res_2);
 }
@@ -776,6 +812,7 @@ fn test_8() {
     let create_workspace1_res = client
         .v2_create_workspace(V2CreateWorkspaceRequest {
             name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
         })
         .unwrap();
 
@@ -786,6 +823,8 @@ fn test_8() {
         port: 8092,
         local_system_workspace_base_path: local_system_workspace_base_path.clone(),
         remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
     };
     runtime.spawn(async {
         sagitta_local_server::api::run_local_api_server(local_server_config).await;
@@ -833,7 +872,7 @@ fn test_8() {
 
     let _cargo_build_output = Command::new("cargo")
         .arg("build")
-        .current_dir(&path_out1.join("foo"))
+        .current_dir(path_out1.join("foo"))
         .output()
         .expect("failed to execute process");
     // Todo: Fix GitHub CI
@@ -841,7 +880,7 @@ fn test_8() {
 
     let _cargo_run_output = Command::new("cargo")
         .arg("run")
-        .current_dir(&path_out1.join("foo"))
+        .current_dir(path_out1.join("foo"))
         .output()
         .expect("failed to execute process");
     eprintln!("cargo run output: {:?}", _cargo_run_output);
@@ -855,6 +894,7 @@ fn test_8() {
 
     let sync_res = local_api_client.v1_sync(V1SyncRequest {
         workspace_id: workspace_id.clone(),
+        dry_run: false,
     });
     insta::assert_debug_snapshot!(sync_res);
 }
@@ -889,6 +929,7 @@ fn test_9() {
     let create_workspace1_res = client
         .v2_create_workspace(V2CreateWorkspaceRequest {
             name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
         })
         .unwrap();
 
@@ -899,6 +940,8 @@ fn test_9() {
         port: 8094,
         local_system_workspace_base_path: local_system_workspace_base_path.clone(),
         remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
     };
     runtime.spawn(async {
         sagitta_local_server::api::run_local_api_server(local_server_config).await;
@@ -943,6 +986,7 @@ fn test_9() {
 
     let sync_res_1 = local_api_client.v1_sync(V1SyncRequest {
         workspace_id: workspace_id.clone(),
+        dry_run: false,
     });
     insta::assert_debug_snapshot!(sync_res_1);
 
@@ -954,6 +998,7 @@ fn test_9() {
 
     let sync_res_2 = local_api_client.v1_sync(V1SyncRequest {
         workspace_id: workspace_id.clone(),
+        dry_run: false,
     });
     insta::assert_debug_snapshot!(sync_res_2);
 
@@ -980,6 +1025,7 @@ fn test_9() {
 
     let sync_res_3 = local_api_client.v1_sync(V1SyncRequest {
         workspace_id: workspace_id.clone(),
+        dry_run: false,
     });
     insta::assert_debug_snapshot!(sync_res_3);
 
@@ -992,6 +1038,7 @@ fn test_9() {
 
     let sync_res_4 = local_api_client.v1_sync(V1SyncRequest {
         workspace_id: workspace_id.clone(),
+        dry_run: false,
     });
     insta::assert_debug_snapshot!(sync_res_4);
 
@@ -1003,3 +1050,917 @@ fn test_9() {
         .expect("failed to execute process");
     insta::assert_debug_snapshot!(out2);
 }
+
+#[test]
+#[serial]
+fn test_10() {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let tempdir1 = tempdir().unwrap();
+    let fixed_system_time =
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(40 * 365 * 24 * 60 * 60);
+    let port = 8095;
+    let config = ServerConfig {
+        base_path: tempdir1.as_ref().to_path_buf(),
+        is_main: false,
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port,
+    };
+
+    runtime.spawn(async {
+        sagitta_remote_server::api::run_server(config).await;
+    });
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    let client = SagittaApiClient::new(format!("http://localhost:{}", port));
+    let create_workspace1_res = client
+        .v2_create_workspace(V2CreateWorkspaceRequest {
+            name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
+        })
+        .unwrap();
+    let workspace_id = match create_workspace1_res {
+        V2CreateWorkspaceResponse::Ok { id } => id,
+        _ => panic!("unexpected response"),
+    };
+
+    let local_system_workspace_base_path = tempdir().unwrap().as_ref().to_path_buf();
+
+    let local_server_config = sagitta_local_server::api::ServerConfig {
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port: 8096,
+        local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+        remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
+    };
+    runtime.spawn(async {
+        sagitta_local_server::api::run_local_api_server(local_server_config).await;
+    });
+
+    let local_api_client =
+        sagitta_local_api_client::SagittaLocalApiClient::new(format!("http://localhost:{}", 8096));
+
+    let tempdir2 = tempdir().unwrap();
+    let tempdir2_str = tempdir2.as_ref().to_str().unwrap().to_string();
+    {
+        let local_system_workspace_base_path = local_system_workspace_base_path.clone();
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        std::thread::spawn(move || {
+            let config = SagittaConfig {
+                base_url: format!("http://localhost:{}", port),
+                mountpoint: tempdir2_str,
+                uid,
+                gid,
+                clock: Clock::new_with_fixed_time(fixed_system_time),
+                local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+                debug_sleep_duration: None,
+            };
+            run_fs(config);
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+
+    let path_out1 = tempdir2.path().join("workspace1");
+    Command::new("bash")
+        .arg("-c")
+        .arg("mkdir dir1 && echo 'Hello!' > dir1/hello.txt && echo 'World!' > world.txt")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+
+    let sync_res_1 = local_api_client
+        .v1_sync(V1SyncRequest {
+            workspace_id: workspace_id.clone(),
+            dry_run: false,
+        })
+        .unwrap();
+    let V1SyncResponse::Ok {
+        upsert_files,
+        upsert_dirs,
+        ..
+    } = sync_res_1
+    else {
+        panic!("unexpected response");
+    };
+    assert_eq!(upsert_dirs, vec![vec!["dir1".to_string()]]);
+    assert_eq!(
+        upsert_files,
+        vec![
+            vec!["dir1".to_string(), "hello.txt".to_string()],
+            vec!["world.txt".to_string()],
+        ]
+    );
+
+    // only the path written since the first sync is synced again
+    Command::new("bash")
+        .arg("-c")
+        .arg("echo 'Hello again!' >> dir1/hello.txt")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+
+    let sync_res_2 = local_api_client
+        .v1_sync(V1SyncRequest {
+            workspace_id: workspace_id.clone(),
+            dry_run: false,
+        })
+        .unwrap();
+    let V1SyncResponse::Ok {
+        upsert_files,
+        delete_files,
+        ..
+    } = sync_res_2
+    else {
+        panic!("unexpected response");
+    };
+    assert_eq!(
+        upsert_files,
+        vec![vec!["dir1".to_string(), "hello.txt".to_string()]]
+    );
+    assert!(delete_files.is_empty());
+
+    let out1 = Command::new("cat")
+        .arg("dir1/hello.txt")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+    assert_eq!(out1.stdout, b"Hello!\nHello again!\n");
+}
+
+#[test]
+#[serial]
+fn test_11() {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let tempdir1 = tempdir().unwrap();
+    let fixed_system_time =
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(40 * 365 * 24 * 60 * 60);
+    let port = 8097;
+    let config = ServerConfig {
+        base_path: tempdir1.as_ref().to_path_buf(),
+        is_main: false,
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port,
+    };
+
+    runtime.spawn(async {
+        sagitta_remote_server::api::run_server(config).await;
+    });
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    let client = SagittaApiClient::new(format!("http://localhost:{}", port));
+    let create_workspace1_res = client
+        .v2_create_workspace(V2CreateWorkspaceRequest {
+            name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
+        })
+        .unwrap();
+    let workspace_id = match create_workspace1_res {
+        V2CreateWorkspaceResponse::Ok { id } => id,
+        _ => panic!("unexpected response"),
+    };
+
+    let local_system_workspace_base_path = tempdir().unwrap().as_ref().to_path_buf();
+
+    let local_server_config = sagitta_local_server::api::ServerConfig {
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port: 8098,
+        local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+        remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
+    };
+    runtime.spawn(async {
+        sagitta_local_server::api::run_local_api_server(local_server_config).await;
+    });
+
+    let local_api_client =
+        sagitta_local_api_client::SagittaLocalApiClient::new(format!("http://localhost:{}", 8098));
+
+    let tempdir2 = tempdir().unwrap();
+    let tempdir2_str = tempdir2.as_ref().to_str().unwrap().to_string();
+    {
+        let local_system_workspace_base_path = local_system_workspace_base_path.clone();
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        std::thread::spawn(move || {
+            let config = SagittaConfig {
+                base_url: format!("http://localhost:{}", port),
+                mountpoint: tempdir2_str,
+                uid,
+                gid,
+                clock: Clock::new_with_fixed_time(fixed_system_time),
+                local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+                debug_sleep_duration: None,
+            };
+            run_fs(config);
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+
+    let path_out1 = tempdir2.path().join("workspace1");
+    let out1 = Command::new("bash")
+        .arg("-c")
+        .arg("echo 'Hello!' > a.txt && ln a.txt b.txt && echo 'World!' >> b.txt && cat a.txt && stat -c %h a.txt b.txt")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+    assert_eq!(out1.stdout, b"Hello!\nWorld!\n2\n2\n");
+
+    // the links share one blob, uploaded once
+    let sync_res_1 = local_api_client
+        .v1_sync(V1SyncRequest {
+            workspace_id: workspace_id.clone(),
+            dry_run: false,
+        })
+        .unwrap();
+    let V1SyncResponse::Ok {
+        upsert_files,
+        reference_files,
+        ..
+    } = sync_res_1
+    else {
+        panic!("unexpected response");
+    };
+    assert_eq!(upsert_files.len(), 1);
+    let mut synced_files = [upsert_files, reference_files].concat();
+    synced_files.sort();
+    assert_eq!(
+        synced_files,
+        vec![vec!["a.txt".to_string()], vec!["b.txt".to_string()]]
+    );
+
+    let out2 = Command::new("bash")
+        .arg("-c")
+        .arg("rm a.txt && cat b.txt && stat -c %h b.txt")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+    assert_eq!(out2.stdout, b"Hello!\nWorld!\n1\n");
+
+    let sync_res_2 = local_api_client
+        .v1_sync(V1SyncRequest {
+            workspace_id: workspace_id.clone(),
+            dry_run: false,
+        })
+        .unwrap();
+    let V1SyncResponse::Ok { delete_files, .. } = sync_res_2 else {
+        panic!("unexpected response");
+    };
+    assert_eq!(delete_files, vec![vec!["a.txt".to_string()]]);
+}
+
+#[test]
+#[serial]
+fn test_12() {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let tempdir1 = tempdir().unwrap();
+    let fixed_system_time =
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(40 * 365 * 24 * 60 * 60);
+    let port = 8099;
+    let config = ServerConfig {
+        base_path: tempdir1.as_ref().to_path_buf(),
+        is_main: false,
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port,
+    };
+
+    runtime.spawn(async {
+        sagitta_remote_server::api::run_server(config).await;
+    });
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    let client = SagittaApiClient::new(format!("http://localhost:{}", port));
+
+    // setup files
+    {
+        let workspace_id = client
+            .v2_create_workspace(V2CreateWorkspaceRequest {
+                name: "setup".to_string(),
+                branch_name: "trunk".to_string(),
+            })
+            .unwrap();
+        let workspace_id = match workspace_id {
+            V2CreateWorkspaceResponse::Ok { id } => id,
+            _ => panic!("unexpected response"),
+        };
+        let base_blob_id = client
+            .v2_write_blob(V2WriteBlobRequest {
+                data: b"Hello, world!\n".to_vec(),
+            })
+            .unwrap()
+            .blob_id;
+        client
+            .v2_sync_files_with_workspace(V2SyncFilesWithWorkspaceRequest {
+                workspace_id: workspace_id.clone(),
+                sync_id: None,
+                items: vec![V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
+                    file_path: vec!["base.txt".to_string()],
+                    blob_id: base_blob_id,
+                    permission: 0o644,
+                    xattrs: BTreeMap::new(),
+                    modified_at: None,
+                }],
+            })
+            .unwrap();
+        client
+            .v2_commit(V2CommitRequest {
+                workspace_id,
+                keep_workspace: false,
+                paths: None,
+            })
+            .unwrap();
+    }
+
+    let create_workspace1_res = client
+        .v2_create_workspace(V2CreateWorkspaceRequest {
+            name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
+        })
+        .unwrap();
+    let workspace_id = match create_workspace1_res {
+        V2CreateWorkspaceResponse::Ok { id } => id,
+        _ => panic!("unexpected response"),
+    };
+
+    let local_system_workspace_base_path = tempdir().unwrap().as_ref().to_path_buf();
+
+    let local_server_config = sagitta_local_server::api::ServerConfig {
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port: 8100,
+        local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+        remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
+    };
+    runtime.spawn(async {
+        sagitta_local_server::api::run_local_api_server(local_server_config).await;
+    });
+
+    let local_api_client =
+        sagitta_local_api_client::SagittaLocalApiClient::new(format!("http://localhost:{}", 8100));
+
+    let tempdir2 = tempdir().unwrap();
+    let tempdir2_str = tempdir2.as_ref().to_str().unwrap().to_string();
+    {
+        let local_system_workspace_base_path = local_system_workspace_base_path.clone();
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        std::thread::spawn(move || {
+            let config = SagittaConfig {
+                base_url: format!("http://localhost:{}", port),
+                mountpoint: tempdir2_str,
+                uid,
+                gid,
+                clock: Clock::new_with_fixed_time(fixed_system_time),
+                local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+                debug_sleep_duration: None,
+            };
+            run_fs(config);
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+
+    let path_out1 = tempdir2.path().join("workspace1");
+    // a handle keeps reading the file it opened after the path is unlinked
+    let out1 = Command::new("bash")
+        .arg("-c")
+        .arg("echo 'Hello!' > a.txt && exec 3< a.txt && rm a.txt && cat <&3")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+    assert_eq!(out1.stdout, b"Hello!\n");
+
+    // and writes go to the renamed file
+    let out2 = Command::new("bash")
+        .arg("-c")
+        .arg(
+            "exec 3>> b.txt && mv b.txt c.txt && echo 'World!' >&3 && exec 3>&- && cat c.txt && ls",
+        )
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+    assert_eq!(out2.stdout, b"World!\nbase.txt\nc.txt\n");
+
+    // a file read from the base commit is pinned too
+    let out3 = Command::new("bash")
+        .arg("-c")
+        .arg("exec 3< base.txt && rm base.txt && cat <&3 && ls")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+    assert_eq!(out3.stdout, b"Hello, world!\nc.txt\n");
+
+    let sync_res = local_api_client
+        .v1_sync(V1SyncRequest {
+            workspace_id: workspace_id.clone(),
+            dry_run: false,
+        })
+        .unwrap();
+    let V1SyncResponse::Ok {
+        upsert_files,
+        delete_files,
+        ..
+    } = sync_res
+    else {
+        panic!("unexpected response");
+    };
+    assert_eq!(upsert_files, vec![vec!["c.txt".to_string()]]);
+    assert_eq!(delete_files, vec![vec!["base.txt".to_string()]]);
+}
+
+#[test]
+#[serial]
+fn test_13() {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let tempdir1 = tempdir().unwrap();
+    let fixed_system_time =
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(40 * 365 * 24 * 60 * 60);
+    let port = 8101;
+    let config = ServerConfig {
+        base_path: tempdir1.as_ref().to_path_buf(),
+        is_main: false,
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port,
+    };
+
+    runtime.spawn(async {
+        sagitta_remote_server::api::run_server(config).await;
+    });
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    let client = SagittaApiClient::new(format!("http://localhost:{}", port));
+
+    // setup files
+    {
+        let workspace_id = client
+            .v2_create_workspace(V2CreateWorkspaceRequest {
+                name: "setup".to_string(),
+                branch_name: "trunk".to_string(),
+            })
+            .unwrap();
+        let workspace_id = match workspace_id {
+            V2CreateWorkspaceResponse::Ok { id } => id,
+            _ => panic!("unexpected response"),
+        };
+        let base_blob_id = client
+            .v2_write_blob(V2WriteBlobRequest {
+                data: b"Hello, world!\n".to_vec(),
+            })
+            .unwrap()
+            .blob_id;
+        client
+            .v2_sync_files_with_workspace(V2SyncFilesWithWorkspaceRequest {
+                workspace_id: workspace_id.clone(),
+                sync_id: None,
+                items: vec![V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
+                    file_path: vec!["base.txt".to_string()],
+                    blob_id: base_blob_id,
+                    permission: 0o644,
+                    xattrs: BTreeMap::new(),
+                    modified_at: None,
+                }],
+            })
+            .unwrap();
+        client
+            .v2_commit(V2CommitRequest {
+                workspace_id,
+                keep_workspace: false,
+                paths: None,
+            })
+            .unwrap();
+    }
+
+    let create_workspace1_res = client
+        .v2_create_workspace(V2CreateWorkspaceRequest {
+            name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
+        })
+        .unwrap();
+    let workspace_id = match create_workspace1_res {
+        V2CreateWorkspaceResponse::Ok { id } => id,
+        _ => panic!("unexpected response"),
+    };
+
+    let local_system_workspace_base_path = tempdir().unwrap().as_ref().to_path_buf();
+
+    let local_server_config = sagitta_local_server::api::ServerConfig {
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port: 8102,
+        local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+        remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
+    };
+    runtime.spawn(async {
+        sagitta_local_server::api::run_local_api_server(local_server_config).await;
+    });
+
+    let local_api_client =
+        sagitta_local_api_client::SagittaLocalApiClient::new(format!("http://localhost:{}", 8102));
+
+    let tempdir2 = tempdir().unwrap();
+    let tempdir2_str = tempdir2.as_ref().to_str().unwrap().to_string();
+    {
+        let local_system_workspace_base_path = local_system_workspace_base_path.clone();
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        std::thread::spawn(move || {
+            let config = SagittaConfig {
+                base_url: format!("http://localhost:{}", port),
+                mountpoint: tempdir2_str,
+                uid,
+                gid,
+                clock: Clock::new_with_fixed_time(fixed_system_time),
+                local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+                debug_sleep_duration: None,
+            };
+            run_fs(config);
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+
+    let path_out1 = tempdir2.path().join("workspace1");
+    let src_path = path_out1.join("src.txt");
+    std::fs::write(&src_path, b"Hello!\n").unwrap();
+    let src = std::fs::File::open(&src_path).unwrap();
+    let dst = std::fs::File::create(path_out1.join("dst.txt")).unwrap();
+    let copied = unsafe {
+        libc::copy_file_range(
+            src.as_raw_fd(),
+            std::ptr::null_mut(),
+            dst.as_raw_fd(),
+            std::ptr::null_mut(),
+            7,
+            0,
+        )
+    };
+    assert_eq!(copied, 7);
+    drop(dst);
+    assert_eq!(
+        std::fs::read(path_out1.join("dst.txt")).unwrap(),
+        b"Hello!\n"
+    );
+
+    // the only data of a small file ends where the file does
+    let data = unsafe { libc::lseek(src.as_raw_fd(), 0, libc::SEEK_DATA) };
+    let hole = unsafe { libc::lseek(src.as_raw_fd(), 0, libc::SEEK_HOLE) };
+    assert_eq!((data, hole), (0, 7));
+    drop(src);
+
+    let big = std::fs::File::create(path_out1.join("big.bin")).unwrap();
+    let res = unsafe { libc::fallocate(big.as_raw_fd(), 0, 0, 8192) };
+    assert_eq!(res, 0);
+    assert_eq!(big.metadata().unwrap().len(), 8192);
+    drop(big);
+
+    // a whole file of the base commit is copied as a reference to its blob
+    let base = std::fs::File::open(path_out1.join("base.txt")).unwrap();
+    let base_copy = std::fs::File::create(path_out1.join("base_copy.txt")).unwrap();
+    let copied = unsafe {
+        libc::copy_file_range(
+            base.as_raw_fd(),
+            std::ptr::null_mut(),
+            base_copy.as_raw_fd(),
+            std::ptr::null_mut(),
+            1024,
+            0,
+        )
+    };
+    assert_eq!(copied, 14);
+    drop(base_copy);
+    assert_eq!(
+        std::fs::read(path_out1.join("base_copy.txt")).unwrap(),
+        b"Hello, world!\n"
+    );
+
+    let sync_res = local_api_client
+        .v1_sync(V1SyncRequest {
+            workspace_id: workspace_id.clone(),
+            dry_run: false,
+        })
+        .unwrap();
+    let V1SyncResponse::Ok {
+        upsert_files,
+        reference_files,
+        ..
+    } = sync_res
+    else {
+        panic!("unexpected response");
+    };
+    assert_eq!(
+        upsert_files,
+        vec![
+            vec!["big.bin".to_string()],
+            vec!["dst.txt".to_string()],
+            vec!["src.txt".to_string()],
+        ]
+    );
+    assert_eq!(reference_files, vec![vec!["base_copy.txt".to_string()]]);
+}
+
+#[test]
+#[serial]
+fn test_14() {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let tempdir1 = tempdir().unwrap();
+    let fixed_system_time =
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(40 * 365 * 24 * 60 * 60);
+    let port = 8103;
+    let config = ServerConfig {
+        base_path: tempdir1.as_ref().to_path_buf(),
+        is_main: false,
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port,
+    };
+
+    runtime.spawn(async {
+        sagitta_remote_server::api::run_server(config).await;
+    });
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    let client = SagittaApiClient::new(format!("http://localhost:{}", port));
+
+    // setup files
+    {
+        let workspace_id = client
+            .v2_create_workspace(V2CreateWorkspaceRequest {
+                name: "setup".to_string(),
+                branch_name: "trunk".to_string(),
+            })
+            .unwrap();
+        let workspace_id = match workspace_id {
+            V2CreateWorkspaceResponse::Ok { id } => id,
+            _ => panic!("unexpected response"),
+        };
+        let base_blob_id = client
+            .v2_write_blob(V2WriteBlobRequest {
+                data: b"Hello, world!\n".to_vec(),
+            })
+            .unwrap()
+            .blob_id;
+        client
+            .v2_sync_files_with_workspace(V2SyncFilesWithWorkspaceRequest {
+                workspace_id: workspace_id.clone(),
+                sync_id: None,
+                items: vec![V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
+                    file_path: vec!["base.txt".to_string()],
+                    blob_id: base_blob_id,
+                    permission: 0o644,
+                    xattrs: BTreeMap::new(),
+                    modified_at: None,
+                }],
+            })
+            .unwrap();
+        client
+            .v2_commit(V2CommitRequest {
+                workspace_id,
+                keep_workspace: false,
+                paths: None,
+            })
+            .unwrap();
+    }
+
+    let create_workspace1_res = client
+        .v2_create_workspace(V2CreateWorkspaceRequest {
+            name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
+        })
+        .unwrap();
+    let workspace_id = match create_workspace1_res {
+        V2CreateWorkspaceResponse::Ok { id } => id,
+        _ => panic!("unexpected response"),
+    };
+
+    let local_system_workspace_base_path = tempdir().unwrap().as_ref().to_path_buf();
+
+    let local_server_config = sagitta_local_server::api::ServerConfig {
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port: 8104,
+        local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+        remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
+    };
+    runtime.spawn(async {
+        sagitta_local_server::api::run_local_api_server(local_server_config).await;
+    });
+
+    let local_api_client =
+        sagitta_local_api_client::SagittaLocalApiClient::new(format!("http://localhost:{}", 8104));
+
+    let tempdir2 = tempdir().unwrap();
+    let tempdir2_str = tempdir2.as_ref().to_str().unwrap().to_string();
+    {
+        let local_system_workspace_base_path = local_system_workspace_base_path.clone();
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        std::thread::spawn(move || {
+            let config = SagittaConfig {
+                base_url: format!("http://localhost:{}", port),
+                mountpoint: tempdir2_str,
+                uid,
+                gid,
+                clock: Clock::new_with_fixed_time(fixed_system_time),
+                local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+                debug_sleep_duration: None,
+            };
+            run_fs(config);
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+
+    let path_out1 = tempdir2.path().join("workspace1");
+    // chmod and rename leave the file unmodified, so it is read through its blob
+    let out1 = Command::new("bash")
+        .arg("-c")
+        .arg("chmod 600 base.txt && mv base.txt moved.txt && cat moved.txt && stat -c %a moved.txt && ls")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+    assert_eq!(out1.stdout, b"Hello, world!\n600\nmoved.txt\n");
+
+    let sync_res = local_api_client
+        .v1_sync(V1SyncRequest {
+            workspace_id: workspace_id.clone(),
+            dry_run: false,
+        })
+        .unwrap();
+    let V1SyncResponse::Ok {
+        upsert_files,
+        reference_files,
+        rename_files,
+        ..
+    } = sync_res
+    else {
+        panic!("unexpected response");
+    };
+    assert!(upsert_files.is_empty());
+    assert_eq!(reference_files, vec![vec!["moved.txt".to_string()]]);
+    assert_eq!(
+        rename_files,
+        vec![(vec!["base.txt".to_string()], vec!["moved.txt".to_string()])]
+    );
+
+    let out2 = Command::new("bash")
+        .arg("-c")
+        .arg("cat moved.txt && stat -c %a moved.txt")
+        .current_dir(&path_out1)
+        .output()
+        .expect("failed to execute process");
+    assert_eq!(out2.stdout, b"Hello, world!\n600\n");
+}
+
+#[test]
+#[serial]
+fn test_15() {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let tempdir1 = tempdir().unwrap();
+    let fixed_system_time =
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(40 * 365 * 24 * 60 * 60);
+    let port = 8105;
+    let config = ServerConfig {
+        base_path: tempdir1.as_ref().to_path_buf(),
+        is_main: false,
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port,
+    };
+
+    runtime.spawn(async {
+        sagitta_remote_server::api::run_server(config).await;
+    });
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    let client = SagittaApiClient::new(format!("http://localhost:{}", port));
+    let create_workspace1_res = client
+        .v2_create_workspace(V2CreateWorkspaceRequest {
+            name: "workspace1".to_string(),
+            branch_name: "trunk".to_string(),
+        })
+        .unwrap();
+    let workspace_id = match create_workspace1_res {
+        V2CreateWorkspaceResponse::Ok { id } => id,
+        _ => panic!("unexpected response"),
+    };
+
+    let local_system_workspace_base_path = tempdir().unwrap().as_ref().to_path_buf();
+
+    let local_server_config = sagitta_local_server::api::ServerConfig {
+        clock: Clock::new_with_fixed_time(fixed_system_time),
+        port: 8106,
+        local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+        remote_api_base_url: format!("http://localhost:{}", port),
+        upload_concurrency: 4,
+        auto_sync: None,
+    };
+    runtime.spawn(async {
+        sagitta_local_server::api::run_local_api_server(local_server_config).await;
+    });
+
+    let local_api_client =
+        sagitta_local_api_client::SagittaLocalApiClient::new(format!("http://localhost:{}", 8106));
+
+    let tempdir2 = tempdir().unwrap();
+    let tempdir2_str = tempdir2.as_ref().to_str().unwrap().to_string();
+    {
+        let local_system_workspace_base_path = local_system_workspace_base_path.clone();
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        std::thread::spawn(move || {
+            let config = SagittaConfig {
+                base_url: format!("http://localhost:{}", port),
+                mountpoint: tempdir2_str,
+                uid,
+                gid,
+                clock: Clock::new_with_fixed_time(fixed_system_time),
+                local_system_workspace_base_path: local_system_workspace_base_path.clone(),
+                debug_sleep_duration: None,
+            };
+            run_fs(config);
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+
+    let path_out1 = tempdir2.path().join("workspace1");
+    let sizes: Vec<usize> = (1..=8).map(|i| i * 1000).collect();
+    for (i, size) in sizes.iter().enumerate() {
+        let data = vec![b'a' + i as u8; *size];
+        std::fs::write(path_out1.join(format!("file{}.txt", i)), data).unwrap();
+    }
+
+    // the uploads run concurrently, and each one is streamed back as a progress line
+    let mut progress_events = vec![];
+    let sync_res = local_api_client
+        .v1_sync_with_progress(
+            V1SyncRequest {
+                workspace_id: workspace_id.clone(),
+                dry_run: false,
+            },
+            |progress| progress_events.push(progress.clone()),
+        )
+        .unwrap();
+    let V1SyncResponse::Ok { upsert_files, .. } = sync_res else {
+        panic!("unexpected response");
+    };
+    assert_eq!(upsert_files.len(), sizes.len());
+
+    let bytes_total: u64 = sizes.iter().map(|size| *size as u64).sum();
+    let first = progress_events.first().unwrap();
+    assert_eq!((first.files_done, first.bytes_done), (0, 0));
+    for progress in &progress_events {
+        assert_eq!(progress.files_total, sizes.len() as u64);
+        assert_eq!(progress.bytes_total, bytes_total);
+    }
+    assert!(progress_events
+        .windows(2)
+        .all(|w| w[0].files_done <= w[1].files_done && w[0].bytes_done <= w[1].bytes_done));
+    let last = progress_events.last().unwrap();
+    assert_eq!(
+        (last.files_done, last.bytes_done),
+        (sizes.len() as u64, bytes_total)
+    );
+
+    let commit_res = client
+        .v2_commit(V2CommitRequest {
+            workspace_id,
+            keep_workspace: false,
+            paths: None,
+        })
+        .unwrap();
+    assert!(matches!(commit_res, V2CommitResponse::Ok { .. }));
+    for (i, size) in sizes.iter().enumerate() {
+        let data =
+            std::fs::read(tempdir2.path().join("trunk").join(format!("file{}.txt", i))).unwrap();
+        assert_eq!(data, vec![b'a' + i as u8; *size]);
+    }
+}
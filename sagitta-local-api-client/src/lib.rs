@@ -1,4 +1,10 @@
-use sagitta_local_api_schema::v1::sync::{V1SyncRequest, V1SyncResponse};
+use std::io::{BufRead, BufReader};
+
+use sagitta_local_api_schema::v1::{
+    rebase::{V1RebaseRequest, V1RebaseResponse},
+    sync::{V1SyncEvent, V1SyncProgress, V1SyncRequest, V1SyncResponse},
+    sync_status::{V1SyncStatusRequest, V1SyncStatusResponse},
+};
 
 #[derive(Debug, Clone)]
 pub struct SagittaLocalApiClient {
@@ -9,6 +15,7 @@ pub struct SagittaLocalApiClient {
 pub enum SagittaLocalApiClientError {
     Ureq(Box<ureq::Error>),
     IO(Box<std::io::Error>),
+    Json(Box<serde_json::Error>),
 }
 
 impl SagittaLocalApiClient {
@@ -23,13 +30,58 @@ impl SagittaLocalApiClient {
     pub fn v1_sync(
         &self,
         request: V1SyncRequest,
+    ) -> Result<V1SyncResponse, SagittaLocalApiClientError> {
+        self.v1_sync_with_progress(request, |_| {})
+    }
+
+    /// Syncs a workspace, calling `on_progress` as its blobs are uploaded.
+    pub fn v1_sync_with_progress(
+        &self,
+        request: V1SyncRequest,
+        mut on_progress: impl FnMut(&V1SyncProgress),
     ) -> Result<V1SyncResponse, SagittaLocalApiClientError> {
         let url = format!("{}/v1/sync", self.base_url);
-        let sync_res: V1SyncResponse = ureq::post(&url)
+        let reader = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaLocalApiClientError::Ureq(Box::new(e)))?
+            .into_reader();
+        for line in BufReader::new(reader).lines() {
+            let line = line.map_err(|e| SagittaLocalApiClientError::IO(Box::new(e)))?;
+            let event: V1SyncEvent = serde_json::from_str(&line)
+                .map_err(|e| SagittaLocalApiClientError::Json(Box::new(e)))?;
+            match event {
+                V1SyncEvent::Progress(progress) => on_progress(&progress),
+                V1SyncEvent::Done(sync_res) => return Ok(sync_res),
+            }
+        }
+        Err(SagittaLocalApiClientError::IO(Box::new(
+            std::io::ErrorKind::UnexpectedEof.into(),
+        )))
+    }
+
+    pub fn v1_rebase(
+        &self,
+        request: V1RebaseRequest,
+    ) -> Result<V1RebaseResponse, SagittaLocalApiClientError> {
+        let url = format!("{}/v1/rebase", self.base_url);
+        let rebase_res: V1RebaseResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaLocalApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaLocalApiClientError::IO(Box::new(e)))?;
+        Ok(rebase_res)
+    }
+
+    pub fn v1_sync_status(
+        &self,
+        request: V1SyncStatusRequest,
+    ) -> Result<V1SyncStatusResponse, SagittaLocalApiClientError> {
+        let url = format!("{}/v1/sync-status", self.base_url);
+        let sync_status_res: V1SyncStatusResponse = ureq::post(&url)
             .send_json(request)
             .map_err(|e| SagittaLocalApiClientError::Ureq(Box::new(e)))?
             .into_json()
             .map_err(|e| SagittaLocalApiClientError::IO(Box::new(e)))?;
-        Ok(sync_res)
+        Ok(sync_status_res)
     }
 }
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V1RebaseRequest {
+    pub workspace_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V1RebaseResponse {
+    Ok {
+        /// Cow dirs removed because the branch now has them.
+        removed_dirs: Vec<Vec<String>>,
+    },
+    Err {
+        message: String,
+    },
+}
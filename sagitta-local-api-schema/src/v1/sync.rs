@@ -3,13 +3,47 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct V1SyncRequest {
     pub workspace_id: String,
+    /// Only collects the changes, without uploading or changing anything.
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum V1SyncResponse {
     Ok {
+        /// Files whose content is uploaded.
         upsert_files: Vec<Vec<String>>,
+        upsert_dirs: Vec<Vec<String>>,
+        upsert_symlinks: Vec<Vec<String>>,
+        /// Files synced as a reference to a blob they don't upload: unmodified files, and
+        /// the other links of a hard linked file.
+        reference_files: Vec<Vec<String>>,
         delete_files: Vec<Vec<String>>,
+        /// `(from, to)` pairs of renamed paths.
+        rename_files: Vec<(Vec<String>, Vec<String>)>,
+        /// Deleted paths with nothing to delete on the server: never synced, or removed
+        /// along with a deleted or renamed dir.
+        skipped_files: Vec<Vec<String>>,
+        /// Changed paths left out by the ignores of a `.sagitta.toml`.
+        ignored_files: Vec<Vec<String>>,
     },
-    Err,
+    Err {
+        message: String,
+    },
+}
+
+/// Progress of the blob uploads of a sync.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V1SyncProgress {
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+/// One line of the newline-delimited JSON that `/v1/sync` streams back: progress while
+/// blobs are uploaded, then the result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V1SyncEvent {
+    Progress(V1SyncProgress),
+    Done(V1SyncResponse),
 }
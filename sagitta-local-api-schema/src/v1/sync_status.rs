@@ -0,0 +1,24 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V1SyncStatusRequest {
+    pub workspace_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V1SyncStatusResponse {
+    Ok {
+        last_synced_at: Option<SystemTime>,
+        /// Number of operations recorded in the journal since the last sync.
+        pending_changes: u64,
+        /// Error of the last sync, `None` if it succeeded.
+        last_error: Option<String>,
+    },
+    /// The workspace does not exist on the server.
+    NotFound,
+    Err {
+        message: String,
+    },
+}
@@ -3,9 +3,12 @@ use std::path::PathBuf;
 use actix_web::{web, App, HttpServer};
 use sagitta_common::clock::Clock;
 
-use crate::api_state::ApiState;
+use crate::{
+    api_state::ApiState,
+    auto_sync::{run_auto_sync, AutoSyncConfig},
+};
 
-use self::v1::sync::v1_sync;
+use self::v1::{rebase::v1_rebase, sync::v1_sync, sync_status::v1_sync_status};
 
 pub mod v1;
 
@@ -14,6 +17,10 @@ pub struct ServerConfig {
     pub port: u16,
     pub local_system_workspace_base_path: PathBuf,
     pub remote_api_base_url: String,
+    /// Number of blobs a sync uploads at once.
+    pub upload_concurrency: usize,
+    /// Syncs workspaces in the background when set.
+    pub auto_sync: Option<AutoSyncConfig>,
 }
 
 pub async fn run_local_api_server(config: ServerConfig) {
@@ -21,13 +28,21 @@ pub async fn run_local_api_server(config: ServerConfig) {
         config.clock.clone(),
         config.local_system_workspace_base_path.clone(),
         &config.remote_api_base_url,
+        config.upload_concurrency,
     )
     .await;
 
+    if let Some(auto_sync) = config.auto_sync {
+        let state = state.clone();
+        std::thread::spawn(move || run_auto_sync(state, auto_sync));
+    }
+
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(state.clone()))
+            .service(v1_rebase)
             .service(v1_sync)
+            .service(v1_sync_status)
     })
     .bind(("0.0.0.0", config.port))
     .unwrap()
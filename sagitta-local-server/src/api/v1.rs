@@ -1 +1,3 @@
+pub mod rebase;
 pub mod sync;
+pub mod sync_status;
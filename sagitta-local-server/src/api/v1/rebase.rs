@@ -0,0 +1,87 @@
+use actix_web::{post, web, Responder};
+use log::error;
+use sagitta_local_api_schema::v1::rebase::{V1RebaseRequest, V1RebaseResponse};
+use sagitta_remote_api_schema::v2::get_attr::{V2GetAttrRequest, V2GetAttrResponse};
+
+use crate::api_state::ApiState;
+
+use super::sync::{journal_paths, recover_pending_sync, SyncError};
+
+#[post("/v1/rebase")]
+pub async fn v1_rebase(
+    state: web::Data<ApiState>,
+    req: web::Json<V1RebaseRequest>,
+) -> impl Responder {
+    let workspace_id = req.workspace_id.clone();
+    let res = web::block(move || rebase_workspace(&state, &workspace_id)).await;
+    let res = match res {
+        Ok(Ok(removed_dirs)) => V1RebaseResponse::Ok { removed_dirs },
+        Ok(Err(e)) => {
+            error!("failed to rebase {}: {:?}", req.workspace_id, e);
+            V1RebaseResponse::Err {
+                message: format!("{:?}", e),
+            }
+        }
+        Err(e) => V1RebaseResponse::Err {
+            message: e.to_string(),
+        },
+    };
+    web::Json(res)
+}
+
+/// Drops the local state of a workspace that a commit made redundant. Synced files are
+/// archived by the sync itself, but their dirs stay in the cow dir; the ones the workspace
+/// now reads from the branch, holding no pending change, are removed. Returns their paths.
+pub fn rebase_workspace(
+    state: &ApiState,
+    workspace_id: &str,
+) -> Result<Vec<Vec<String>>, SyncError> {
+    let sync_lock = state.sync_lock(workspace_id);
+    let _guard = sync_lock
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    recover_pending_sync(state, workspace_id)?;
+
+    let index_entries = state
+        .local_system_workspace
+        .list_index_entries(workspace_id)?;
+    let journal = state
+        .local_system_workspace
+        .get_journal_changes(workspace_id)?;
+    let pending_paths = journal_paths(journal.entries.iter());
+
+    let mut removed_dirs = vec![];
+    // children first, so that a dir emptied by removing its subdirs goes too
+    for path in state
+        .local_system_workspace
+        .list_cow_dirs(workspace_id)?
+        .into_iter()
+        .rev()
+    {
+        if index_entries
+            .iter()
+            .any(|entry| entry.path.starts_with(&path))
+            || pending_paths
+                .iter()
+                .any(|pending| pending.starts_with(&path))
+        {
+            continue;
+        }
+        let attr = state.remote_api_client.v2_get_attr(V2GetAttrRequest {
+            path: path.clone(),
+            workspace_id: Some(workspace_id.to_string()),
+            branch_name: None,
+            commit_rank: None,
+        })?;
+        if !matches!(attr, V2GetAttrResponse::Found { is_dir: true, .. }) {
+            continue;
+        }
+        if state
+            .local_system_workspace
+            .remove_empty_cow_dir(workspace_id, &path)?
+        {
+            removed_dirs.push(path);
+        }
+    }
+    Ok(removed_dirs)
+}
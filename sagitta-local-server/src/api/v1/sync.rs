@@ -1,12 +1,31 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    task::{Context, Poll},
+    time::SystemTime,
+};
 
-use actix_web::{post, web, Responder};
-use log::debug;
+use actix_web::{
+    body::{BodySize, MessageBody},
+    post,
+    web::{self, Bytes},
+    HttpResponse, Responder,
+};
+use log::{debug, error, info};
 use sagitta_config_file::SagittaConfigToml;
-use sagitta_local_api_schema::v1::sync::{V1SyncRequest, V1SyncResponse};
+use sagitta_local_api_schema::v1::sync::{
+    V1SyncEvent, V1SyncProgress, V1SyncRequest, V1SyncResponse,
+};
+use sagitta_local_system_workspace::{IndexEntry, IndexState, JournalEntry, PendingSync};
+use sagitta_remote_api_client::SagittaApiClientError;
 use sagitta_remote_api_schema::v2::{
     get_attr::{V2GetAttrRequest, V2GetAttrResponse},
     get_file_blob_id::{V2GetFileBlobIdRequest, V2GetFileBlobIdResponse},
+    get_sync_state::{V2GetSyncStateRequest, V2GetSyncStateResponse},
     read_blob::{V2ReadBlobRequest, V2ReadBlobResponse},
     sync_files_with_workspace::{
         V2SyncFilesWithWorkspaceRequest, V2SyncFilesWithWorkspaceRequestItem,
@@ -14,26 +33,420 @@ use sagitta_remote_api_schema::v2::{
     write_blob::V2WriteBlobRequest,
 };
 
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
 use crate::api_state::ApiState;
 
 #[post("/v1/sync")]
 pub async fn v1_sync(state: web::Data<ApiState>, req: web::Json<V1SyncRequest>) -> impl Responder {
-    let workspace_id = req.workspace_id.clone();
+    // the sync blocks on the file system and the server, so it runs off the workers and
+    // streams its events back as they come
+    let (tx, rx) = unbounded_channel();
+    actix_web::rt::task::spawn_blocking(move || {
+        let send = |event: &V1SyncEvent| {
+            let mut line = serde_json::to_vec(event).unwrap();
+            line.push(b'\n');
+            // the client may have gone away; the sync is finished regardless
+            let _ = tx.send(Bytes::from(line));
+        };
+        let on_progress = |progress: &V1SyncProgress| {
+            send(&V1SyncEvent::Progress(progress.clone()));
+        };
+        let res = match sync_workspace(&state, &req.workspace_id, req.dry_run, &on_progress) {
+            Ok(res) => res,
+            Err(e) => {
+                error!("failed to sync {}: {:?}", req.workspace_id, e);
+                V1SyncResponse::Err {
+                    message: format!("{:?}", e),
+                }
+            }
+        };
+        send(&V1SyncEvent::Done(res));
+    });
+    HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .body(LinesBody { rx })
+}
+
+/// A response body made of the lines sent from a blocking task, ending when it is done.
+struct LinesBody {
+    rx: UnboundedReceiver<Bytes>,
+}
+
+impl MessageBody for LinesBody {
+    type Error = std::convert::Infallible;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        self.rx.poll_recv(cx).map(|line| line.map(Ok))
+    }
+}
+
+#[derive(Debug)]
+pub enum SyncError {
+    Local(sagitta_local_system_workspace::Error),
+    Remote(SagittaApiClientError),
+    /// A `.sagitta.toml` that could not be parsed.
+    InvalidConfig(Vec<String>),
+}
 
+impl From<sagitta_local_system_workspace::Error> for SyncError {
+    fn from(e: sagitta_local_system_workspace::Error) -> Self {
+        SyncError::Local(e)
+    }
+}
+
+impl From<SagittaApiClientError> for SyncError {
+    fn from(e: SagittaApiClientError) -> Self {
+        SyncError::Remote(e)
+    }
+}
+
+/// Syncs the changes of a workspace to the server and records the outcome in the sync
+/// status of the workspace. A dry run only reports what a sync would do.
+pub fn sync_workspace(
+    state: &ApiState,
+    workspace_id: &str,
+    dry_run: bool,
+    on_progress: &(dyn Fn(&V1SyncProgress) + Sync),
+) -> Result<V1SyncResponse, SyncError> {
+    // a manual sync and an automatic one must not upload the same changes twice. The lock
+    // guards no data, so a sync that panicked does not poison it for the next ones.
+    let sync_lock = state.sync_lock(workspace_id);
+    let _guard = sync_lock
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let res = sync_workspace_inner(state, workspace_id, dry_run, on_progress);
+    if dry_run {
+        return res;
+    }
+    let mut sync_status = state.sync_status.lock().unwrap();
+    let status = sync_status.entry(workspace_id.to_string()).or_default();
+    match &res {
+        Ok(_) => {
+            status.last_synced_at = Some(state.clock.now());
+            status.last_error = None;
+        }
+        Err(e) => {
+            status.last_error = Some(format!("{:?}", e));
+        }
+    }
+    res
+}
+
+/// Resumes or rolls back a sync that was interrupted before it was finished. Nothing local
+/// changes until the server has applied a sync, so one the server never applied is just
+/// forgotten and its changes are collected again.
+pub(crate) fn recover_pending_sync(state: &ApiState, workspace_id: &str) -> Result<(), SyncError> {
+    let Some(pending_sync) = state
+        .local_system_workspace
+        .get_pending_sync(workspace_id)?
+    else {
+        return Ok(());
+    };
+    let sync_state = state
+        .remote_api_client
+        .v2_get_sync_state(V2GetSyncStateRequest {
+            workspace_id: workspace_id.to_string(),
+            sync_id: pending_sync.sync_id.clone(),
+        })?;
+    match sync_state {
+        V2GetSyncStateResponse::Applied => {
+            info!("resuming sync {} of {}", pending_sync.sync_id, workspace_id);
+            finish_sync(state, workspace_id, &pending_sync)
+        }
+        V2GetSyncStateResponse::NotApplied => {
+            info!(
+                "rolling back sync {} of {}",
+                pending_sync.sync_id, workspace_id
+            );
+            state
+                .local_system_workspace
+                .discard_pending_sync(workspace_id)?;
+            Ok(())
+        }
+    }
+}
+
+/// Archives the cow files of a sync the server has applied and acknowledges the journal.
+/// Paths changed again after the changes were collected keep their cow files for the next
+/// sync.
+fn finish_sync(
+    state: &ApiState,
+    workspace_id: &str,
+    pending_sync: &PendingSync,
+) -> Result<(), SyncError> {
+    let journal = state
+        .local_system_workspace
+        .get_journal_changes(workspace_id)?;
+    let changed_paths = journal_paths(
+        journal
+            .entries
+            .iter()
+            .filter(|entry| entry.seq > pending_sync.seq),
+    );
+    let archived_paths = pending_sync
+        .archived_paths
+        .iter()
+        .filter(|path| {
+            !changed_paths
+                .iter()
+                .any(|changed| path.starts_with(changed))
+        })
+        .cloned()
+        .collect();
+    state.local_system_workspace.archive_cow_dir(
+        workspace_id,
+        &pending_sync.sync_id,
+        &archived_paths,
+    )?;
+    state
+        .local_system_workspace
+        .acknowledge_journal(workspace_id, pending_sync.seq)?;
+    Ok(())
+}
+
+/// The paths the journal entries touched, with both ends of a rename.
+pub(crate) fn journal_paths<'a>(
+    entries: impl Iterator<Item = &'a JournalEntry>,
+) -> Vec<Vec<String>> {
+    entries
+        .flat_map(|entry| std::iter::once(entry.path.clone()).chain(entry.to_path.clone()))
+        .collect()
+}
+
+/// A sync id that sorts by time, so archive dirs named after it do too.
+fn new_sync_id(state: &ApiState) -> String {
+    let secs = state
+        .clock
+        .now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    format!("{}-{:016x}", secs, rand::random::<u64>())
+}
+
+fn parse_config(config_path: &[String], file: &[u8]) -> Result<SagittaConfigToml, SyncError> {
+    std::str::from_utf8(file)
+        .ok()
+        .and_then(|toml| toml::from_str(toml).ok())
+        .ok_or_else(|| SyncError::InvalidConfig(config_path.to_vec()))
+}
+
+/// A cow file whose contents are uploaded as a blob.
+struct Upload {
+    path: Vec<String>,
+    size: u64,
+}
+
+/// Uploads the blobs of `uploads` on up to `upload_concurrency` threads and returns their
+/// blob ids in the same order.
+fn upload_blobs(
+    state: &ApiState,
+    workspace_id: &str,
+    uploads: &[Upload],
+    on_progress: &(dyn Fn(&V1SyncProgress) + Sync),
+) -> Result<Vec<String>, SyncError> {
+    let progress = Mutex::new(V1SyncProgress {
+        files_done: 0,
+        files_total: uploads.len() as u64,
+        bytes_done: 0,
+        bytes_total: uploads.iter().map(|upload| upload.size).sum(),
+    });
+    on_progress(&progress.lock().unwrap());
+
+    let next = AtomicUsize::new(0);
+    let blob_ids = Mutex::new(vec![None; uploads.len()]);
+    let first_error = Mutex::new(None);
+    let upload_blob = |upload: &Upload| -> Result<String, SyncError> {
+        let file = state.local_system_workspace.read_cow_file(
+            workspace_id,
+            &upload.path,
+            0,
+            4_000_000_000,
+        )?;
+        let res = state
+            .remote_api_client
+            .v2_write_blob(V2WriteBlobRequest { data: file })?;
+        Ok(res.blob_id)
+    };
+    std::thread::scope(|scope| {
+        for _ in 0..state.upload_concurrency.clamp(1, uploads.len().max(1)) {
+            scope.spawn(|| {
+                while first_error.lock().unwrap().is_none() {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(upload) = uploads.get(index) else {
+                        break;
+                    };
+                    match upload_blob(upload) {
+                        Ok(blob_id) => {
+                            blob_ids.lock().unwrap()[index] = Some(blob_id);
+                            let mut progress = progress.lock().unwrap();
+                            progress.files_done += 1;
+                            progress.bytes_done += upload.size;
+                            on_progress(&progress);
+                        }
+                        Err(e) => {
+                            first_error.lock().unwrap().get_or_insert(e);
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    if let Some(e) = first_error.into_inner().unwrap() {
+        return Err(e);
+    }
+    Ok(blob_ids
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|blob_id| blob_id.unwrap())
+        .collect())
+}
+
+fn sync_workspace_inner(
+    state: &ApiState,
+    workspace_id: &str,
+    dry_run: bool,
+    on_progress: &(dyn Fn(&V1SyncProgress) + Sync),
+) -> Result<V1SyncResponse, SyncError> {
+    if !dry_run {
+        recover_pending_sync(state, workspace_id)?;
+    }
+
+    let sync_id = new_sync_id(state);
     let mut sync_request = V2SyncFilesWithWorkspaceRequest {
-        workspace_id: workspace_id.clone(),
+        workspace_id: workspace_id.to_string(),
+        sync_id: Some(sync_id.clone()),
         items: vec![],
     };
 
-    let paths = state
+    let index_entries = state
         .local_system_workspace
-        .list_cow_files(&req.workspace_id)
-        .unwrap();
+        .list_index_entries(workspace_id)?;
+
+    // the journal names the paths touched since the last sync. Without an acknowledged
+    // position, or when an ignore file changed, the whole cow dir is walked instead.
+    let journal = state
+        .local_system_workspace
+        .get_journal_changes(workspace_id)?;
+    let mut touched_paths = journal
+        .acknowledged_seq
+        .map(|_| journal_paths(journal.entries.iter()))
+        .filter(|touched_paths| {
+            !touched_paths
+                .iter()
+                .any(|path| path.last().is_some_and(|name| name == ".sagitta.toml"))
+        });
+    let mut paths = match &touched_paths {
+        Some(touched_paths) => state
+            .local_system_workspace
+            .list_cow_files_under(workspace_id, touched_paths)?,
+        None => state.local_system_workspace.list_cow_files(workspace_id)?,
+    };
+    // a write through one hard link changes the other links, which the journal does not name
+    if touched_paths.is_some()
+        && paths
+            .iter()
+            .map(|(path, _)| {
+                state
+                    .local_system_workspace
+                    .get_ino_and_nlink_of_cow_file(workspace_id, path)
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .any(|(_, nlink)| *nlink > 1)
+    {
+        touched_paths = None;
+        paths = state.local_system_workspace.list_cow_files(workspace_id)?;
+    }
+    // files that only refer to a blob have no cow file
+    for entry in &index_entries {
+        let blob_ref = state
+            .local_system_workspace
+            .get_cow_blob_ref(workspace_id, &entry.path)?;
+        if let Some(blob_ref) = blob_ref {
+            paths.push((entry.path.clone(), blob_ref.mode as i64));
+        }
+    }
+
+    // renames go first, deepest first, so that a path moved out of a renamed dir is
+    // taken from its old place before the rest of the dir follows
+    let mut rename_files = index_entries
+        .iter()
+        .filter(|entry| entry.state == IndexState::Renamed)
+        .filter_map(|entry| Some((entry.renamed_from.clone()?, entry.path.clone())))
+        .collect::<Vec<_>>();
+    rename_files.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    for (from, to) in &rename_files {
+        sync_request
+            .items
+            .push(V2SyncFilesWithWorkspaceRequestItem::Rename {
+                from: from.clone(),
+                to: to.clone(),
+            });
+    }
+
+    // new and changed dirs have an index entry with their mode; dirs of older versions
+    // that never reached the server have none. Dirs go first so that their files do not
+    // create them with the default mode.
+    let mut dir_paths = HashSet::new();
+    let mut dirs = vec![];
+    let cow_dirs = match &touched_paths {
+        Some(touched_paths) => state
+            .local_system_workspace
+            .list_cow_dirs_under(workspace_id, touched_paths)?,
+        None => state.local_system_workspace.list_cow_dirs(workspace_id)?,
+    };
+    for path in cow_dirs {
+        let entry = index_entries.iter().find(|entry| entry.path == path);
+        let mode = match entry {
+            Some(entry) if entry.state == IndexState::Deleted => continue,
+            Some(IndexEntry {
+                mode: Some(mode), ..
+            }) => *mode as i64,
+            _ => {
+                // a copy of a synced dir, or a dir moved along with a renamed one
+                if rename_files.iter().any(|(_, to)| path.starts_with(to)) {
+                    continue;
+                }
+                let attr = state.remote_api_client.v2_get_attr(V2GetAttrRequest {
+                    path: path.clone(),
+                    workspace_id: Some(workspace_id.to_string()),
+                    branch_name: None,
+                    commit_rank: None,
+                })?;
+                if let V2GetAttrResponse::Found { is_dir: true, .. } = attr {
+                    continue;
+                }
+                0o755
+            }
+        };
+        dir_paths.insert(path.clone());
+        dirs.push((path, mode));
+    }
+    let paths = dirs.into_iter().chain(paths).collect::<Vec<_>>();
 
     let mut config_cache = HashMap::new();
+    let mut uploads = vec![];
+    let mut linked_uploads: HashMap<u64, usize> = HashMap::new();
+    let mut uploaded_items = vec![];
 
     let mut upsert_files = vec![];
+    let mut upsert_dirs = vec![];
+    let mut upsert_symlinks = vec![];
+    let mut reference_files = vec![];
     let mut delete_files = vec![];
+    let mut skipped_files = vec![];
+    let mut ignored_files = vec![];
     for (path, mode) in &paths {
         // retrieve config files
         for i in 0..path.len() {
@@ -44,38 +457,40 @@ pub async fn v1_sync(state: web::Data<ApiState>, req: web::Json<V1SyncRequest>)
 
                 // read cow file
                 let file = state.local_system_workspace.read_cow_file(
-                    &req.workspace_id,
+                    workspace_id,
                     &config_path,
                     0,
                     4_000_000_000,
                 );
                 if let Ok(file) = file {
-                    let toml = std::str::from_utf8(&file).unwrap();
-                    let config: SagittaConfigToml = toml::from_str(toml).unwrap();
+                    let config = parse_config(&config_path, &file)?;
                     config_cache.insert(path.to_vec(), Some(config));
                     continue;
                 }
 
-                // read commited or synced file
+                // read commited or synced file, from where it was before a rename
+                let synced_config_path = state
+                    .local_system_workspace
+                    .get_synced_path(workspace_id, &config_path)?;
                 let file = state
                     .remote_api_client
                     .v2_get_file_blob_id(V2GetFileBlobIdRequest {
-                        workspace_id: Some(workspace_id.clone()),
-                        path: config_path.clone(),
-                    })
-                    .unwrap();
+                        workspace_id: Some(workspace_id.to_string()),
+                        path: synced_config_path,
+                        branch_name: None,
+                        commit_rank: None,
+                    })?;
                 match file {
                     V2GetFileBlobIdResponse::Found { blob_id } => {
-                        let blob = state
-                            .remote_api_client
-                            .v2_read_blob_request(V2ReadBlobRequest {
-                                blob_id: blob_id.clone(),
-                            })
-                            .unwrap();
+                        let blob =
+                            state
+                                .remote_api_client
+                                .v2_read_blob_request(V2ReadBlobRequest {
+                                    blob_id: blob_id.clone(),
+                                })?;
                         match blob {
                             V2ReadBlobResponse::Direct { blob } => {
-                                let toml = std::str::from_utf8(&blob).unwrap();
-                                let config: SagittaConfigToml = toml::from_str(toml).unwrap();
+                                let config = parse_config(&config_path, &blob)?;
                                 config_cache.insert(path.to_vec(), Some(config));
                             }
                             V2ReadBlobResponse::NotFound => {
@@ -108,64 +523,206 @@ pub async fn v1_sync(state: web::Data<ApiState>, req: web::Json<V1SyncRequest>)
         }
         if ignored {
             debug!("ignored: {:?}", path);
+            ignored_files.push(path.clone());
             continue;
         }
 
-        // sync
-        let file = state
+        if dir_paths.contains(path) {
+            let sync_item = V2SyncFilesWithWorkspaceRequestItem::UpsertDir {
+                file_path: path.clone(),
+                permission: *mode,
+            };
+            sync_request.items.push(sync_item);
+            upsert_dirs.push(path.clone());
+            continue;
+        }
+
+        // a blob reference is synced without uploading the blob again
+        let blob_ref = state
             .local_system_workspace
-            .read_cow_file(&req.workspace_id, path, 0, 4_000_000_000)
-            .unwrap();
-        let res = state
-            .remote_api_client
-            .v2_write_blob(V2WriteBlobRequest { data: file })
-            .unwrap();
-        let blob_id = res.blob_id;
-
-        if let Some(tail) = path.last().unwrap().strip_prefix(".sagitta.delete.") {
-            let mut delete_path = path[0..path.len() - 1].to_vec();
-            delete_path.push(tail.to_string());
-
-            let exists = state
-                .remote_api_client
-                .v2_get_attr(V2GetAttrRequest {
-                    path: delete_path.clone(),
-                    workspace_id: Some(workspace_id.clone()),
-                })
-                .unwrap();
-            if let V2GetAttrResponse::Found { .. } = exists {
-                let sync_item = V2SyncFilesWithWorkspaceRequestItem::DeleteFile {
-                    file_path: delete_path.clone(),
-                };
-                sync_request.items.push(sync_item);
-                delete_files.push(delete_path);
-            }
-        } else {
+            .get_cow_blob_ref(workspace_id, path)?;
+        if let Some(blob_ref) = blob_ref {
+            let xattrs = state
+                .local_system_workspace
+                .get_cow_xattrs(workspace_id, path)?;
             let sync_item = V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
                 file_path: path.clone(),
-                blob_id,
+                blob_id: blob_ref.blob_id,
                 permission: *mode,
+                xattrs,
+                modified_at: Some(blob_ref.modified_at),
             };
             sync_request.items.push(sync_item);
-            upsert_files.push(path.clone());
+            reference_files.push(path.clone());
+            continue;
+        }
+
+        // sync
+        let is_symlink = state
+            .local_system_workspace
+            .check_cow_symlink(workspace_id, path)?;
+        if is_symlink {
+            let target = state
+                .local_system_workspace
+                .read_cow_symlink(workspace_id, path)?;
+            let sync_item = V2SyncFilesWithWorkspaceRequestItem::UpsertSymlink {
+                file_path: path.clone(),
+                target,
+            };
+            sync_request.items.push(sync_item);
+            upsert_symlinks.push(path.clone());
+            continue;
         }
+
+        let xattrs = state
+            .local_system_workspace
+            .get_cow_xattrs(workspace_id, path)?;
+        let (size, _, mtime, _) = state
+            .local_system_workspace
+            .get_len_ctime_mtime_and_perm_of_cow_file(workspace_id, path)?;
+
+        // hard linked paths share one blob, so upload it only once
+        let (cow_ino, nlink) = state
+            .local_system_workspace
+            .get_ino_and_nlink_of_cow_file(workspace_id, path)?;
+        let upload_index = match linked_uploads.get(&cow_ino) {
+            Some(upload_index) => {
+                reference_files.push(path.clone());
+                *upload_index
+            }
+            None => {
+                uploads.push(Upload {
+                    path: path.clone(),
+                    size,
+                });
+                if nlink > 1 {
+                    linked_uploads.insert(cow_ino, uploads.len() - 1);
+                }
+                upsert_files.push(path.clone());
+                uploads.len() - 1
+            }
+        };
+
+        // the blob id is filled in once the blob is uploaded
+        let sync_item = V2SyncFilesWithWorkspaceRequestItem::UpsertFile {
+            file_path: path.clone(),
+            blob_id: String::new(),
+            permission: *mode,
+            xattrs,
+            modified_at: Some(mtime),
+        };
+        sync_request.items.push(sync_item);
+        uploaded_items.push((sync_request.items.len() - 1, upload_index));
     }
 
-    let _sync_res = state
-        .remote_api_client
-        .v2_sync_files_with_workspace(sync_request)
-        .unwrap();
+    if !dry_run {
+        let blob_ids = upload_blobs(state, workspace_id, &uploads, on_progress)?;
+        for (item_index, upload_index) in uploaded_items {
+            if let V2SyncFilesWithWorkspaceRequestItem::UpsertFile { blob_id, .. } =
+                &mut sync_request.items[item_index]
+            {
+                *blob_id = blob_ids[upload_index].clone();
+            }
+        }
+    }
 
-    state
-        .local_system_workspace
-        .archive_cow_dir(&req.workspace_id, &upsert_files)
-        .unwrap();
+    // deleted paths that were never synced have nothing to delete on the server
+    let mut deleted_paths = vec![];
+    for entry in &index_entries {
+        if entry.state != IndexState::Deleted {
+            continue;
+        }
+        deleted_paths.push(entry.path.clone());
+
+        // the rename already removes what it moves, and a deleted dir what is under it
+        if index_entries.iter().any(|dir| {
+            dir.state == IndexState::Deleted
+                && dir.path.len() < entry.path.len()
+                && entry.path.starts_with(&dir.path)
+        }) {
+            skipped_files.push(entry.path.clone());
+            continue;
+        }
+        if rename_files
+            .iter()
+            .any(|(from, _)| entry.path.starts_with(from))
+        {
+            skipped_files.push(entry.path.clone());
+            continue;
+        }
+        // a path under a renamed dir still has its old path on the server
+        let remote_path = rename_files
+            .iter()
+            .find(|(_, to)| entry.path.starts_with(to))
+            .map(|(from, to)| [from.as_slice(), &entry.path[to.len()..]].concat())
+            .unwrap_or_else(|| entry.path.clone());
+        let exists = state.remote_api_client.v2_get_attr(V2GetAttrRequest {
+            path: remote_path,
+            workspace_id: Some(workspace_id.to_string()),
+            branch_name: None,
+            commit_rank: None,
+        })?;
+        if let V2GetAttrResponse::Found { is_dir, .. } = exists {
+            let sync_item = if is_dir {
+                V2SyncFilesWithWorkspaceRequestItem::DeleteDir {
+                    file_path: entry.path.clone(),
+                }
+            } else {
+                V2SyncFilesWithWorkspaceRequestItem::DeleteFile {
+                    file_path: entry.path.clone(),
+                }
+            };
+            sync_request.items.push(sync_item);
+            delete_files.push(entry.path.clone());
+        } else {
+            skipped_files.push(entry.path.clone());
+        }
+    }
+
+    if !dry_run {
+        // recorded before the changes are sent, so that a crash at any point from here on
+        // is recovered by the next sync
+        let pending_sync = PendingSync {
+            sync_id,
+            seq: journal.last_seq(),
+            archived_paths: upsert_files
+                .iter()
+                .chain(upsert_dirs.iter())
+                .chain(upsert_symlinks.iter())
+                .chain(reference_files.iter())
+                .chain(deleted_paths.iter())
+                .chain(rename_files.iter().map(|(_, to)| to))
+                .cloned()
+                .collect(),
+        };
+        state
+            .local_system_workspace
+            .begin_sync(workspace_id, &pending_sync)?;
+        let _sync_res = state
+            .remote_api_client
+            .v2_sync_files_with_workspace(sync_request)?;
+        finish_sync(state, workspace_id, &pending_sync)?;
+    }
 
     upsert_files.sort();
+    upsert_dirs.sort();
+    upsert_symlinks.sort();
+    reference_files.sort();
     delete_files.sort();
 
-    web::Json(V1SyncResponse::Ok {
+    rename_files.sort();
+
+    skipped_files.sort();
+    ignored_files.sort();
+
+    Ok(V1SyncResponse::Ok {
         upsert_files,
+        upsert_dirs,
+        upsert_symlinks,
+        reference_files,
         delete_files,
+        rename_files,
+        skipped_files,
+        ignored_files,
     })
 }
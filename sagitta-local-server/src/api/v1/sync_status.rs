@@ -0,0 +1,57 @@
+use actix_web::{post, web, Responder};
+use sagitta_local_api_schema::v1::sync_status::{V1SyncStatusRequest, V1SyncStatusResponse};
+use sagitta_remote_api_schema::v2::get_workspaces::{
+    V2GetWorkspacesRequest, V2GetWorkspacesResponse,
+};
+
+use crate::api_state::ApiState;
+
+#[post("/v1/sync-status")]
+pub async fn v1_sync_status(
+    state: web::Data<ApiState>,
+    req: web::Json<V1SyncStatusRequest>,
+) -> impl Responder {
+    let status = state
+        .sync_status
+        .lock()
+        .unwrap()
+        .get(&req.workspace_id)
+        .cloned()
+        .unwrap_or_default();
+    let workspace_id = req.workspace_id.clone();
+    let res = web::block(move || {
+        // only the workspaces of the server have a journal worth reading
+        let workspaces = state
+            .remote_api_client
+            .v2_get_workspaces(V2GetWorkspacesRequest {})
+            .map_err(|e| format!("{:?}", e))?;
+        let V2GetWorkspacesResponse::Ok { items } = workspaces else {
+            return Err("failed to get the workspaces".to_string());
+        };
+        if !items.iter().any(|item| item.id == workspace_id) {
+            return Ok(None);
+        }
+        state
+            .local_system_workspace
+            .get_journal_changes(&workspace_id)
+            .map(Some)
+            .map_err(|e| format!("{:?}", e))
+    })
+    .await;
+    let changes = match res {
+        Ok(Ok(Some(changes))) => changes,
+        Ok(Ok(None)) => return web::Json(V1SyncStatusResponse::NotFound),
+        Ok(Err(message)) => return web::Json(V1SyncStatusResponse::Err { message }),
+        Err(e) => {
+            return web::Json(V1SyncStatusResponse::Err {
+                message: e.to_string(),
+            })
+        }
+    };
+    let pending_changes = changes.entries.len() as u64;
+    web::Json(V1SyncStatusResponse::Ok {
+        last_synced_at: status.last_synced_at,
+        pending_changes,
+        last_error: status.last_error,
+    })
+}
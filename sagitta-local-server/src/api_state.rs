@@ -1,4 +1,9 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
 
 use sagitta_common::clock::Clock;
 use sagitta_local_system_workspace::LocalSystemWorkspaceManager;
@@ -9,6 +14,18 @@ pub struct ApiState {
     pub clock: Clock,
     pub local_system_workspace: LocalSystemWorkspaceManager,
     pub remote_api_client: SagittaApiClient,
+    /// Number of blobs a sync uploads at once.
+    pub upload_concurrency: usize,
+    /// Held while a workspace is synced, by workspace id.
+    pub sync_locks: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+    /// Outcome of the latest sync of each workspace, by workspace id.
+    pub sync_status: Arc<Mutex<HashMap<String, SyncStatus>>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyncStatus {
+    pub last_synced_at: Option<SystemTime>,
+    pub last_error: Option<String>,
 }
 
 impl ApiState {
@@ -16,11 +33,25 @@ impl ApiState {
         clock: Clock,
         local_system_workspace_path: PathBuf,
         remote_api_base_url: &str,
+        upload_concurrency: usize,
     ) -> Self {
         Self {
             clock,
             local_system_workspace: LocalSystemWorkspaceManager::new(local_system_workspace_path),
             remote_api_client: SagittaApiClient::new(remote_api_base_url.to_string()),
+            upload_concurrency,
+            sync_locks: Arc::new(Mutex::new(HashMap::new())),
+            sync_status: Arc::new(Mutex::new(HashMap::new())),
         }
     }
+
+    /// The lock held while a workspace is synced or its local state is otherwise rewritten.
+    pub fn sync_lock(&self, workspace_id: &str) -> Arc<Mutex<()>> {
+        self.sync_locks
+            .lock()
+            .unwrap()
+            .entry(workspace_id.to_string())
+            .or_default()
+            .clone()
+    }
 }
@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use log::{error, info, warn};
+use sagitta_common::clock::Clock;
+
+use crate::{api::v1::sync::sync_workspace, api_state::ApiState};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct AutoSyncConfig {
+    /// How long the journal of a workspace has to stay unchanged before it is synced.
+    pub debounce: Duration,
+    /// Upper bound of the wait before retrying a failed sync. The wait starts at
+    /// `debounce` and doubles on every failure.
+    pub max_backoff: Duration,
+}
+
+#[derive(Debug)]
+struct PendingWorkspace {
+    last_seq: i64,
+    changed_at: SystemTime,
+    retry_at: Option<SystemTime>,
+    backoff: Duration,
+}
+
+/// Decides when the workspaces with unsynced changes are synced, from the journal
+/// positions it is shown and the outcomes of the syncs.
+#[derive(Debug)]
+struct AutoSyncSchedule {
+    config: AutoSyncConfig,
+    clock: Clock,
+    pending: HashMap<String, PendingWorkspace>,
+}
+
+impl AutoSyncSchedule {
+    fn new(config: AutoSyncConfig, clock: Clock) -> Self {
+        Self {
+            config,
+            clock,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Forgets the workspaces that are gone.
+    fn retain(&mut self, workspace_ids: &[String]) {
+        self.pending
+            .retain(|workspace_id, _| workspace_ids.contains(workspace_id));
+    }
+
+    /// Records the last journal position of a workspace, `None` if it has no unsynced
+    /// changes, and returns `true` if it is due for a sync.
+    fn poll(&mut self, workspace_id: &str, last_seq: Option<i64>) -> bool {
+        let Some(last_seq) = last_seq else {
+            self.pending.remove(workspace_id);
+            return false;
+        };
+        let now = self.clock.now();
+        let workspace = self
+            .pending
+            .entry(workspace_id.to_string())
+            .or_insert(PendingWorkspace {
+                last_seq,
+                changed_at: now,
+                retry_at: None,
+                backoff: self.config.debounce,
+            });
+        if workspace.last_seq != last_seq {
+            workspace.last_seq = last_seq;
+            workspace.changed_at = now;
+        }
+        now >= workspace.changed_at + self.config.debounce
+            && workspace.retry_at.is_none_or(|retry_at| now >= retry_at)
+    }
+
+    fn synced(&mut self, workspace_id: &str) {
+        self.pending.remove(workspace_id);
+    }
+
+    /// Puts off the next sync of a workspace whose sync failed, and returns for how long.
+    fn failed(&mut self, workspace_id: &str) -> Duration {
+        let now = self.clock.now();
+        let Some(workspace) = self.pending.get_mut(workspace_id) else {
+            return Duration::ZERO;
+        };
+        let backoff = workspace.backoff;
+        workspace.retry_at = Some(now + backoff);
+        workspace.backoff = std::cmp::min(backoff * 2, self.config.max_backoff);
+        backoff
+    }
+}
+
+/// Watches the journals of all workspaces and syncs the ones with changes. Never returns.
+pub fn run_auto_sync(state: ApiState, config: AutoSyncConfig) {
+    let mut schedule = AutoSyncSchedule::new(config, state.clock.clone());
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let workspace_ids = match state.local_system_workspace.list_workspace_ids() {
+            Ok(workspace_ids) => workspace_ids,
+            Err(e) => {
+                error!("failed to list workspaces: {:?}", e);
+                continue;
+            }
+        };
+        schedule.retain(&workspace_ids);
+
+        for workspace_id in workspace_ids {
+            let changes = match state
+                .local_system_workspace
+                .get_journal_changes(&workspace_id)
+            {
+                Ok(changes) => changes,
+                Err(e) => {
+                    error!("failed to read the journal of {}: {:?}", workspace_id, e);
+                    continue;
+                }
+            };
+            let last_seq = (!changes.entries.is_empty()).then(|| changes.last_seq());
+            if !schedule.poll(&workspace_id, last_seq) {
+                continue;
+            }
+
+            match sync_workspace(&state, &workspace_id, false, &|_| {}) {
+                Ok(_) => {
+                    info!("synced {}", workspace_id);
+                    schedule.synced(&workspace_id);
+                }
+                Err(e) => {
+                    let backoff = schedule.failed(&workspace_id);
+                    warn!(
+                        "failed to sync {}, retrying in {:?}: {:?}",
+                        workspace_id, backoff, e
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_sync_schedule() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let config = AutoSyncConfig {
+            debounce: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(15),
+        };
+        let mut schedule = AutoSyncSchedule::new(config, Clock::new_with_fixed_time(start));
+        let advance_to = |schedule: &mut AutoSyncSchedule, secs: u64| {
+            schedule.clock = Clock::new_with_fixed_time(start + Duration::from_secs(secs));
+        };
+
+        // a sync waits until the journal has not changed for the debounce
+        assert!(!schedule.poll("ws1", Some(1)));
+        advance_to(&mut schedule, 3);
+        assert!(!schedule.poll("ws1", Some(2)));
+        advance_to(&mut schedule, 7);
+        assert!(!schedule.poll("ws1", Some(2)));
+        advance_to(&mut schedule, 8);
+        assert!(schedule.poll("ws1", Some(2)));
+
+        // failed syncs are retried after a doubling backoff, up to the maximum
+        assert_eq!(schedule.failed("ws1"), Duration::from_secs(5));
+        advance_to(&mut schedule, 12);
+        assert!(!schedule.poll("ws1", Some(2)));
+        advance_to(&mut schedule, 13);
+        assert!(schedule.poll("ws1", Some(2)));
+        assert_eq!(schedule.failed("ws1"), Duration::from_secs(10));
+        advance_to(&mut schedule, 23);
+        assert!(schedule.poll("ws1", Some(2)));
+        assert_eq!(schedule.failed("ws1"), Duration::from_secs(15));
+        advance_to(&mut schedule, 38);
+        assert!(schedule.poll("ws1", Some(2)));
+        assert_eq!(schedule.failed("ws1"), Duration::from_secs(15));
+
+        // a successful sync starts over
+        advance_to(&mut schedule, 53);
+        assert!(schedule.poll("ws1", Some(2)));
+        schedule.synced("ws1");
+        assert!(!schedule.poll("ws1", Some(3)));
+        advance_to(&mut schedule, 58);
+        assert!(schedule.poll("ws1", Some(3)));
+
+        // a workspace without changes, or that is gone, is forgotten
+        assert!(!schedule.poll("ws1", None));
+        assert!(!schedule.pending.contains_key("ws1"));
+        schedule.poll("ws2", Some(1));
+        schedule.retain(&["ws1".to_string()]);
+        assert!(schedule.pending.is_empty());
+    }
+}
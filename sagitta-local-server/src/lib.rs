@@ -1,2 +1,3 @@
 pub mod api;
 pub mod api_state;
+pub mod auto_sync;
@@ -0,0 +1,367 @@
+use std::{collections::BTreeMap, path::Path, sync::Mutex, time::SystemTime};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{OpenFlags, OptionalExtension};
+
+/// How a path of a workspace changed since the workspace was last synced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexState {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+impl IndexState {
+    fn to_i64(self) -> i64 {
+        match self {
+            IndexState::Added => 0,
+            IndexState::Modified => 1,
+            IndexState::Deleted => 2,
+            IndexState::Renamed => 3,
+        }
+    }
+
+    fn from_i64(state: i64) -> Self {
+        match state {
+            0 => IndexState::Added,
+            1 => IndexState::Modified,
+            2 => IndexState::Deleted,
+            3 => IndexState::Renamed,
+            _ => panic!("unknown index state: {}", state),
+        }
+    }
+}
+
+/// A changed path of a workspace.
+///
+/// The contents of the path are its cow file when there is one. Otherwise they are the
+/// remote blob `blob_id`, described by `size`, `mode` and `modified_at`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub path: Vec<String>,
+    pub state: IndexState,
+    /// The synced path a `Renamed` entry was moved from.
+    pub renamed_from: Option<Vec<String>>,
+    pub mode: Option<u32>,
+    pub modified_at: Option<SystemTime>,
+    pub size: Option<u64>,
+    /// Remote blob the entry refers to, or was copied from.
+    pub blob_id: Option<String>,
+}
+
+impl IndexEntry {
+    pub fn new(path: Vec<String>, state: IndexState) -> Self {
+        Self {
+            path,
+            state,
+            renamed_from: None,
+            mode: None,
+            modified_at: None,
+            size: None,
+            blob_id: None,
+        }
+    }
+}
+
+/// An extended attribute of a path: the path, its name and its value.
+pub(crate) type PathXattr = (Vec<String>, String, Vec<u8>);
+
+/// The index of one workspace, kept in `index.sqlite` next to its cow dir. It also holds
+/// the extended attributes of the workspace files that have local changes.
+#[derive(Debug)]
+pub(crate) struct WorkspaceIndex {
+    db: Mutex<rusqlite::Connection>,
+    /// Whether changes are written to `index.sqlite`, rather than the index being read
+    /// only or kept in memory.
+    pub(crate) writable: bool,
+    /// Whether the cow dir still holds the delete marker files of older versions, which
+    /// is only the case until the workspace gets `index.sqlite`.
+    pub(crate) has_marker_files: bool,
+}
+
+impl WorkspaceIndex {
+    pub(crate) fn open<P: AsRef<Path>>(sqlite_path: P) -> Result<Self, rusqlite::Error> {
+        let db = rusqlite::Connection::open(sqlite_path)?;
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS entry (
+                path TEXT PRIMARY KEY,
+                state INTEGER NOT NULL,
+                renamed_from TEXT,
+                mode INTEGER,
+                modified_at TEXT,
+                size INTEGER,
+                blob_id TEXT
+            )",
+            (),
+        )?;
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS xattr (
+                path TEXT NOT NULL,
+                name TEXT NOT NULL,
+                value BLOB NOT NULL,
+                PRIMARY KEY (path, name)
+            )",
+            (),
+        )?;
+        Ok(Self {
+            db: Mutex::new(db),
+            writable: true,
+            has_marker_files: false,
+        })
+    }
+
+    /// Opens an existing index without writing to it.
+    pub(crate) fn open_read_only<P: AsRef<Path>>(sqlite_path: P) -> Result<Self, rusqlite::Error> {
+        let db =
+            rusqlite::Connection::open_with_flags(sqlite_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self {
+            db: Mutex::new(db),
+            writable: false,
+            has_marker_files: false,
+        })
+    }
+
+    /// Creates an index that only lives in memory, for a workspace without `index.sqlite`.
+    pub(crate) fn open_in_memory() -> Result<Self, rusqlite::Error> {
+        Ok(Self {
+            writable: false,
+            ..Self::open(":memory:")?
+        })
+    }
+
+    pub(crate) fn get(&self, path: &[String]) -> Result<Option<IndexEntry>, rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        db.query_row(
+            "SELECT path, state, renamed_from, mode, modified_at, size, blob_id
+                FROM entry WHERE path = ?",
+            rusqlite::params![path.join("/")],
+            Self::entry_from_row,
+        )
+        .optional()
+    }
+
+    pub(crate) fn list(&self) -> Result<Vec<IndexEntry>, rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let mut stmt = db.prepare(
+            "SELECT path, state, renamed_from, mode, modified_at, size, blob_id
+            FROM entry ORDER BY path",
+        )?;
+        let entries = stmt
+            .query_map((), Self::entry_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    pub(crate) fn upsert(&self, entry: &IndexEntry) -> Result<(), rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let modified_at = entry
+            .modified_at
+            .map(|t| DateTime::<Utc>::from(t).to_rfc3339());
+        db.execute(
+            "INSERT OR REPLACE INTO entry (path, state, renamed_from, mode, modified_at, size, blob_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?)",
+            rusqlite::params![
+                entry.path.join("/"),
+                entry.state.to_i64(),
+                entry.renamed_from.as_ref().map(|path| path.join("/")),
+                entry.mode,
+                modified_at,
+                entry.size.map(|size| size as i64),
+                entry.blob_id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn remove(&self, path: &[String]) -> Result<(), rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        db.execute(
+            "DELETE FROM entry WHERE path = ?",
+            rusqlite::params![path.join("/")],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn get_xattrs(
+        &self,
+        path: &[String],
+    ) -> Result<BTreeMap<String, Vec<u8>>, rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let mut stmt = match db.prepare("SELECT name, value FROM xattr WHERE path = ?") {
+            Ok(stmt) => stmt,
+            // an index written before it held extended attributes has none to read
+            Err(_) if !self.writable => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e),
+        };
+        let xattrs = stmt
+            .query_map(rusqlite::params![path.join("/")], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        Ok(xattrs)
+    }
+
+    pub(crate) fn set_xattr(
+        &self,
+        path: &[String],
+        name: &str,
+        value: &[u8],
+    ) -> Result<(), rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        db.execute(
+            "INSERT OR REPLACE INTO xattr (path, name, value) VALUES (?, ?, ?)",
+            rusqlite::params![path.join("/"), name, value],
+        )?;
+        Ok(())
+    }
+
+    /// Returns `false` if the attribute was not set.
+    pub(crate) fn remove_xattr(
+        &self,
+        path: &[String],
+        name: &str,
+    ) -> Result<bool, rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let removed = db.execute(
+            "DELETE FROM xattr WHERE path = ? AND name = ?",
+            rusqlite::params![path.join("/"), name],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Removes the extended attributes of `path` and of the paths under it, returning them
+    /// by path.
+    pub(crate) fn take_xattrs_under(
+        &self,
+        path: &[String],
+    ) -> Result<Vec<PathXattr>, rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let path = path.join("/");
+        let prefix = format!("{}/", path);
+        let xattrs = db
+            .prepare(
+                "SELECT path, name, value FROM xattr
+                WHERE path = ? OR substr(path, 1, length(?)) = ?",
+            )?
+            .query_map(rusqlite::params![path, prefix, prefix], |row| {
+                let path: String = row.get(0)?;
+                Ok((split_path(&path), row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        db.execute(
+            "DELETE FROM xattr WHERE path = ? OR substr(path, 1, length(?)) = ?",
+            rusqlite::params![path, prefix, prefix],
+        )?;
+        Ok(xattrs)
+    }
+
+    fn entry_from_row(row: &rusqlite::Row) -> Result<IndexEntry, rusqlite::Error> {
+        let path: String = row.get(0)?;
+        let state: i64 = row.get(1)?;
+        let renamed_from: Option<String> = row.get(2)?;
+        let modified_at: Option<String> = row.get(4)?;
+        let size: Option<i64> = row.get(5)?;
+        Ok(IndexEntry {
+            path: split_path(&path),
+            state: IndexState::from_i64(state),
+            renamed_from: renamed_from.map(|path| split_path(&path)),
+            mode: row.get(3)?,
+            modified_at: modified_at.map(|t| DateTime::parse_from_rfc3339(&t).unwrap().into()),
+            size: size.map(|size| size as u64),
+            blob_id: row.get(6)?,
+        })
+    }
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    path.split('/').map(|p| p.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(path: &str) -> Vec<String> {
+        split_path(path)
+    }
+
+    #[test]
+    fn test_workspace_index_upsert_and_remove() {
+        let index = WorkspaceIndex::open(":memory:").unwrap();
+        let modified_at = SystemTime::UNIX_EPOCH + std::time::Duration::from_nanos(1_500_000_001);
+        let entry = IndexEntry {
+            renamed_from: Some(path("dir1/old.txt")),
+            mode: Some(0o644),
+            modified_at: Some(modified_at),
+            size: Some(12),
+            blob_id: Some("blob1".to_string()),
+            ..IndexEntry::new(path("dir1/new.txt"), IndexState::Renamed)
+        };
+        index.upsert(&entry).unwrap();
+        index
+            .upsert(&IndexEntry::new(path("dir1/old.txt"), IndexState::Deleted))
+            .unwrap();
+        assert_eq!(
+            index.get(&path("dir1/new.txt")).unwrap(),
+            Some(entry.clone())
+        );
+
+        index
+            .upsert(&IndexEntry {
+                state: IndexState::Modified,
+                ..entry.clone()
+            })
+            .unwrap();
+        let states = index
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.path.join("/"), entry.state))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            states,
+            vec![
+                ("dir1/new.txt".to_string(), IndexState::Modified),
+                ("dir1/old.txt".to_string(), IndexState::Deleted),
+            ]
+        );
+
+        index.remove(&path("dir1/new.txt")).unwrap();
+        assert_eq!(index.get(&path("dir1/new.txt")).unwrap(), None);
+        assert_eq!(index.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_workspace_index_xattrs() {
+        let index = WorkspaceIndex::open(":memory:").unwrap();
+        // names that look like the files older versions kept them in are plain names
+        index
+            .set_xattr(&path("dir1/.sagitta.xattr.a"), "user.a", b"1")
+            .unwrap();
+        index.set_xattr(&path("dir1/b"), "user.b", b"2").unwrap();
+        index.set_xattr(&path("dir10"), "user.c", b"3").unwrap();
+        assert_eq!(
+            index.get_xattrs(&path("dir1/.sagitta.xattr.a")).unwrap(),
+            BTreeMap::from([("user.a".to_string(), b"1".to_vec())])
+        );
+
+        assert!(index.remove_xattr(&path("dir1/b"), "user.b").unwrap());
+        assert!(!index.remove_xattr(&path("dir1/b"), "user.b").unwrap());
+
+        let taken = index.take_xattrs_under(&path("dir1")).unwrap();
+        assert_eq!(
+            taken,
+            vec![(
+                path("dir1/.sagitta.xattr.a"),
+                "user.a".to_string(),
+                b"1".to_vec()
+            )]
+        );
+        assert!(index
+            .get_xattrs(&path("dir1/.sagitta.xattr.a"))
+            .unwrap()
+            .is_empty());
+        assert_eq!(index.get_xattrs(&path("dir10")).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,346 @@
+use std::{path::Path, sync::Mutex};
+
+use rusqlite::{OpenFlags, OptionalExtension};
+
+/// A mutating operation on a workspace path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalOp {
+    Create,
+    Write,
+    Setattr,
+    Rename,
+    Unlink,
+    Mkdir,
+    Rmdir,
+}
+
+impl JournalOp {
+    fn to_i64(self) -> i64 {
+        match self {
+            JournalOp::Create => 0,
+            JournalOp::Write => 1,
+            JournalOp::Setattr => 2,
+            JournalOp::Rename => 3,
+            JournalOp::Unlink => 4,
+            JournalOp::Mkdir => 5,
+            JournalOp::Rmdir => 6,
+        }
+    }
+
+    fn from_i64(op: i64) -> Self {
+        match op {
+            0 => JournalOp::Create,
+            1 => JournalOp::Write,
+            2 => JournalOp::Setattr,
+            3 => JournalOp::Rename,
+            4 => JournalOp::Unlink,
+            5 => JournalOp::Mkdir,
+            6 => JournalOp::Rmdir,
+            _ => panic!("unknown journal op: {}", op),
+        }
+    }
+}
+
+/// One operation recorded in the journal of a workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub seq: i64,
+    pub op: JournalOp,
+    pub path: Vec<String>,
+    /// Destination of a `Rename`.
+    pub to_path: Option<Vec<String>>,
+}
+
+/// The operations a sync has not acknowledged yet.
+#[derive(Debug, Clone)]
+pub struct JournalChanges {
+    /// Last acknowledged position, `None` if no sync has acknowledged one. Changes made
+    /// before the journal existed are then only found by walking the whole cow dir.
+    pub acknowledged_seq: Option<i64>,
+    pub entries: Vec<JournalEntry>,
+}
+
+impl JournalChanges {
+    /// Position to acknowledge once the changes are synced.
+    pub fn last_seq(&self) -> i64 {
+        self.entries
+            .last()
+            .map(|entry| entry.seq)
+            .or(self.acknowledged_seq)
+            .unwrap_or(0)
+    }
+}
+
+/// A sync whose changes were sent, or are about to be sent, to the server but whose cow
+/// files are not archived yet. It is kept until the sync is finished, so that a sync
+/// interrupted by a crash can be resumed or rolled back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingSync {
+    pub sync_id: String,
+    /// Journal position the changes were collected at.
+    pub seq: i64,
+    /// Paths to archive once the server has applied the sync.
+    pub archived_paths: Vec<Vec<String>>,
+}
+
+/// The journal of one workspace, kept in `journal.sqlite` next to its cow dir.
+#[derive(Debug)]
+pub(crate) struct WorkspaceJournal {
+    db: Mutex<rusqlite::Connection>,
+    /// Whether the journal was opened for writing.
+    pub(crate) writable: bool,
+}
+
+impl WorkspaceJournal {
+    pub(crate) fn open<P: AsRef<Path>>(sqlite_path: P) -> Result<Self, rusqlite::Error> {
+        let db = rusqlite::Connection::open(sqlite_path)?;
+        // every write of a file is appended, so appends must not wait for the disk
+        db.pragma_update(None, "journal_mode", "WAL")?;
+        db.pragma_update(None, "synchronous", "NORMAL")?;
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS entry (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                op INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                to_path TEXT
+            )",
+            (),
+        )?;
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS acknowledged (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                seq INTEGER NOT NULL
+            )",
+            (),
+        )?;
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS pending_sync (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                sync_id TEXT NOT NULL,
+                seq INTEGER NOT NULL
+            )",
+            (),
+        )?;
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS pending_sync_path (
+                path TEXT NOT NULL
+            )",
+            (),
+        )?;
+        Ok(Self {
+            db: Mutex::new(db),
+            writable: true,
+        })
+    }
+
+    /// Opens an existing journal without writing to it.
+    pub(crate) fn open_read_only<P: AsRef<Path>>(sqlite_path: P) -> Result<Self, rusqlite::Error> {
+        let db =
+            rusqlite::Connection::open_with_flags(sqlite_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self {
+            db: Mutex::new(db),
+            writable: false,
+        })
+    }
+
+    pub(crate) fn append(
+        &self,
+        op: JournalOp,
+        path: &[String],
+        to_path: Option<&[String]>,
+    ) -> Result<(), rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        db.execute(
+            "INSERT INTO entry (op, path, to_path) VALUES (?, ?, ?)",
+            rusqlite::params![
+                op.to_i64(),
+                path.join("/"),
+                to_path.map(|to_path| to_path.join("/"))
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn changes(&self) -> Result<JournalChanges, rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let acknowledged_seq: Option<i64> = db
+            .query_row("SELECT seq FROM acknowledged WHERE id = 0", (), |row| {
+                row.get(0)
+            })
+            .optional()?;
+        let mut stmt =
+            db.prepare("SELECT seq, op, path, to_path FROM entry WHERE seq > ? ORDER BY seq")?;
+        let entries = stmt
+            .query_map(rusqlite::params![acknowledged_seq.unwrap_or(0)], |row| {
+                let op: i64 = row.get(1)?;
+                let path: String = row.get(2)?;
+                let to_path: Option<String> = row.get(3)?;
+                Ok(JournalEntry {
+                    seq: row.get(0)?,
+                    op: JournalOp::from_i64(op),
+                    path: split_path(&path),
+                    to_path: to_path.map(|to_path| split_path(&to_path)),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(JournalChanges {
+            acknowledged_seq,
+            entries,
+        })
+    }
+
+    /// Marks the operations up to `seq` as synced and drops them, along with the pending
+    /// sync that synced them.
+    pub(crate) fn acknowledge(&self, seq: i64) -> Result<(), rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let tx = db.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO acknowledged (id, seq) VALUES (0, ?)",
+            rusqlite::params![seq],
+        )?;
+        tx.execute("DELETE FROM entry WHERE seq <= ?", rusqlite::params![seq])?;
+        Self::delete_pending_sync(&tx)?;
+        tx.commit()
+    }
+
+    /// Records a sync before its changes are sent, replacing any previous one.
+    pub(crate) fn begin_sync(&self, pending_sync: &PendingSync) -> Result<(), rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let tx = db.unchecked_transaction()?;
+        Self::delete_pending_sync(&tx)?;
+        tx.execute(
+            "INSERT INTO pending_sync (id, sync_id, seq) VALUES (0, ?, ?)",
+            rusqlite::params![pending_sync.sync_id, pending_sync.seq],
+        )?;
+        for path in &pending_sync.archived_paths {
+            tx.execute(
+                "INSERT INTO pending_sync_path (path) VALUES (?)",
+                rusqlite::params![path.join("/")],
+            )?;
+        }
+        tx.commit()
+    }
+
+    pub(crate) fn pending_sync(&self) -> Result<Option<PendingSync>, rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let pending_sync: Option<(String, i64)> = db
+            .query_row(
+                "SELECT sync_id, seq FROM pending_sync WHERE id = 0",
+                (),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((sync_id, seq)) = pending_sync else {
+            return Ok(None);
+        };
+        let mut stmt = db.prepare("SELECT path FROM pending_sync_path ORDER BY rowid")?;
+        let archived_paths = stmt
+            .query_map((), |row| {
+                let path: String = row.get(0)?;
+                Ok(split_path(&path))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(PendingSync {
+            sync_id,
+            seq,
+            archived_paths,
+        }))
+    }
+
+    /// Forgets a pending sync the server never applied. Its changes are still in the
+    /// journal and the cow dir, so the next sync collects them again.
+    pub(crate) fn discard_pending_sync(&self) -> Result<(), rusqlite::Error> {
+        let db = self.db.lock().unwrap();
+        let tx = db.unchecked_transaction()?;
+        Self::delete_pending_sync(&tx)?;
+        tx.commit()
+    }
+
+    fn delete_pending_sync(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
+        tx.execute("DELETE FROM pending_sync", ())?;
+        tx.execute("DELETE FROM pending_sync_path", ())?;
+        Ok(())
+    }
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    path.split('/').map(|p| p.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(path: &str) -> Vec<String> {
+        split_path(path)
+    }
+
+    #[test]
+    fn test_workspace_journal_append_and_acknowledge() {
+        let journal = WorkspaceJournal::open(":memory:").unwrap();
+        assert_eq!(journal.changes().unwrap().acknowledged_seq, None);
+
+        journal
+            .append(JournalOp::Create, &path("dir1/a.txt"), None)
+            .unwrap();
+        journal
+            .append(JournalOp::Write, &path("dir1/a.txt"), None)
+            .unwrap();
+        journal
+            .append(
+                JournalOp::Rename,
+                &path("dir1/a.txt"),
+                Some(&path("dir1/b.txt")),
+            )
+            .unwrap();
+        let changes = journal.changes().unwrap();
+        let ops = changes
+            .entries
+            .iter()
+            .map(|entry| entry.op)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            vec![JournalOp::Create, JournalOp::Write, JournalOp::Rename]
+        );
+        assert_eq!(changes.entries[2].to_path, Some(path("dir1/b.txt")));
+
+        // operations after the acknowledged position are kept
+        let seq = changes.last_seq();
+        journal
+            .append(JournalOp::Unlink, &path("dir1/b.txt"), None)
+            .unwrap();
+        journal.acknowledge(seq).unwrap();
+        let changes = journal.changes().unwrap();
+        assert_eq!(changes.acknowledged_seq, Some(seq));
+        assert_eq!(changes.entries.len(), 1);
+        assert_eq!(changes.entries[0].op, JournalOp::Unlink);
+        assert_eq!(changes.last_seq(), seq + 1);
+    }
+
+    #[test]
+    fn test_workspace_journal_pending_sync() {
+        let journal = WorkspaceJournal::open(":memory:").unwrap();
+        assert_eq!(journal.pending_sync().unwrap(), None);
+
+        journal
+            .append(JournalOp::Write, &path("dir1/a.txt"), None)
+            .unwrap();
+        let pending_sync = PendingSync {
+            sync_id: "sync1".to_string(),
+            seq: journal.changes().unwrap().last_seq(),
+            archived_paths: vec![path("dir1/a.txt"), path("dir1")],
+        };
+        journal.begin_sync(&pending_sync).unwrap();
+        assert_eq!(journal.pending_sync().unwrap(), Some(pending_sync.clone()));
+
+        journal.discard_pending_sync().unwrap();
+        assert_eq!(journal.pending_sync().unwrap(), None);
+
+        // acknowledging the synced operations finishes the sync
+        journal.begin_sync(&pending_sync).unwrap();
+        journal.acknowledge(pending_sync.seq).unwrap();
+        assert_eq!(journal.pending_sync().unwrap(), None);
+        assert!(journal.changes().unwrap().entries.is_empty());
+    }
+}
@@ -1,10 +1,21 @@
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
 use std::{
+    collections::{BTreeMap, HashMap},
     io::{Read, Seek, Write},
     path::PathBuf,
+    sync::{Arc, Mutex},
     time::SystemTime,
 };
 
+use index::WorkspaceIndex;
+pub use index::{IndexEntry, IndexState};
+use journal::WorkspaceJournal;
+pub use journal::{JournalChanges, JournalEntry, JournalOp, PendingSync};
+
+mod index;
+mod journal;
+
 // file hierarchy
 // root
 // - trunk
@@ -21,33 +32,499 @@ use std::{
 //       - 23
 //         - 012345...
 //     - 03
+//   - index.sqlite (paths changed since the last sync, see `IndexEntry`, and the extended
+//     attributes of the changed files)
+//   - journal.sqlite (operations not yet acknowledged by a sync, see `JournalEntry`)
 //   - cow
 //     - dir1
 //       - file1
 //     - dir2
 //       - file2
+//       - link1 -> ../dir1/file1 (symlinks are kept as symlinks)
 // - workspace2
+// - tmp (unlinked files holding the blobs read through open file handles)
 
 #[derive(Debug, Clone)]
 pub struct LocalSystemWorkspaceManager {
     base_path: PathBuf,
+    // every write through the mount touches the journal and most lookups the index, so
+    // they are opened once per workspace and shared by the clones of the manager
+    indexes: Arc<Mutex<HashMap<String, Arc<WorkspaceIndex>>>>,
+    journals: Arc<Mutex<HashMap<String, Arc<WorkspaceJournal>>>>,
 }
 
 #[derive(Debug)]
 pub enum Error {
     IOError(std::io::Error),
+    IndexError(rusqlite::Error),
+    JournalError(rusqlite::Error),
+}
+
+/// A workspace file whose contents are an already uploaded blob, recorded in the index
+/// without copying the data into the cow dir.
+#[derive(Debug, Clone)]
+pub struct CowBlobRef {
+    pub blob_id: String,
+    pub size: u64,
+    pub mode: u32,
+    pub modified_at: SystemTime,
 }
 
 #[derive(Debug, Clone)]
 pub struct ReadCowDirItem {
     pub name: String,
+    pub is_symlink: bool,
 }
 
 impl LocalSystemWorkspaceManager {
     pub fn new(base_path: PathBuf) -> Self {
         Self {
             base_path: base_path.clone(),
+            indexes: Arc::new(Mutex::new(HashMap::new())),
+            journals: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns `true` for the delete marker files of older versions. They are only skipped
+    /// in a workspace that has them, see `WorkspaceIndex::has_marker_files`.
+    fn is_marker_file_name(file_name: &str) -> bool {
+        file_name.starts_with(".sagitta.delete.")
+    }
+
+    /// Opens the index of a workspace for writing, creating it if needed. A new index
+    /// takes over the delete marker files that older versions left in the cow dir.
+    fn open_index(&self, workspace_id: &str) -> Result<Arc<WorkspaceIndex>, Error> {
+        let mut indexes = self.indexes.lock().unwrap();
+        if let Some(index) = indexes.get(workspace_id).filter(|index| index.writable) {
+            return Ok(index.clone());
+        }
+        let workspace_path = self.base_path.join(workspace_id);
+        std::fs::create_dir_all(&workspace_path).map_err(Error::IOError)?;
+        let index_path = workspace_path.join("index.sqlite");
+        let is_new = !index_path.exists();
+        let index = WorkspaceIndex::open(&index_path).map_err(Error::IndexError)?;
+        let cow_path = workspace_path.join("cow");
+        if is_new && cow_path.exists() {
+            Self::import_delete_markers(&index, cow_path, &mut vec![], true)?;
+        }
+        let index = Arc::new(index);
+        indexes.insert(workspace_id.to_string(), index.clone());
+        Ok(index)
+    }
+
+    /// Returns the index of a workspace for reading, without creating it. A workspace
+    /// without `index.sqlite` gets one in memory that holds its delete marker files.
+    fn read_index(&self, workspace_id: &str) -> Result<Arc<WorkspaceIndex>, Error> {
+        let mut indexes = self.indexes.lock().unwrap();
+        if let Some(index) = indexes.get(workspace_id) {
+            return Ok(index.clone());
+        }
+        let workspace_path = self.base_path.join(workspace_id);
+        let index_path = workspace_path.join("index.sqlite");
+        let index = if index_path.exists() {
+            WorkspaceIndex::open_read_only(&index_path).map_err(Error::IndexError)?
+        } else {
+            let mut index = WorkspaceIndex::open_in_memory().map_err(Error::IndexError)?;
+            let cow_path = workspace_path.join("cow");
+            if cow_path.exists() {
+                Self::import_delete_markers(&index, cow_path, &mut vec![], false)?;
+                index.has_marker_files = true;
+            }
+            index
+        };
+        let index = Arc::new(index);
+        indexes.insert(workspace_id.to_string(), index.clone());
+        Ok(index)
+    }
+
+    /// Lists the ids of the workspaces that have a journal.
+    pub fn list_workspace_ids(&self) -> Result<Vec<String>, Error> {
+        let mut res = vec![];
+        let entries = match std::fs::read_dir(&self.base_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(res),
+            Err(e) => return Err(Error::IOError(e)),
+        };
+        for entry in entries {
+            let entry = entry.map_err(Error::IOError)?;
+            if entry.path().join("journal.sqlite").exists() {
+                res.push(entry.file_name().to_str().unwrap().to_string());
+            }
+        }
+        res.sort();
+        Ok(res)
+    }
+
+    fn open_journal(&self, workspace_id: &str) -> Result<Arc<WorkspaceJournal>, Error> {
+        let mut journals = self.journals.lock().unwrap();
+        if let Some(journal) = journals
+            .get(workspace_id)
+            .filter(|journal| journal.writable)
+        {
+            return Ok(journal.clone());
+        }
+        let workspace_path = self.base_path.join(workspace_id);
+        std::fs::create_dir_all(&workspace_path).map_err(Error::IOError)?;
+        let journal = WorkspaceJournal::open(workspace_path.join("journal.sqlite"))
+            .map_err(Error::JournalError)?;
+        let journal = Arc::new(journal);
+        journals.insert(workspace_id.to_string(), journal.clone());
+        Ok(journal)
+    }
+
+    /// Returns the journal of a workspace for reading if it has one, without creating it.
+    fn read_journal(&self, workspace_id: &str) -> Result<Option<Arc<WorkspaceJournal>>, Error> {
+        let mut journals = self.journals.lock().unwrap();
+        if let Some(journal) = journals.get(workspace_id) {
+            return Ok(Some(journal.clone()));
+        }
+        let journal_path = self.base_path.join(workspace_id).join("journal.sqlite");
+        if !journal_path.exists() {
+            return Ok(None);
+        }
+        let journal =
+            WorkspaceJournal::open_read_only(journal_path).map_err(Error::JournalError)?;
+        let journal = Arc::new(journal);
+        journals.insert(workspace_id.to_string(), journal.clone());
+        Ok(Some(journal))
+    }
+
+    /// Records a mutating operation on `path`, or on `path` and `to_path` for a rename.
+    pub fn append_journal(
+        &self,
+        workspace_id: &str,
+        op: JournalOp,
+        path: &[String],
+        to_path: Option<&[String]>,
+    ) -> Result<(), Error> {
+        let journal = self.open_journal(workspace_id)?;
+        journal
+            .append(op, path, to_path)
+            .map_err(Error::JournalError)
+    }
+
+    /// Returns the operations recorded since the last acknowledged position. A workspace
+    /// without a journal has no changes and nothing acknowledged.
+    pub fn get_journal_changes(&self, workspace_id: &str) -> Result<JournalChanges, Error> {
+        let Some(journal) = self.read_journal(workspace_id)? else {
+            return Ok(JournalChanges {
+                acknowledged_seq: None,
+                entries: vec![],
+            });
+        };
+        journal.changes().map_err(Error::JournalError)
+    }
+
+    /// Marks the operations up to `seq` as synced and finishes the pending sync.
+    pub fn acknowledge_journal(&self, workspace_id: &str, seq: i64) -> Result<(), Error> {
+        let journal = self.open_journal(workspace_id)?;
+        journal.acknowledge(seq).map_err(Error::JournalError)
+    }
+
+    /// Records a sync before its changes are sent to the server.
+    pub fn begin_sync(&self, workspace_id: &str, pending_sync: &PendingSync) -> Result<(), Error> {
+        let journal = self.open_journal(workspace_id)?;
+        journal
+            .begin_sync(pending_sync)
+            .map_err(Error::JournalError)
+    }
+
+    /// Returns the sync that was begun but not finished, if any.
+    pub fn get_pending_sync(&self, workspace_id: &str) -> Result<Option<PendingSync>, Error> {
+        let Some(journal) = self.read_journal(workspace_id)? else {
+            return Ok(None);
+        };
+        journal.pending_sync().map_err(Error::JournalError)
+    }
+
+    /// Rolls back a pending sync that the server never applied.
+    pub fn discard_pending_sync(&self, workspace_id: &str) -> Result<(), Error> {
+        let journal = self.open_journal(workspace_id)?;
+        journal.discard_pending_sync().map_err(Error::JournalError)
+    }
+
+    /// Records the delete marker files under `dir_path` as deleted paths, removing the
+    /// markers if `remove` is set.
+    fn import_delete_markers(
+        index: &WorkspaceIndex,
+        dir_path: PathBuf,
+        path: &mut Vec<String>,
+        remove: bool,
+    ) -> Result<(), Error> {
+        let entries = std::fs::read_dir(dir_path).map_err(Error::IOError)?;
+        for entry in entries {
+            let entry = entry.map_err(Error::IOError)?;
+            let file_name = entry.file_name().to_str().unwrap().to_string();
+            if let Some(deleted_name) = file_name.strip_prefix(".sagitta.delete.") {
+                path.push(deleted_name.to_string());
+                index
+                    .upsert(&IndexEntry::new(path.clone(), IndexState::Deleted))
+                    .map_err(Error::IndexError)?;
+                path.pop();
+                if remove {
+                    std::fs::remove_file(entry.path()).map_err(Error::IOError)?;
+                }
+            } else if entry.file_type().map_err(Error::IOError)?.is_dir() {
+                path.push(file_name);
+                Self::import_delete_markers(index, entry.path(), path, remove)?;
+                path.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Records that the cow file or symlink at `path` was written.
+    fn record_cow_write(&self, workspace_id: &str, path: &[String]) -> Result<(), Error> {
+        let index = self.open_index(workspace_id)?;
+        let entry = index.get(path).map_err(Error::IndexError)?;
+        let entry = match entry {
+            Some(entry) if entry.state != IndexState::Deleted => IndexEntry {
+                mode: None,
+                modified_at: None,
+                size: None,
+                ..entry
+            },
+            // a deleted path is one that was synced
+            Some(entry) => IndexEntry::new(entry.path, IndexState::Modified),
+            None => IndexEntry::new(path.to_vec(), IndexState::Added),
+        };
+        index.upsert(&entry).map_err(Error::IndexError)
+    }
+
+    /// Records that the cow file or symlink at `path` was copied from its synced version
+    /// rather than newly added.
+    pub fn record_cow_copy(&self, workspace_id: &str, path: &[String]) -> Result<(), Error> {
+        let index = self.open_index(workspace_id)?;
+        let entry = index.get(path).map_err(Error::IndexError)?;
+        match entry {
+            Some(entry) if entry.state != IndexState::Added => Ok(()),
+            _ => index
+                .upsert(&IndexEntry::new(path.to_vec(), IndexState::Modified))
+                .map_err(Error::IndexError),
+        }
+    }
+
+    /// Moves the index entry of `old_path` to `new_path`. A synced path is deleted by the
+    /// move; one that only exists locally is forgotten.
+    fn move_index_entry(
+        old_index: &WorkspaceIndex,
+        new_index: &WorkspaceIndex,
+        same_workspace: bool,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), Error> {
+        let old_entry = old_index.get(old_path).map_err(Error::IndexError)?;
+        if let Some(entry) = old_entry
+            .as_ref()
+            .filter(|entry| entry.state == IndexState::Deleted)
+        {
+            // stays deleted at both paths
+            return new_index
+                .upsert(&IndexEntry {
+                    path: new_path.to_vec(),
+                    ..entry.clone()
+                })
+                .map_err(Error::IndexError);
+        }
+        let (state, renamed_from) = match &old_entry {
+            _ if !same_workspace => (IndexState::Added, None),
+            Some(entry) if entry.state == IndexState::Added => (IndexState::Added, None),
+            Some(entry) if entry.state == IndexState::Renamed => {
+                (IndexState::Renamed, entry.renamed_from.clone())
+            }
+            _ => (
+                IndexState::Renamed,
+                Some(Self::synced_path(old_index, old_path)?),
+            ),
+        };
+        let old_is_synced = !matches!(
+            old_entry.as_ref().map(|entry| entry.state),
+            Some(IndexState::Added | IndexState::Renamed)
+        );
+        let new_entry = match old_entry {
+            Some(entry) => IndexEntry {
+                path: new_path.to_vec(),
+                state,
+                renamed_from,
+                ..entry
+            },
+            None => IndexEntry {
+                renamed_from,
+                ..IndexEntry::new(new_path.to_vec(), state)
+            },
+        };
+        if old_is_synced {
+            old_index
+                .upsert(&IndexEntry::new(old_path.to_vec(), IndexState::Deleted))
+                .map_err(Error::IndexError)?;
+        } else {
+            old_index.remove(old_path).map_err(Error::IndexError)?;
+        }
+        new_index.upsert(&new_entry).map_err(Error::IndexError)
+    }
+
+    /// Returns the path `path` has on the remote, following the renames of it or of its
+    /// ancestors that are not synced yet.
+    fn synced_path(index: &WorkspaceIndex, path: &[String]) -> Result<Vec<String>, Error> {
+        for i in (1..=path.len()).rev() {
+            let entry = index.get(&path[..i]).map_err(Error::IndexError)?;
+            if let Some(IndexEntry {
+                state: IndexState::Renamed,
+                renamed_from: Some(renamed_from),
+                ..
+            }) = entry
+            {
+                let mut synced_path = renamed_from;
+                synced_path.extend_from_slice(&path[i..]);
+                return Ok(synced_path);
+            }
+        }
+        Ok(path.to_vec())
+    }
+
+    /// Returns the path `path` has on the remote, for reading what was synced there.
+    pub fn get_synced_path(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+    ) -> Result<Vec<String>, Error> {
+        let index = self.read_index(workspace_id)?;
+        Self::synced_path(&index, path)
+    }
+
+    pub fn get_index_entry(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+    ) -> Result<Option<IndexEntry>, Error> {
+        let index = self.read_index(workspace_id)?;
+        index.get(path).map_err(Error::IndexError)
+    }
+
+    /// Lists the paths changed since the last sync.
+    pub fn list_index_entries(&self, workspace_id: &str) -> Result<Vec<IndexEntry>, Error> {
+        let index = self.read_index(workspace_id)?;
+        index.list().map_err(Error::IndexError)
+    }
+
+    /// Returns `true` if `path` or one of its ancestors was deleted in the workspace.
+    pub fn check_cow_deleted(&self, workspace_id: &str, path: &[String]) -> Result<bool, Error> {
+        let index = self.read_index(workspace_id)?;
+        for i in 1..=path.len() {
+            let entry = index.get(&path[..i]).map_err(Error::IndexError)?;
+            if entry.is_some_and(|entry| entry.state == IndexState::Deleted) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Records `path` as a reference to an existing blob, replacing any cow file there.
+    pub fn create_cow_blob_ref(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+        blob_ref: &CowBlobRef,
+    ) -> Result<(), Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        if cow_path.symlink_metadata().is_ok() {
+            std::fs::remove_file(&cow_path).map_err(Error::IOError)?;
+        }
+        let index = self.open_index(workspace_id)?;
+        let (state, renamed_from) = match index.get(path).map_err(Error::IndexError)? {
+            Some(entry) if entry.state != IndexState::Deleted => (entry.state, entry.renamed_from),
+            _ => (IndexState::Modified, None),
+        };
+        index
+            .upsert(&IndexEntry {
+                path: path.to_vec(),
+                state,
+                renamed_from,
+                mode: Some(blob_ref.mode),
+                modified_at: Some(blob_ref.modified_at),
+                size: Some(blob_ref.size),
+                blob_id: Some(blob_ref.blob_id.clone()),
+            })
+            .map_err(Error::IndexError)
+    }
+
+    /// Returns the blob `path` refers to, unless it has a cow file or symlink.
+    pub fn get_cow_blob_ref(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+    ) -> Result<Option<CowBlobRef>, Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        if cow_path.symlink_metadata().is_ok() {
+            return Ok(None);
+        }
+        let index = self.read_index(workspace_id)?;
+        let Some(entry) = index.get(path).map_err(Error::IndexError)? else {
+            return Ok(None);
+        };
+        if entry.state == IndexState::Deleted {
+            return Ok(None);
         }
+        let (Some(blob_id), Some(size), Some(mode), Some(modified_at)) =
+            (entry.blob_id, entry.size, entry.mode, entry.modified_at)
+        else {
+            return Ok(None);
+        };
+        Ok(Some(CowBlobRef {
+            blob_id,
+            size,
+            mode,
+            modified_at,
+        }))
+    }
+
+    pub fn get_cow_xattrs(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+    ) -> Result<BTreeMap<String, Vec<u8>>, Error> {
+        let index = self.read_index(workspace_id)?;
+        index.get_xattrs(path).map_err(Error::IndexError)
+    }
+
+    pub fn set_cow_xattr(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+        name: &str,
+        value: &[u8],
+    ) -> Result<(), Error> {
+        let index = self.open_index(workspace_id)?;
+        index
+            .set_xattr(path, name, value)
+            .map_err(Error::IndexError)
+    }
+
+    /// Returns `false` if the attribute was not set.
+    pub fn remove_cow_xattr(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+        name: &str,
+    ) -> Result<bool, Error> {
+        let index = self.open_index(workspace_id)?;
+        index.remove_xattr(path, name).map_err(Error::IndexError)
+    }
+
+    /// Drops the extended attributes of `path` itself, leaving those of the paths under it.
+    fn remove_cow_xattrs(index: &WorkspaceIndex, path: &[String]) -> Result<(), Error> {
+        for name in index.get_xattrs(path).map_err(Error::IndexError)?.keys() {
+            index.remove_xattr(path, name).map_err(Error::IndexError)?;
+        }
+        Ok(())
     }
 
     pub fn create_cow_file(
@@ -71,15 +548,7 @@ impl LocalSystemWorkspaceManager {
             permissions.set_mode(mode);
             std::fs::set_permissions(&cow_path, permissions).map_err(Error::IOError)?;
         }
-        {
-            let mut cow_path = cow_path.clone();
-            cow_path.pop();
-            cow_path.push(format!(".sagitta.delete.{}", path.last().unwrap()));
-            if cow_path.exists() {
-                std::fs::remove_file(&cow_path).map_err(Error::IOError)?;
-            }
-        }
-        Ok(())
+        self.record_cow_write(workspace_id, path)
     }
 
     pub fn change_cow_file_mode(
@@ -101,31 +570,113 @@ impl LocalSystemWorkspaceManager {
         Ok(())
     }
 
-    pub fn create_cow_dir(&self, workspace_id: &str, path: &[String]) -> Result<(), Error> {
+    /// Creates a cow dir. With a `mode` the dir is a new one and is recorded in the index,
+    /// which keeps its mode; the cow dir itself stays writable. Without one it is a local
+    /// copy of a synced dir.
+    pub fn create_cow_dir(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+        mode: Option<u32>,
+    ) -> Result<(), Error> {
         let workspace_path = self.base_path.join(workspace_id);
         let mut cow_path = workspace_path.join("cow");
         for p in path {
             cow_path = cow_path.join(p);
         }
         std::fs::create_dir_all(&cow_path).map_err(Error::IOError)?;
-        {
-            let mut cow_path = cow_path.clone();
-            cow_path.pop();
-            cow_path.push(format!(".sagitta.delete.{}", path.last().unwrap()));
-            if cow_path.exists() {
-                std::fs::remove_file(&cow_path).map_err(Error::IOError)?;
+        let index = self.open_index(workspace_id)?;
+        let entry = index.get(path).map_err(Error::IndexError)?;
+        match (entry, mode) {
+            (Some(entry), None) if entry.state == IndexState::Deleted => {
+                index.remove(path).map_err(Error::IndexError)?;
+            }
+            (_, None) => {}
+            (entry, Some(mode)) => {
+                // a deleted path is one that was synced
+                let state = match entry {
+                    Some(entry) if entry.state == IndexState::Deleted => IndexState::Modified,
+                    Some(entry) => entry.state,
+                    None => IndexState::Added,
+                };
+                index
+                    .upsert(&IndexEntry {
+                        mode: Some(mode & 0o7777),
+                        ..IndexEntry::new(path.to_vec(), state)
+                    })
+                    .map_err(Error::IndexError)?;
             }
         }
         Ok(())
     }
 
+    /// Changes the mode of a dir, recorded in the index like that of a new dir.
+    pub fn set_cow_dir_mode(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+        mode: u32,
+    ) -> Result<(), Error> {
+        self.create_cow_dir(workspace_id, path, None)?;
+        let index = self.open_index(workspace_id)?;
+        let entry = match index.get(path).map_err(Error::IndexError)? {
+            Some(entry) => IndexEntry {
+                mode: Some(mode & 0o7777),
+                ..entry
+            },
+            None => IndexEntry {
+                mode: Some(mode & 0o7777),
+                ..IndexEntry::new(path.to_vec(), IndexState::Modified)
+            },
+        };
+        index.upsert(&entry).map_err(Error::IndexError)
+    }
+
+    pub fn create_cow_symlink(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+        target: &str,
+    ) -> Result<(), Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        std::fs::create_dir_all(cow_path.parent().unwrap()).map_err(Error::IOError)?;
+        if cow_path.symlink_metadata().is_ok() {
+            std::fs::remove_file(&cow_path).map_err(Error::IOError)?;
+        }
+        symlink(target, &cow_path).map_err(Error::IOError)?;
+        self.record_cow_write(workspace_id, path)
+    }
+
+    pub fn read_cow_symlink(&self, workspace_id: &str, path: &[String]) -> Result<String, Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        let target = std::fs::read_link(&cow_path).map_err(Error::IOError)?;
+        Ok(target.to_str().unwrap().to_string())
+    }
+
+    pub fn check_cow_symlink(&self, workspace_id: &str, path: &[String]) -> Result<bool, Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        Ok(cow_path.is_symlink())
+    }
+
     pub fn check_cow_file(&self, workspace_id: &str, path: &[String]) -> Result<bool, Error> {
         let workspace_path = self.base_path.join(workspace_id);
         let mut cow_path = workspace_path.join("cow");
         for p in path {
             cow_path = cow_path.join(p);
         }
-        Ok(cow_path.exists() && cow_path.is_file())
+        Ok(cow_path.exists() && cow_path.is_file() && !cow_path.is_symlink())
     }
 
     pub fn get_len_ctime_mtime_and_perm_of_cow_file(
@@ -147,13 +698,63 @@ impl LocalSystemWorkspaceManager {
         Ok((len, ctime, mtime, mode as i64))
     }
 
+    pub fn set_cow_file_mtime(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+        mtime: SystemTime,
+    ) -> Result<(), Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        let file = std::fs::File::open(cow_path).map_err(Error::IOError)?;
+        file.set_modified(mtime).map_err(Error::IOError)?;
+        Ok(())
+    }
+
+    pub fn get_ino_and_nlink_of_cow_file(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+    ) -> Result<(u64, u64), Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        let metadata = std::fs::symlink_metadata(cow_path).map_err(Error::IOError)?;
+        Ok((metadata.ino(), metadata.nlink()))
+    }
+
+    pub fn link_cow_file(
+        &self,
+        workspace_id: &str,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut old_cow_path = workspace_path.join("cow");
+        for p in old_path {
+            old_cow_path = old_cow_path.join(p);
+        }
+        let mut new_cow_path = workspace_path.join("cow");
+        for p in new_path {
+            new_cow_path = new_cow_path.join(p);
+        }
+        std::fs::create_dir_all(new_cow_path.parent().unwrap()).map_err(Error::IOError)?;
+        std::fs::hard_link(&old_cow_path, &new_cow_path).map_err(Error::IOError)?;
+        self.record_cow_write(workspace_id, new_path)
+    }
+
     pub fn check_cow_dir(&self, workspace_id: &str, path: &[String]) -> Result<bool, Error> {
         let workspace_path = self.base_path.join(workspace_id);
         let mut cow_path = workspace_path.join("cow");
         for p in path {
             cow_path = cow_path.join(p);
         }
-        Ok(cow_path.exists() && cow_path.is_dir())
+        Ok(cow_path.exists() && cow_path.is_dir() && !cow_path.is_symlink())
     }
 
     pub fn read_cow_dir(
@@ -166,21 +767,42 @@ impl LocalSystemWorkspaceManager {
         for p in path {
             cow_path = cow_path.join(p);
         }
-        let entries = std::fs::read_dir(&cow_path).map_err(Error::IOError)?;
+        let index = self.read_index(workspace_id)?;
         let mut result = Vec::new();
+
+        // files that only refer to a blob have no cow file
+        for entry in index.list().map_err(Error::IndexError)? {
+            let is_child = entry.path.len() == path.len() + 1 && entry.path.starts_with(path);
+            if is_child && entry.state != IndexState::Deleted && entry.blob_id.is_some() {
+                let name = entry.path.last().unwrap().clone();
+                if cow_path.join(&name).symlink_metadata().is_err() {
+                    result.push(ReadCowDirItem {
+                        name,
+                        is_symlink: false,
+                    });
+                }
+            }
+        }
+
+        let entries = std::fs::read_dir(&cow_path).map_err(Error::IOError)?;
         for entry in entries {
             let entry = entry.map_err(Error::IOError)?;
             let file_name = entry.file_name();
             let file_name = file_name.to_str().unwrap().to_string();
-            if file_name.starts_with(".sagitta.delete.") {
+            if index.has_marker_files && Self::is_marker_file_name(&file_name) {
                 continue;
-            } else {
-                let delete_path = cow_path.join(format!(".sagitta.delete.{}", file_name));
-                if delete_path.exists() {
-                    continue;
-                }
             }
-            let item = ReadCowDirItem { name: file_name };
+            let mut file_path = path.to_vec();
+            file_path.push(file_name.clone());
+            let index_entry = index.get(&file_path).map_err(Error::IndexError)?;
+            if index_entry.is_some_and(|entry| entry.state == IndexState::Deleted) {
+                continue;
+            }
+            let is_symlink = entry.file_type().map_err(Error::IOError)?.is_symlink();
+            let item = ReadCowDirItem {
+                name: file_name,
+                is_symlink,
+            };
             result.push(item);
         }
         Ok(result)
@@ -210,6 +832,97 @@ impl LocalSystemWorkspaceManager {
         Ok(data)
     }
 
+    /// Opens a cow file. The returned file keeps working if the cow file is later
+    /// renamed or archived.
+    pub fn open_cow_file(
+        &self,
+        workspace_id: &str,
+        path: &[String],
+        write: bool,
+        append: bool,
+    ) -> Result<std::fs::File, Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(write)
+            .append(append)
+            .open(cow_path)
+            .map_err(Error::IOError)
+    }
+
+    /// Writes `blob` to a file that is unlinked right away, so that it is read from disk
+    /// instead of memory and removed when the last descriptor of it is closed.
+    pub fn spill_blob(&self, blob_id: &str, blob: &[u8]) -> Result<std::fs::File, Error> {
+        let tmp_path = self.base_path.join("tmp");
+        std::fs::create_dir_all(&tmp_path).map_err(Error::IOError)?;
+        let spill_path = tmp_path.join(format!("{}.{}", blob_id, std::process::id()));
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&spill_path)
+            .map_err(Error::IOError)?;
+        std::fs::remove_file(&spill_path).map_err(Error::IOError)?;
+        file.write_all(blob).map_err(Error::IOError)?;
+        Ok(file)
+    }
+
+    /// Allocates space like `fallocate(2)`, on the file of an open handle.
+    pub fn fallocate_file(
+        file: &std::fs::File,
+        mode: i32,
+        offset: i64,
+        length: i64,
+    ) -> Result<(), Error> {
+        let res = unsafe { libc::fallocate(file.as_raw_fd(), mode, offset, length) };
+        if res != 0 {
+            return Err(Error::IOError(std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    /// Repositions like `lseek(2)`, which also answers `SEEK_DATA` and `SEEK_HOLE`, on the
+    /// file of an open handle.
+    pub fn seek_file(file: &std::fs::File, offset: i64, whence: i32) -> Result<i64, Error> {
+        let res = unsafe { libc::lseek(file.as_raw_fd(), offset, whence) };
+        if res < 0 {
+            return Err(Error::IOError(std::io::Error::last_os_error()));
+        }
+        Ok(res)
+    }
+
+    /// Copies with `copy_file_range(2)` between the files of open handles, so the local
+    /// file system may share the extents. Returns the number of bytes copied.
+    pub fn copy_file_range(
+        file_in: &std::fs::File,
+        offset_in: i64,
+        file_out: &std::fs::File,
+        offset_out: i64,
+        len: u64,
+    ) -> Result<u64, Error> {
+        let mut offset_in = offset_in;
+        let mut offset_out = offset_out;
+        let res = unsafe {
+            libc::copy_file_range(
+                file_in.as_raw_fd(),
+                &mut offset_in,
+                file_out.as_raw_fd(),
+                &mut offset_out,
+                len as usize,
+                0,
+            )
+        };
+        if res < 0 {
+            return Err(Error::IOError(std::io::Error::last_os_error()));
+        }
+        Ok(res as u64)
+    }
+
     pub fn write_cow_file(
         &self,
         workspace_id: &str,
@@ -229,14 +942,6 @@ impl LocalSystemWorkspaceManager {
         file.seek(std::io::SeekFrom::Start(offset as u64))
             .map_err(Error::IOError)?;
         file.write_all(data).map_err(Error::IOError)?;
-        {
-            let mut cow_path = cow_path.clone();
-            cow_path.pop();
-            cow_path.push(format!(".sagitta.delete.{}", path.last().unwrap()));
-            if cow_path.exists() {
-                std::fs::remove_file(&cow_path).map_err(Error::IOError)?;
-            }
-        }
         Ok(())
     }
 
@@ -246,58 +951,165 @@ impl LocalSystemWorkspaceManager {
         for p in path {
             cow_path = cow_path.join(p);
         }
-        if cow_path.exists() {
+        if cow_path.symlink_metadata().is_ok() {
             std::fs::remove_file(&cow_path).map_err(Error::IOError)?;
         }
-        {
-            let mut cow_path = cow_path.clone();
-            cow_path.pop();
-            cow_path.push(format!(".sagitta.delete.{}", path.last().unwrap()));
-            std::fs::write(&cow_path, "").map_err(Error::IOError)?;
-        }
-        Ok(())
+        let index = self.open_index(workspace_id)?;
+        Self::remove_cow_xattrs(&index, path)?;
+        index
+            .upsert(&IndexEntry::new(path.to_vec(), IndexState::Deleted))
+            .map_err(Error::IndexError)
     }
 
+    /// Deletes a dir and everything under it. The entries under the dir are covered by
+    /// the deletion of the dir itself, so they are dropped from the index.
     pub fn delete_cow_dir(&self, workspace_id: &str, path: &[String]) -> Result<(), Error> {
         let workspace_path = self.base_path.join(workspace_id);
         let mut cow_path = workspace_path.join("cow");
         for p in path {
             cow_path = cow_path.join(p);
         }
-        {
-            let mut cow_path = cow_path.clone();
-            cow_path.pop();
-            cow_path.push(format!(".sagitta.delete.{}", path.last().unwrap()));
-            std::fs::write(&cow_path, "").map_err(Error::IOError)?;
+        if cow_path.is_dir() {
+            std::fs::remove_dir_all(&cow_path).map_err(Error::IOError)?;
         }
-        Ok(())
+        let index = self.open_index(workspace_id)?;
+        index.take_xattrs_under(path).map_err(Error::IndexError)?;
+        for entry in index.list().map_err(Error::IndexError)? {
+            if entry.path.len() > path.len() && entry.path.starts_with(path) {
+                index.remove(&entry.path).map_err(Error::IndexError)?;
+            }
+        }
+        index
+            .upsert(&IndexEntry::new(path.to_vec(), IndexState::Deleted))
+            .map_err(Error::IndexError)
     }
 
     pub fn list_cow_files(&self, workspace_id: &str) -> Result<Vec<(Vec<String>, i64)>, Error> {
+        self.list_cow_files_under(workspace_id, &[vec![]])
+    }
+
+    /// Lists the cow files at or under `roots`, as [`Self::list_cow_files`] does for the
+    /// whole workspace.
+    pub fn list_cow_files_under(
+        &self,
+        workspace_id: &str,
+        roots: &[Vec<String>],
+    ) -> Result<Vec<(Vec<String>, i64)>, Error> {
         let mut res = vec![];
         let workspace_path = self.base_path.join(workspace_id);
-        let cow_path = workspace_path.join("cow");
-        Self::list_cow_files_sub(cow_path, &mut [], &mut res).unwrap();
+        let skip_markers = self.read_index(workspace_id)?.has_marker_files;
+        for root in Self::outermost_paths(roots) {
+            let mut cow_path = workspace_path.join("cow");
+            for p in &root {
+                cow_path = cow_path.join(p);
+            }
+            let Ok(metadata) = cow_path.symlink_metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                Self::list_cow_files_sub(cow_path, &root, skip_markers, &mut res)?;
+            } else if !(skip_markers
+                && root
+                    .last()
+                    .is_some_and(|name| Self::is_marker_file_name(name)))
+            {
+                res.push((root, metadata.permissions().mode() as i64));
+            }
+        }
+        Ok(res)
+    }
+
+    /// Lists the cow dirs at or under `roots`, parents before their children.
+    pub fn list_cow_dirs_under(
+        &self,
+        workspace_id: &str,
+        roots: &[Vec<String>],
+    ) -> Result<Vec<Vec<String>>, Error> {
+        let mut res = vec![];
+        let workspace_path = self.base_path.join(workspace_id);
+        for root in Self::outermost_paths(roots) {
+            let mut cow_path = workspace_path.join("cow");
+            for p in &root {
+                cow_path = cow_path.join(p);
+            }
+            if !cow_path
+                .symlink_metadata()
+                .is_ok_and(|metadata| metadata.is_dir())
+            {
+                continue;
+            }
+            if !root.is_empty() {
+                res.push(root.clone());
+            }
+            Self::list_cow_dirs_sub(cow_path, &root, &mut res)?;
+        }
+        res.sort();
         Ok(res)
     }
 
+    /// Drops the paths that are under another one of `paths`.
+    fn outermost_paths(paths: &[Vec<String>]) -> Vec<Vec<String>> {
+        let mut paths = paths.to_vec();
+        paths.sort();
+        paths.dedup();
+        let mut res: Vec<Vec<String>> = vec![];
+        for path in paths {
+            if !res.iter().any(|root| path.starts_with(root)) {
+                res.push(path);
+            }
+        }
+        res
+    }
+
+    /// Lists the cow dirs of a workspace, parents before their children.
+    pub fn list_cow_dirs(&self, workspace_id: &str) -> Result<Vec<Vec<String>>, Error> {
+        self.list_cow_dirs_under(workspace_id, &[vec![]])
+    }
+
+    fn list_cow_dirs_sub(
+        dir_path: PathBuf,
+        base_path: &[String],
+        res: &mut Vec<Vec<String>>,
+    ) -> Result<(), Error> {
+        let entries = std::fs::read_dir(dir_path).map_err(Error::IOError)?;
+        for entry in entries {
+            let entry = entry.map_err(Error::IOError)?;
+            let file_name = entry.file_name();
+            let file_name = file_name.to_str().unwrap().to_string();
+            if entry.file_type().map_err(Error::IOError)?.is_dir() {
+                let mut path = base_path.to_vec();
+                path.push(file_name);
+                res.push(path.clone());
+                Self::list_cow_dirs_sub(entry.path(), &path, res)?;
+            }
+        }
+        Ok(())
+    }
+
     fn list_cow_files_sub(
         file_path: PathBuf,
-        base_path: &mut [String],
+        base_path: &[String],
+        skip_markers: bool,
         res: &mut Vec<(Vec<String>, i64)>,
     ) -> Result<(), Error> {
         let entries = std::fs::read_dir(file_path).map_err(Error::IOError)?;
         for entry in entries {
             let entry = entry.map_err(Error::IOError)?;
-            let file_name = entry.file_name();
-            let file_name = file_name.to_str().unwrap().to_string();
+            let file_name = entry
+                .file_name()
+                .into_string()
+                .map_err(|_| Error::IOError(std::io::ErrorKind::InvalidData.into()))?;
+            if skip_markers && Self::is_marker_file_name(&file_name) {
+                continue;
+            }
             let mut base_path = base_path.to_vec();
             base_path.push(file_name);
             let metadata = entry.metadata().map_err(Error::IOError)?;
             let permissions = metadata.permissions();
             let mode = permissions.mode();
-            if entry.path().is_dir() {
-                Self::list_cow_files_sub(entry.path(), &mut base_path, res)?;
+            // do not follow symlinks, they are synced as links
+            if metadata.is_dir() {
+                Self::list_cow_files_sub(entry.path(), &base_path, skip_markers, res)?;
             } else {
                 res.push((base_path.clone(), mode as i64));
             }
@@ -305,21 +1117,19 @@ impl LocalSystemWorkspaceManager {
         Ok(())
     }
 
+    /// Moves the cow files of the synced `paths` into `cow-<sync_id>`. Running it again for
+    /// the same sync skips the paths that were already moved.
     pub fn archive_cow_dir(
         &self,
         workspace_id: &str,
+        sync_id: &str,
         paths: &Vec<Vec<String>>,
     ) -> Result<(), Error> {
-        let now = SystemTime::now();
         let workspace_path = self.base_path.join(workspace_id);
         let cow_path = workspace_path.join("cow");
-        let archive_path = workspace_path.join(format!(
-            "cow-{}",
-            now.duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-        ));
+        let archive_path = workspace_path.join(format!("cow-{}", sync_id));
 
+        let index = self.open_index(workspace_id)?;
         for path in paths {
             let mut cow_path = cow_path.clone();
             for p in path {
@@ -329,13 +1139,41 @@ impl LocalSystemWorkspaceManager {
             for p in path {
                 archive_path = archive_path.join(p);
             }
+            // the extended attributes were synced along with the path
+            index.remove(path).map_err(Error::IndexError)?;
+            Self::remove_cow_xattrs(&index, path)?;
             std::fs::create_dir_all(archive_path.parent().unwrap()).map_err(Error::IOError)?;
-            std::fs::rename(cow_path, archive_path).map_err(Error::IOError)?;
+            // deleted paths and blob references have no cow file, and a dir stays as it
+            // may still hold files that were not synced
+            if cow_path
+                .symlink_metadata()
+                .is_ok_and(|metadata| !metadata.is_dir())
+            {
+                std::fs::rename(cow_path, archive_path).map_err(Error::IOError)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Removes a cow dir along with its extended attributes if it holds nothing else.
+    /// Returns `false` if it is not empty.
+    pub fn remove_empty_cow_dir(&self, workspace_id: &str, path: &[String]) -> Result<bool, Error> {
+        let workspace_path = self.base_path.join(workspace_id);
+        let mut cow_path = workspace_path.join("cow");
+        for p in path {
+            cow_path = cow_path.join(p);
+        }
+        let mut entries = std::fs::read_dir(&cow_path).map_err(Error::IOError)?;
+        if entries.next().is_some() {
+            return Ok(false);
+        }
+        std::fs::remove_dir(&cow_path).map_err(Error::IOError)?;
+        let index = self.open_index(workspace_id)?;
+        Self::remove_cow_xattrs(&index, path)?;
+        Ok(true)
+    }
+
     pub fn rename_cow_file(
         &self,
         old_workspace_id: &str,
@@ -354,7 +1192,47 @@ impl LocalSystemWorkspaceManager {
             new_cow_path = new_cow_path.join(p);
         }
         std::fs::create_dir_all(new_cow_path.parent().unwrap()).map_err(Error::IOError)?;
-        std::fs::rename(&old_cow_path, &new_cow_path).map_err(Error::IOError)?;
-        Ok(())
+        let is_dir = old_cow_path.is_dir() && !old_cow_path.is_symlink();
+        if old_cow_path.symlink_metadata().is_ok() {
+            std::fs::rename(&old_cow_path, &new_cow_path).map_err(Error::IOError)?;
+        } else if self.get_cow_blob_ref(old_workspace_id, old_path)?.is_some() {
+            if new_cow_path.symlink_metadata().is_ok() {
+                std::fs::remove_file(&new_cow_path).map_err(Error::IOError)?;
+            }
+        } else {
+            return Err(Error::IOError(std::io::Error::from(
+                std::io::ErrorKind::NotFound,
+            )));
+        }
+
+        let old_index = self.open_index(old_workspace_id)?;
+        let new_index = self.open_index(new_workspace_id)?;
+        // the extended attributes of the replaced path go, those of the moved ones follow them
+        new_index
+            .take_xattrs_under(new_path)
+            .map_err(Error::IndexError)?;
+        for (path, name, value) in old_index
+            .take_xattrs_under(old_path)
+            .map_err(Error::IndexError)?
+        {
+            let mut path_to = new_path.to_vec();
+            path_to.extend_from_slice(&path[old_path.len()..]);
+            new_index
+                .set_xattr(&path_to, &name, &value)
+                .map_err(Error::IndexError)?;
+        }
+        let same_workspace = old_workspace_id == new_workspace_id;
+        // the entries under a dir move with it
+        if is_dir {
+            for entry in old_index.list().map_err(Error::IndexError)? {
+                if entry.path.len() <= old_path.len() || !entry.path.starts_with(old_path) {
+                    continue;
+                }
+                let mut path = new_path.to_vec();
+                path.extend_from_slice(&entry.path[old_path.len()..]);
+                Self::move_index_entry(&old_index, &new_index, same_workspace, &entry.path, &path)?;
+            }
+        }
+        Self::move_index_entry(&old_index, &new_index, same_workspace, old_path, new_path)
     }
 }
@@ -1,14 +1,27 @@
 use sagitta_remote_api_schema::v2::{
+    blame::{V2BlameRequest, V2BlameResponse},
+    cherry_pick::{V2CherryPickRequest, V2CherryPickResponse},
     commit::{V2CommitRequest, V2CommitResponse},
+    create_branch::{V2CreateBranchRequest, V2CreateBranchResponse},
+    create_tag::{V2CreateTagRequest, V2CreateTagResponse},
     create_workspace::{V2CreateWorkspaceRequest, V2CreateWorkspaceResponse},
+    delete_tag::{V2DeleteTagRequest, V2DeleteTagResponse},
     get_attr::{V2GetAttrRequest, V2GetAttrResponse},
+    get_branches::{V2GetBranchesRequest, V2GetBranchesResponse},
     get_file_blob_id::{V2GetFileBlobIdRequest, V2GetFileBlobIdResponse},
+    get_file_history::{V2GetFileHistoryRequest, V2GetFileHistoryResponse},
+    get_file_state::{V2GetFileStateRequest, V2GetFileStateResponse},
+    get_sync_state::{V2GetSyncStateRequest, V2GetSyncStateResponse},
+    get_tags::{V2GetTagsRequest, V2GetTagsResponse},
     get_workspace_id_from_name::{
         V2GetWorkspaceIdFromNameRequest, V2GetWorkspaceIdFromNameResponse,
     },
     get_workspaces::{V2GetWorkspacesRequest, V2GetWorkspacesResponse},
+    get_xattrs::{V2GetXattrsRequest, V2GetXattrsResponse},
     read_blob::{V2ReadBlobRequest, V2ReadBlobResponse},
     read_dir::{V2ReadDirRequest, V2ReadDirResponse},
+    revert_commit::{V2RevertCommitRequest, V2RevertCommitResponse},
+    stats::{V2StatsRequest, V2StatsResponse},
     sync_files_with_workspace::{
         V2SyncFilesWithWorkspaceRequest, V2SyncFilesWithWorkspaceResponse,
     },
@@ -74,6 +87,45 @@ impl SagittaApiClient {
         Ok(get_file_blob_id_res)
     }
 
+    pub fn v2_get_xattrs(
+        &self,
+        request: V2GetXattrsRequest,
+    ) -> Result<V2GetXattrsResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/get-xattrs", self.base_url);
+        let get_xattrs_res: V2GetXattrsResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(get_xattrs_res)
+    }
+
+    pub fn v2_get_file_state(
+        &self,
+        request: V2GetFileStateRequest,
+    ) -> Result<V2GetFileStateResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/get-file-state", self.base_url);
+        let get_file_state_res: V2GetFileStateResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(get_file_state_res)
+    }
+
+    pub fn v2_stats(
+        &self,
+        request: V2StatsRequest,
+    ) -> Result<V2StatsResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/stats", self.base_url);
+        let stats_res: V2StatsResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(stats_res)
+    }
+
     pub fn v2_read_blob_request(
         &self,
         request: V2ReadBlobRequest,
@@ -152,6 +204,19 @@ impl SagittaApiClient {
         Ok(sync_files_with_workspace_res)
     }
 
+    pub fn v2_get_sync_state(
+        &self,
+        request: V2GetSyncStateRequest,
+    ) -> Result<V2GetSyncStateResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/get-sync-state", self.base_url);
+        let get_sync_state_res: V2GetSyncStateResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(get_sync_state_res)
+    }
+
     pub fn v2_get_workspace_id_from_name(
         &self,
         request: V2GetWorkspaceIdFromNameRequest,
@@ -164,4 +229,121 @@ impl SagittaApiClient {
             .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
         Ok(get_workspace_id_from_name_res)
     }
+
+    pub fn v2_revert_commit(
+        &self,
+        request: V2RevertCommitRequest,
+    ) -> Result<V2RevertCommitResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/revert-commit", self.base_url);
+        let revert_commit_res: V2RevertCommitResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(revert_commit_res)
+    }
+
+    pub fn v2_create_tag(
+        &self,
+        request: V2CreateTagRequest,
+    ) -> Result<V2CreateTagResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/create-tag", self.base_url);
+        let create_tag_res: V2CreateTagResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(create_tag_res)
+    }
+
+    pub fn v2_get_tags(
+        &self,
+        request: V2GetTagsRequest,
+    ) -> Result<V2GetTagsResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/get-tags", self.base_url);
+        let get_tags_res: V2GetTagsResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(get_tags_res)
+    }
+
+    pub fn v2_delete_tag(
+        &self,
+        request: V2DeleteTagRequest,
+    ) -> Result<V2DeleteTagResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/delete-tag", self.base_url);
+        let delete_tag_res: V2DeleteTagResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(delete_tag_res)
+    }
+
+    pub fn v2_create_branch(
+        &self,
+        request: V2CreateBranchRequest,
+    ) -> Result<V2CreateBranchResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/create-branch", self.base_url);
+        let create_branch_res: V2CreateBranchResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(create_branch_res)
+    }
+
+    pub fn v2_get_branches(
+        &self,
+        request: V2GetBranchesRequest,
+    ) -> Result<V2GetBranchesResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/get-branches", self.base_url);
+        let get_branches_res: V2GetBranchesResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(get_branches_res)
+    }
+
+    pub fn v2_cherry_pick(
+        &self,
+        request: V2CherryPickRequest,
+    ) -> Result<V2CherryPickResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/cherry-pick", self.base_url);
+        let cherry_pick_res: V2CherryPickResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(cherry_pick_res)
+    }
+
+    pub fn v2_get_file_history(
+        &self,
+        request: V2GetFileHistoryRequest,
+    ) -> Result<V2GetFileHistoryResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/get-file-history", self.base_url);
+        let get_file_history_res: V2GetFileHistoryResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(get_file_history_res)
+    }
+
+    pub fn v2_blame(
+        &self,
+        request: V2BlameRequest,
+    ) -> Result<V2BlameResponse, SagittaApiClientError> {
+        let url = format!("{}/v2/blame", self.base_url);
+        let blame_res: V2BlameResponse = ureq::post(&url)
+            .send_json(request)
+            .map_err(|e| SagittaApiClientError::Ureq(Box::new(e)))?
+            .into_json()
+            .map_err(|e| SagittaApiClientError::IO(Box::new(e)))?;
+        Ok(blame_res)
+    }
 }
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2BlameRequest {
+    pub branch_name: String,
+    pub path: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2BlameResponseLine {
+    pub commit_id: String,
+    pub commit_rank: i64,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2BlameResponse {
+    Ok {
+        lines: Vec<V2BlameResponseLine>,
+    },
+    NotFound,
+    /// A revision of the file is binary or too large to blame.
+    NotText {
+        commit_id: String,
+    },
+}
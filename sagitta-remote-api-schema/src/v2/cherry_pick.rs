@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2CherryPickRequest {
+    pub commit_rank: i64,
+    pub branch_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2CherryPickResponse {
+    Ok { commit_id: String, commit_rank: i64 },
+    NotFound,
+    BranchNotFound,
+}
@@ -3,10 +3,13 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct V2CommitRequest {
     pub workspace_id: String,
+    pub keep_workspace: bool,
+    pub paths: Option<Vec<Vec<String>>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum V2CommitResponse {
-    Ok,
-    Err,
+    Ok { commit_id: String, commit_rank: i64 },
+    Conflict { file_paths: Vec<String> },
+    NotFound,
 }
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2CreateBranchRequest {
+    pub branch_name: String,
+    pub source_branch_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2CreateBranchResponse {
+    Ok { commit_id: String, commit_rank: i64 },
+    AlreadyExists,
+    SourceNotFound,
+    InvalidName,
+    NameTaken,
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2CreateTagRequest {
+    pub tag_name: String,
+    pub commit_rank: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2CreateTagResponse {
+    Ok { tag_id: String },
+    AlreadyExists,
+    CommitNotFound,
+}
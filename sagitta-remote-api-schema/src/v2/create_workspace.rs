@@ -3,10 +3,14 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct V2CreateWorkspaceRequest {
     pub name: String,
+    pub branch_name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum V2CreateWorkspaceResponse {
     Ok { id: String },
     AlreadyExists,
+    BranchNotFound,
+    InvalidName,
+    NameTaken,
 }
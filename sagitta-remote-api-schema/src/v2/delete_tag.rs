@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2DeleteTagRequest {
+    pub tag_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2DeleteTagResponse {
+    Ok,
+    NotFound,
+}
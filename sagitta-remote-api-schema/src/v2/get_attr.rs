@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 pub struct V2GetAttrRequest {
     pub path: Vec<String>,
     pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +17,7 @@ pub enum V2GetAttrResponse {
         size: u64,
         modified_at: SystemTime,
         permission: i64,
+        symlink_target: Option<String>,
     },
     NotFound,
 }
@@ -0,0 +1,20 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetBranchesRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetBranchesResponseItem {
+    pub name: String,
+    pub head_commit_rank: i64,
+    pub created_at: SystemTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2GetBranchesResponse {
+    Ok {
+        items: Vec<V2GetBranchesResponseItem>,
+    },
+}
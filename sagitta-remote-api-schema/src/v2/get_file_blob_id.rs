@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 pub struct V2GetFileBlobIdRequest {
     pub path: Vec<String>,
     pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,28 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetFileHistoryRequest {
+    pub branch_name: String,
+    pub path: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetFileHistoryResponseItem {
+    pub commit_id: String,
+    pub commit_rank: i64,
+    pub blob_id: Option<String>,
+    pub is_dir: bool,
+    pub permission: i64,
+    pub deleted: bool,
+    pub created_at: SystemTime,
+    pub renamed_from: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2GetFileHistoryResponse {
+    Ok {
+        items: Vec<V2GetFileHistoryResponseItem>,
+    },
+}
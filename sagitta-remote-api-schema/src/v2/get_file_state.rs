@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetFileStateRequest {
+    pub path: Vec<String>,
+    pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum V2FileState {
+    Clean,
+    Modified,
+    New,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2GetFileStateResponse {
+    Found {
+        blob_id: Option<String>,
+        sha256: Option<String>,
+        commit_rank: Option<i64>,
+        state: V2FileState,
+    },
+    NotFound,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetSyncStateRequest {
+    pub workspace_id: String,
+    pub sync_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2GetSyncStateResponse {
+    Applied,
+    NotApplied,
+}
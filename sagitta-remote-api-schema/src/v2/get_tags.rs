@@ -0,0 +1,21 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetTagsRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetTagsResponseItem {
+    pub tag_id: String,
+    pub name: String,
+    pub commit_id: String,
+    pub commit_rank: i64,
+    pub branch_name: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2GetTagsResponse {
+    Ok { items: Vec<V2GetTagsResponseItem> },
+}
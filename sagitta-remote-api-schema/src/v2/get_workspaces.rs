@@ -7,6 +7,7 @@ pub struct V2GetWorkspacesRequest {}
 pub struct V2GetWorkspacesResponseItem {
     pub id: String,
     pub name: String,
+    pub branch_name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,17 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2GetXattrsRequest {
+    pub path: Vec<String>,
+    pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2GetXattrsResponse {
+    Found { xattrs: BTreeMap<String, Vec<u8>> },
+    NotFound,
+}
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 pub struct V2ReadDirRequest {
     pub path: Vec<String>,
     pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
     pub include_deleted: bool,
 }
 
@@ -13,6 +15,7 @@ pub struct V2ReadDirRequest {
 pub struct V2ReadDirResponseItem {
     pub name: String,
     pub is_dir: bool,
+    pub is_symlink: bool,
     pub size: u64,
     pub modified_at: SystemTime,
 }
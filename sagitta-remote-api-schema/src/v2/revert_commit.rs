@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2RevertCommitRequest {
+    pub commit_rank: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum V2RevertCommitResponse {
+    Ok { commit_id: String, commit_rank: i64 },
+    Conflict { file_paths: Vec<String> },
+    NotFound,
+}
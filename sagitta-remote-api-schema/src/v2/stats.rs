@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2StatsRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V2StatsResponse {
+    pub blob_count: u64,
+    pub blob_total_size: u64,
+}
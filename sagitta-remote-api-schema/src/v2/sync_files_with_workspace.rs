@@ -1,3 +1,5 @@
+use std::{collections::BTreeMap, time::SystemTime};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -6,22 +8,34 @@ pub enum V2SyncFilesWithWorkspaceRequestItem {
         file_path: Vec<String>,
         blob_id: String,
         permission: i64,
+        xattrs: BTreeMap<String, Vec<u8>>,
+        modified_at: Option<SystemTime>,
     },
     UpsertDir {
         file_path: Vec<String>,
         permission: i64,
     },
+    UpsertSymlink {
+        file_path: Vec<String>,
+        target: String,
+    },
     DeleteFile {
         file_path: Vec<String>,
     },
     DeleteDir {
         file_path: Vec<String>,
     },
+    Rename {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct V2SyncFilesWithWorkspaceRequest {
     pub workspace_id: String,
+    /// Identifies the sync, so that a retried sync is applied only once.
+    pub sync_id: Option<String>,
     pub items: Vec<V2SyncFilesWithWorkspaceRequestItem>,
 }
 
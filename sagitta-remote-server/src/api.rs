@@ -13,13 +13,26 @@ use crate::state::ApiState;
 
 use crate::api::v2::read_dir::*;
 
+use self::v2::blame::v2_blame;
+use self::v2::cherry_pick::v2_cherry_pick;
 use self::v2::commit::v2_commit;
+use self::v2::create_branch::v2_create_branch;
+use self::v2::create_tag::v2_create_tag;
 use self::v2::create_workspace::v2_create_workspace;
+use self::v2::delete_tag::v2_delete_tag;
 use self::v2::get_attr::v2_get_attr;
+use self::v2::get_branches::v2_get_branches;
 use self::v2::get_file_blob_id::v2_get_file_blob_id;
+use self::v2::get_file_history::v2_get_file_history;
+use self::v2::get_file_state::v2_get_file_state;
+use self::v2::get_sync_state::v2_get_sync_state;
+use self::v2::get_tags::v2_get_tags;
 use self::v2::get_workspace_id_from_name::v2_get_workspace_id_from_name;
 use self::v2::get_workspaces::v2_get_workspaces;
+use self::v2::get_xattrs::v2_get_xattrs;
 use self::v2::read_blob::v2_read_blob;
+use self::v2::revert_commit::v2_revert_commit;
+use self::v2::stats::v2_stats;
 use self::v2::sync_files_with_workspace::v2_sync_files_with_workspace;
 use self::v2::write_blob::v2_write_blob;
 
@@ -83,8 +96,21 @@ pub async fn run_server(config: ServerConfig) {
             .service(v2_create_workspace)
             .service(v2_write_blob)
             .service(v2_sync_files_with_workspace)
+            .service(v2_get_sync_state)
             .service(v2_commit)
             .service(v2_get_workspace_id_from_name)
+            .service(v2_revert_commit)
+            .service(v2_create_tag)
+            .service(v2_get_tags)
+            .service(v2_delete_tag)
+            .service(v2_create_branch)
+            .service(v2_get_branches)
+            .service(v2_cherry_pick)
+            .service(v2_get_file_history)
+            .service(v2_blame)
+            .service(v2_get_xattrs)
+            .service(v2_get_file_state)
+            .service(v2_stats)
     })
     .bind(("0.0.0.0", config.port))
     .unwrap()
@@ -1,10 +1,23 @@
+pub mod blame;
+pub mod cherry_pick;
 pub mod commit;
+pub mod create_branch;
+pub mod create_tag;
 pub mod create_workspace;
+pub mod delete_tag;
 pub mod get_attr;
+pub mod get_branches;
 pub mod get_file_blob_id;
+pub mod get_file_history;
+pub mod get_file_state;
+pub mod get_sync_state;
+pub mod get_tags;
 pub mod get_workspace_id_from_name;
 pub mod get_workspaces;
+pub mod get_xattrs;
 pub mod read_blob;
 pub mod read_dir;
+pub mod revert_commit;
+pub mod stats;
 pub mod sync_files_with_workspace;
 pub mod write_blob;
@@ -0,0 +1,38 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::blame::{V2BlameRequest, V2BlameResponse, V2BlameResponseLine};
+use sagitta_remote_system_workspace::{BlameRequest, BlameResponse};
+
+use crate::state::ApiState;
+
+#[post("/v2/blame")]
+pub async fn v2_blame(
+    state: web::Data<ApiState>,
+    req: web::Json<V2BlameRequest>,
+) -> impl Responder {
+    let request = BlameRequest {
+        branch_name: req.branch_name.clone(),
+        file_path: req.path.clone(),
+    };
+
+    let blame_res = state
+        .remote_system_workspace_manager
+        .blame(request)
+        .unwrap();
+
+    let res = match blame_res {
+        BlameResponse::Found { lines } => V2BlameResponse::Ok {
+            lines: lines
+                .into_iter()
+                .map(|line| V2BlameResponseLine {
+                    commit_id: line.commit_id,
+                    commit_rank: line.commit_rank,
+                    line: line.line,
+                })
+                .collect(),
+        },
+        BlameResponse::NotFound => V2BlameResponse::NotFound,
+        BlameResponse::NotText { commit_id } => V2BlameResponse::NotText { commit_id },
+    };
+
+    web::Json(res)
+}
@@ -0,0 +1,35 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::cherry_pick::{V2CherryPickRequest, V2CherryPickResponse};
+use sagitta_remote_system_db::{CherryPickRequest, CherryPickResponse};
+
+use crate::state::ApiState;
+
+#[post("/v2/cherry-pick")]
+pub async fn v2_cherry_pick(
+    state: web::Data<ApiState>,
+    req: web::Json<V2CherryPickRequest>,
+) -> impl Responder {
+    let request = CherryPickRequest {
+        commit_rank: req.commit_rank,
+        branch_name: req.branch_name.clone(),
+    };
+
+    let cherry_pick_res = state
+        .remote_system_workspace_manager
+        .cherry_pick(request)
+        .unwrap();
+
+    let res = match cherry_pick_res {
+        CherryPickResponse::Picked {
+            commit_id,
+            commit_rank,
+        } => V2CherryPickResponse::Ok {
+            commit_id,
+            commit_rank,
+        },
+        CherryPickResponse::NotFound => V2CherryPickResponse::NotFound,
+        CherryPickResponse::BranchNotFound => V2CherryPickResponse::BranchNotFound,
+    };
+
+    web::Json(res)
+}
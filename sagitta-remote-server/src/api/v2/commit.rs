@@ -1,6 +1,7 @@
 use actix_web::{post, web, Responder};
 use sagitta_remote_api_schema::v2::commit::{V2CommitRequest, V2CommitResponse};
-use sagitta_remote_system_db::CommitRequest;
+use sagitta_remote_system_db::{CommitRequest, CommitResponse, SagittaRemoteSystemDBError};
+use sagitta_remote_system_workspace::Error;
 
 use crate::state::ApiState;
 
@@ -11,14 +12,26 @@ pub async fn v2_commit(
 ) -> impl Responder {
     let request = CommitRequest {
         workspace_id: req.workspace_id.clone(),
+        keep_workspace: req.keep_workspace,
+        paths: req.paths.clone(),
     };
 
-    let _commit_res = state
-        .remote_system_workspace_manager
-        .commit(request)
-        .unwrap();
+    let commit_res = state.remote_system_workspace_manager.commit(request);
 
-    let res = V2CommitResponse::Ok {};
+    let res = match commit_res {
+        Ok(CommitResponse::Committed {
+            commit_id,
+            commit_rank,
+        }) => V2CommitResponse::Ok {
+            commit_id,
+            commit_rank,
+        },
+        Ok(CommitResponse::Conflict { file_paths }) => V2CommitResponse::Conflict { file_paths },
+        Err(Error::SagittaRemoteSystemDBError(SagittaRemoteSystemDBError::WorkspaceNotFound)) => {
+            V2CommitResponse::NotFound
+        }
+        Err(e) => panic!("failed to commit: {:?}", e),
+    };
 
     web::Json(res)
 }
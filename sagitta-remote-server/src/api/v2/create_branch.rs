@@ -0,0 +1,37 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::create_branch::{V2CreateBranchRequest, V2CreateBranchResponse};
+use sagitta_remote_system_db::{CreateBranchRequest, CreateBranchResponse};
+
+use crate::state::ApiState;
+
+#[post("/v2/create-branch")]
+pub async fn v2_create_branch(
+    state: web::Data<ApiState>,
+    req: web::Json<V2CreateBranchRequest>,
+) -> impl Responder {
+    let request = CreateBranchRequest {
+        branch_name: req.branch_name.clone(),
+        source_branch_name: req.source_branch_name.clone(),
+    };
+
+    let create_branch_res = state
+        .remote_system_workspace_manager
+        .create_branch(request)
+        .unwrap();
+
+    let res = match create_branch_res {
+        CreateBranchResponse::Created {
+            commit_id,
+            commit_rank,
+        } => V2CreateBranchResponse::Ok {
+            commit_id,
+            commit_rank,
+        },
+        CreateBranchResponse::AlreadyExists => V2CreateBranchResponse::AlreadyExists,
+        CreateBranchResponse::SourceNotFound => V2CreateBranchResponse::SourceNotFound,
+        CreateBranchResponse::InvalidName => V2CreateBranchResponse::InvalidName,
+        CreateBranchResponse::NameTaken => V2CreateBranchResponse::NameTaken,
+    };
+
+    web::Json(res)
+}
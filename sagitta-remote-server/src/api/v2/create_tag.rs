@@ -0,0 +1,29 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::create_tag::{V2CreateTagRequest, V2CreateTagResponse};
+use sagitta_remote_system_db::{CreateTagRequest, CreateTagResponse};
+
+use crate::state::ApiState;
+
+#[post("/v2/create-tag")]
+pub async fn v2_create_tag(
+    state: web::Data<ApiState>,
+    req: web::Json<V2CreateTagRequest>,
+) -> impl Responder {
+    let request = CreateTagRequest {
+        tag_name: req.tag_name.clone(),
+        commit_rank: req.commit_rank,
+    };
+
+    let create_tag_res = state
+        .remote_system_workspace_manager
+        .create_tag(request)
+        .unwrap();
+
+    let res = match create_tag_res {
+        CreateTagResponse::Created { tag_id } => V2CreateTagResponse::Ok { tag_id },
+        CreateTagResponse::AlreadyExists => V2CreateTagResponse::AlreadyExists,
+        CreateTagResponse::CommitNotFound => V2CreateTagResponse::CommitNotFound,
+    };
+
+    web::Json(res)
+}
@@ -2,7 +2,7 @@ use actix_web::{post, web, Responder};
 use sagitta_remote_api_schema::v2::create_workspace::{
     V2CreateWorkspaceRequest, V2CreateWorkspaceResponse,
 };
-use sagitta_remote_system_db::CreateWorkspaceRequest;
+use sagitta_remote_system_db::{CreateWorkspaceRequest, SagittaRemoteSystemDBError};
 
 use crate::state::ApiState;
 
@@ -13,15 +13,30 @@ pub async fn v2_create_workspace(
 ) -> impl Responder {
     let request = CreateWorkspaceRequest {
         workspace_name: req.name.clone(),
+        branch_name: req.branch_name.clone(),
     };
 
     let create_workspace_res = state
         .remote_system_workspace_manager
-        .create_workspace(request)
-        .unwrap();
+        .create_workspace(request);
 
-    let res = V2CreateWorkspaceResponse::Ok {
-        id: create_workspace_res.workspace_id,
+    let res = match create_workspace_res {
+        Ok(create_workspace_res) => V2CreateWorkspaceResponse::Ok {
+            id: create_workspace_res.workspace_id,
+        },
+        Err(sagitta_remote_system_workspace::Error::SagittaRemoteSystemDBError(
+            SagittaRemoteSystemDBError::BranchNotFound,
+        )) => V2CreateWorkspaceResponse::BranchNotFound,
+        Err(sagitta_remote_system_workspace::Error::SagittaRemoteSystemDBError(
+            SagittaRemoteSystemDBError::WorkspaceAlreadyExists,
+        )) => V2CreateWorkspaceResponse::AlreadyExists,
+        Err(sagitta_remote_system_workspace::Error::SagittaRemoteSystemDBError(
+            SagittaRemoteSystemDBError::InvalidName,
+        )) => V2CreateWorkspaceResponse::InvalidName,
+        Err(sagitta_remote_system_workspace::Error::SagittaRemoteSystemDBError(
+            SagittaRemoteSystemDBError::NameTaken,
+        )) => V2CreateWorkspaceResponse::NameTaken,
+        Err(e) => panic!("{:?}", e),
     };
 
     web::Json(res)
@@ -0,0 +1,27 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::delete_tag::{V2DeleteTagRequest, V2DeleteTagResponse};
+use sagitta_remote_system_db::{DeleteTagRequest, DeleteTagResponse};
+
+use crate::state::ApiState;
+
+#[post("/v2/delete-tag")]
+pub async fn v2_delete_tag(
+    state: web::Data<ApiState>,
+    req: web::Json<V2DeleteTagRequest>,
+) -> impl Responder {
+    let request = DeleteTagRequest {
+        tag_name: req.tag_name.clone(),
+    };
+
+    let delete_tag_res = state
+        .remote_system_workspace_manager
+        .delete_tag(request)
+        .unwrap();
+
+    let res = match delete_tag_res {
+        DeleteTagResponse::Deleted => V2DeleteTagResponse::Ok,
+        DeleteTagResponse::NotFound => V2DeleteTagResponse::NotFound,
+    };
+
+    web::Json(res)
+}
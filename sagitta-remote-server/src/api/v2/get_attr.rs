@@ -11,6 +11,8 @@ pub async fn v2_get_attr(
 ) -> impl Responder {
     let request = GetAttrRequest {
         workspace_id: req.workspace_id.clone(),
+        branch_name: req.branch_name.clone(),
+        commit_rank: req.commit_rank,
         file_path: req.path.clone(),
     };
 
@@ -25,11 +27,13 @@ pub async fn v2_get_attr(
             size,
             modified_at,
             permission,
+            symlink_target,
         } => V2GetAttrResponse::Found {
             is_dir: file_type == SagittaFileType::Dir,
             size,
             modified_at,
             permission,
+            symlink_target,
         },
         GetAttrResponse::NotFound => V2GetAttrResponse::NotFound,
     };
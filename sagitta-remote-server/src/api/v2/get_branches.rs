@@ -0,0 +1,30 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::get_branches::{
+    V2GetBranchesRequest, V2GetBranchesResponse, V2GetBranchesResponseItem,
+};
+use sagitta_remote_system_db::GetBranchesRequest;
+
+use crate::state::ApiState;
+
+#[post("/v2/get-branches")]
+pub async fn v2_get_branches(
+    state: web::Data<ApiState>,
+    _req: web::Json<V2GetBranchesRequest>,
+) -> impl Responder {
+    let get_branches_res = state
+        .remote_system_workspace_manager
+        .get_branches(GetBranchesRequest {})
+        .unwrap();
+
+    let items = get_branches_res
+        .items
+        .into_iter()
+        .map(|item| V2GetBranchesResponseItem {
+            name: item.branch_name,
+            head_commit_rank: item.head_commit_rank,
+            created_at: item.created_at,
+        })
+        .collect();
+
+    web::Json(V2GetBranchesResponse::Ok { items })
+}
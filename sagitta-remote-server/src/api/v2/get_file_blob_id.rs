@@ -13,6 +13,8 @@ pub async fn v2_get_file_blob_id(
 ) -> impl Responder {
     let request = GetFileBlobIdRequest {
         workspace_id: req.workspace_id.clone(),
+        branch_name: req.branch_name.clone(),
+        commit_rank: req.commit_rank,
         file_path: req.path.clone(),
     };
 
@@ -0,0 +1,40 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::get_file_history::{
+    V2GetFileHistoryRequest, V2GetFileHistoryResponse, V2GetFileHistoryResponseItem,
+};
+use sagitta_remote_system_db::{GetFileHistoryRequest, SagittaFileType};
+
+use crate::state::ApiState;
+
+#[post("/v2/get-file-history")]
+pub async fn v2_get_file_history(
+    state: web::Data<ApiState>,
+    req: web::Json<V2GetFileHistoryRequest>,
+) -> impl Responder {
+    let request = GetFileHistoryRequest {
+        branch_name: req.branch_name.clone(),
+        file_path: req.path.clone(),
+    };
+
+    let get_file_history_res = state
+        .remote_system_workspace_manager
+        .get_file_history(request)
+        .unwrap();
+
+    let items = get_file_history_res
+        .items
+        .into_iter()
+        .map(|item| V2GetFileHistoryResponseItem {
+            commit_id: item.commit_id,
+            commit_rank: item.commit_rank,
+            blob_id: item.blob_id,
+            is_dir: item.file_type == SagittaFileType::Dir,
+            permission: item.permission,
+            deleted: item.deleted,
+            created_at: item.created_at,
+            renamed_from: item.renamed_from,
+        })
+        .collect();
+
+    web::Json(V2GetFileHistoryResponse::Ok { items })
+}
@@ -0,0 +1,46 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::get_file_state::{
+    V2FileState, V2GetFileStateRequest, V2GetFileStateResponse,
+};
+use sagitta_remote_system_db::{GetFileStateRequest, GetFileStateResponse, SagittaFileState};
+
+use crate::state::ApiState;
+
+#[post("/v2/get-file-state")]
+pub async fn v2_get_file_state(
+    state: web::Data<ApiState>,
+    req: web::Json<V2GetFileStateRequest>,
+) -> impl Responder {
+    let request = GetFileStateRequest {
+        workspace_id: req.workspace_id.clone(),
+        branch_name: req.branch_name.clone(),
+        commit_rank: req.commit_rank,
+        file_path: req.path.clone(),
+    };
+
+    let get_file_state_res = state
+        .remote_system_workspace_manager
+        .get_file_state(request)
+        .unwrap();
+
+    let res = match get_file_state_res {
+        GetFileStateResponse::Found {
+            blob_id,
+            sha256,
+            commit_rank,
+            state,
+        } => V2GetFileStateResponse::Found {
+            blob_id,
+            sha256,
+            commit_rank,
+            state: match state {
+                SagittaFileState::Clean => V2FileState::Clean,
+                SagittaFileState::Modified => V2FileState::Modified,
+                SagittaFileState::New => V2FileState::New,
+            },
+        },
+        GetFileStateResponse::NotFound => V2GetFileStateResponse::NotFound,
+    };
+
+    web::Json(res)
+}
@@ -0,0 +1,30 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::get_sync_state::{
+    V2GetSyncStateRequest, V2GetSyncStateResponse,
+};
+use sagitta_remote_system_db::{GetSyncStateRequest, GetSyncStateResponse};
+
+use crate::state::ApiState;
+
+#[post("/v2/get-sync-state")]
+pub async fn v2_get_sync_state(
+    state: web::Data<ApiState>,
+    req: web::Json<V2GetSyncStateRequest>,
+) -> impl Responder {
+    let request = GetSyncStateRequest {
+        workspace_id: req.workspace_id.clone(),
+        sync_id: req.sync_id.clone(),
+    };
+
+    let get_sync_state_res = state
+        .remote_system_workspace_manager
+        .get_sync_state(request)
+        .unwrap();
+
+    let res = match get_sync_state_res {
+        GetSyncStateResponse::Applied => V2GetSyncStateResponse::Applied,
+        GetSyncStateResponse::NotApplied => V2GetSyncStateResponse::NotApplied,
+    };
+
+    web::Json(res)
+}
@@ -0,0 +1,33 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::get_tags::{
+    V2GetTagsRequest, V2GetTagsResponse, V2GetTagsResponseItem,
+};
+use sagitta_remote_system_db::GetTagsRequest;
+
+use crate::state::ApiState;
+
+#[post("/v2/get-tags")]
+pub async fn v2_get_tags(
+    state: web::Data<ApiState>,
+    _req: web::Json<V2GetTagsRequest>,
+) -> impl Responder {
+    let get_tags_res = state
+        .remote_system_workspace_manager
+        .get_tags(GetTagsRequest {})
+        .unwrap();
+
+    let items = get_tags_res
+        .items
+        .into_iter()
+        .map(|item| V2GetTagsResponseItem {
+            tag_id: item.tag_id,
+            name: item.tag_name,
+            commit_id: item.commit_id,
+            commit_rank: item.commit_rank,
+            branch_name: item.branch_name,
+            created_at: item.created_at,
+        })
+        .collect();
+
+    web::Json(V2GetTagsResponse::Ok { items })
+}
@@ -27,6 +27,7 @@ pub async fn v2_get_workspaces(
             .map(|workspace| V2GetWorkspacesResponseItem {
                 id: workspace.workspace_id,
                 name: workspace.workspace_name,
+                branch_name: workspace.branch_name,
             })
             .collect(),
     };
@@ -0,0 +1,30 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::get_xattrs::{V2GetXattrsRequest, V2GetXattrsResponse};
+use sagitta_remote_system_db::{GetXattrsRequest, GetXattrsResponse};
+
+use crate::state::ApiState;
+
+#[post("/v2/get-xattrs")]
+pub async fn v2_get_xattrs(
+    state: web::Data<ApiState>,
+    req: web::Json<V2GetXattrsRequest>,
+) -> impl Responder {
+    let request = GetXattrsRequest {
+        workspace_id: req.workspace_id.clone(),
+        branch_name: req.branch_name.clone(),
+        commit_rank: req.commit_rank,
+        file_path: req.path.clone(),
+    };
+
+    let get_xattrs_res = state
+        .remote_system_workspace_manager
+        .get_xattrs(request)
+        .unwrap();
+
+    let res = match get_xattrs_res {
+        GetXattrsResponse::Found { xattrs } => V2GetXattrsResponse::Found { xattrs },
+        GetXattrsResponse::NotFound => V2GetXattrsResponse::NotFound,
+    };
+
+    web::Json(res)
+}
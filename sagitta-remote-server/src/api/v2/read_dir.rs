@@ -13,6 +13,8 @@ pub async fn v2_read_dir(
 ) -> impl Responder {
     let request = ReadDirRequest {
         workspace_id: req.workspace_id.clone(),
+        branch_name: req.branch_name.clone(),
+        commit_rank: req.commit_rank,
         file_path: req.path.clone(),
         include_deleted: req.include_deleted,
     };
@@ -29,6 +31,7 @@ pub async fn v2_read_dir(
                 .map(|item| V2ReadDirResponseItem {
                     name: item.file_name,
                     is_dir: item.file_type == SagittaFileType::Dir,
+                    is_symlink: item.file_type == SagittaFileType::Symlink,
                     size: item.size,
                     modified_at: item.modified_at,
                 })
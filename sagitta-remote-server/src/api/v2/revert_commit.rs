@@ -0,0 +1,36 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::revert_commit::{V2RevertCommitRequest, V2RevertCommitResponse};
+use sagitta_remote_system_db::{RevertCommitRequest, RevertCommitResponse};
+
+use crate::state::ApiState;
+
+#[post("/v2/revert-commit")]
+pub async fn v2_revert_commit(
+    state: web::Data<ApiState>,
+    req: web::Json<V2RevertCommitRequest>,
+) -> impl Responder {
+    let request = RevertCommitRequest {
+        commit_rank: req.commit_rank,
+    };
+
+    let revert_commit_res = state
+        .remote_system_workspace_manager
+        .revert_commit(request)
+        .unwrap();
+
+    let res = match revert_commit_res {
+        RevertCommitResponse::Reverted {
+            commit_id,
+            commit_rank,
+        } => V2RevertCommitResponse::Ok {
+            commit_id,
+            commit_rank,
+        },
+        RevertCommitResponse::Conflict { file_paths } => {
+            V2RevertCommitResponse::Conflict { file_paths }
+        }
+        RevertCommitResponse::NotFound => V2RevertCommitResponse::NotFound,
+    };
+
+    web::Json(res)
+}
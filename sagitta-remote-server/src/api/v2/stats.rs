@@ -0,0 +1,23 @@
+use actix_web::{post, web, Responder};
+use sagitta_remote_api_schema::v2::stats::{V2StatsRequest, V2StatsResponse};
+use sagitta_remote_system_db::GetStatsRequest;
+
+use crate::state::ApiState;
+
+#[post("/v2/stats")]
+pub async fn v2_stats(
+    state: web::Data<ApiState>,
+    _req: web::Json<V2StatsRequest>,
+) -> impl Responder {
+    let get_stats_res = state
+        .remote_system_workspace_manager
+        .get_stats(GetStatsRequest {})
+        .unwrap();
+
+    let res = V2StatsResponse {
+        blob_count: get_stats_res.blob_count,
+        blob_total_size: get_stats_res.blob_total_size,
+    };
+
+    web::Json(res)
+}
@@ -14,6 +14,7 @@ pub async fn v2_sync_files_with_workspace(
 ) -> impl Responder {
     let request = SyncFilesToWorkspaceRequest {
         workspace_id: req.workspace_id.clone(),
+        sync_id: req.sync_id.clone(),
         items: req
             .items
             .iter()
@@ -22,10 +23,14 @@ pub async fn v2_sync_files_with_workspace(
                     file_path,
                     blob_id,
                     permission,
+                    xattrs,
+                    modified_at,
                 } => SyncFilesToWorkspaceRequestItem::UpsertFile {
                     file_path: file_path.clone(),
                     blob_id: blob_id.clone(),
                     permission: *permission,
+                    xattrs: xattrs.clone(),
+                    modified_at: *modified_at,
                 },
                 V2SyncFilesWithWorkspaceRequestItem::UpsertDir {
                     file_path,
@@ -34,6 +39,12 @@ pub async fn v2_sync_files_with_workspace(
                     file_path: file_path.clone(),
                     permission: *permission,
                 },
+                V2SyncFilesWithWorkspaceRequestItem::UpsertSymlink { file_path, target } => {
+                    SyncFilesToWorkspaceRequestItem::UpsertSymlink {
+                        file_path: file_path.clone(),
+                        target: target.clone(),
+                    }
+                }
                 V2SyncFilesWithWorkspaceRequestItem::DeleteFile { file_path } => {
                     SyncFilesToWorkspaceRequestItem::DeleteFile {
                         file_path: file_path.clone(),
@@ -44,6 +55,12 @@ pub async fn v2_sync_files_with_workspace(
                         file_path: file_path.clone(),
                     }
                 }
+                V2SyncFilesWithWorkspaceRequestItem::Rename { from, to } => {
+                    SyncFilesToWorkspaceRequestItem::Rename {
+                        from: from.clone(),
+                        to: to.clone(),
+                    }
+                }
             })
             .collect(),
     };
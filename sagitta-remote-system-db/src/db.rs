@@ -77,6 +77,15 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDB {
         }
     }
 
+    fn get_sync_state(
+        &self,
+        request: crate::GetSyncStateRequest,
+    ) -> Result<crate::GetSyncStateResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.get_sync_state(request),
+        }
+    }
+
     fn get_workspace_changelist(
         &self,
         request: crate::GetWorkspaceChangelistRequest,
@@ -95,6 +104,78 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDB {
         }
     }
 
+    fn revert_commit(
+        &self,
+        request: crate::RevertCommitRequest,
+    ) -> Result<crate::RevertCommitResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.revert_commit(request),
+        }
+    }
+
+    fn create_tag(
+        &self,
+        request: crate::CreateTagRequest,
+    ) -> Result<crate::CreateTagResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.create_tag(request),
+        }
+    }
+
+    fn get_tags(
+        &self,
+        request: crate::GetTagsRequest,
+    ) -> Result<crate::GetTagsResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.get_tags(request),
+        }
+    }
+
+    fn delete_tag(
+        &self,
+        request: crate::DeleteTagRequest,
+    ) -> Result<crate::DeleteTagResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.delete_tag(request),
+        }
+    }
+
+    fn create_branch(
+        &self,
+        request: crate::CreateBranchRequest,
+    ) -> Result<crate::CreateBranchResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.create_branch(request),
+        }
+    }
+
+    fn get_branches(
+        &self,
+        request: crate::GetBranchesRequest,
+    ) -> Result<crate::GetBranchesResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.get_branches(request),
+        }
+    }
+
+    fn cherry_pick(
+        &self,
+        request: crate::CherryPickRequest,
+    ) -> Result<crate::CherryPickResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.cherry_pick(request),
+        }
+    }
+
+    fn get_file_history(
+        &self,
+        request: crate::GetFileHistoryRequest,
+    ) -> Result<crate::GetFileHistoryResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.get_file_history(request),
+        }
+    }
+
     fn get_all_trunk_files(
         &self,
         request: crate::GetAllTrunkFilesRequest,
@@ -140,6 +221,33 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDB {
         }
     }
 
+    fn get_xattrs(
+        &self,
+        request: crate::GetXattrsRequest,
+    ) -> Result<crate::GetXattrsResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.get_xattrs(request),
+        }
+    }
+
+    fn get_file_state(
+        &self,
+        request: crate::GetFileStateRequest,
+    ) -> Result<crate::GetFileStateResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.get_file_state(request),
+        }
+    }
+
+    fn get_stats(
+        &self,
+        request: crate::GetStatsRequest,
+    ) -> Result<crate::GetStatsResponse, crate::SagittaRemoteSystemDBError> {
+        match self {
+            SagittaRemoteSystemDB::Sqlite(db) => db.get_stats(request),
+        }
+    }
+
     fn get_workspace_id_from_name(
         &self,
         request: crate::GetWorkspaceIdFromNameRequest,
@@ -1,12 +1,16 @@
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::time::SystemTime;
 
 pub mod db;
 pub mod sqlite;
 
+pub const TRUNK_BRANCH_NAME: &str = "trunk";
+
 #[derive(Debug)]
 pub struct CreateWorkspaceRequest {
     pub workspace_name: String,
+    pub branch_name: String,
 }
 
 #[derive(Debug)]
@@ -23,6 +27,8 @@ pub struct GetWorkspacesRequest {
 pub struct GetWorkspacesResponseItem {
     pub workspace_id: String,
     pub workspace_name: String,
+    pub branch_name: String,
+    pub base_commit_rank: i64,
     pub created_at: SystemTime,
     pub deleted_at: Option<SystemTime>,
 }
@@ -89,28 +95,55 @@ pub enum SyncFilesToWorkspaceRequestItem {
         file_path: Vec<String>,
         blob_id: String,
         permission: i64,
+        xattrs: BTreeMap<String, Vec<u8>>,
+        /// mtime of the file; the sync time is used when `None`.
+        modified_at: Option<SystemTime>,
     },
     UpsertDir {
         file_path: Vec<String>,
         permission: i64,
     },
+    UpsertSymlink {
+        file_path: Vec<String>,
+        target: String,
+    },
     DeleteFile {
         file_path: Vec<String>,
     },
     DeleteDir {
         file_path: Vec<String>,
     },
+    /// Moves `from`, and everything under it when it is a dir, to `to` as the workspace
+    /// currently sees it. The moved revisions remember where they came from.
+    Rename {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
 }
 
 #[derive(Debug)]
 pub struct SyncFilesToWorkspaceRequest {
     pub workspace_id: String,
+    /// Identifies the sync, so that a retried sync is applied only once.
+    pub sync_id: Option<String>,
     pub items: Vec<SyncFilesToWorkspaceRequestItem>,
 }
 
 #[derive(Debug)]
 pub struct SyncFilesToWorkspaceResponse {}
 
+#[derive(Debug)]
+pub struct GetSyncStateRequest {
+    pub workspace_id: String,
+    pub sync_id: String,
+}
+
+#[derive(Debug)]
+pub enum GetSyncStateResponse {
+    Applied,
+    NotApplied,
+}
+
 #[derive(Debug)]
 pub struct GetWorkspaceChangelistRequest {
     pub workspace_id: String,
@@ -120,6 +153,7 @@ pub struct GetWorkspaceChangelistRequest {
 pub enum SagittaFileType {
     File,
     Dir,
+    Symlink,
 }
 
 #[derive(Debug)]
@@ -138,13 +172,152 @@ pub struct GetWorkspaceChangelistResponse {
 #[derive(Debug)]
 pub struct CommitRequest {
     pub workspace_id: String,
+    pub keep_workspace: bool,
+    pub paths: Option<Vec<Vec<String>>>,
+}
+
+#[derive(Debug)]
+pub enum CommitResponse {
+    Committed {
+        commit_id: String,
+        commit_rank: i64,
+    },
+    /// Paths the branch changed after the base of the workspace.
+    Conflict {
+        file_paths: Vec<String>,
+    },
+}
+
+#[derive(Debug)]
+pub struct RevertCommitRequest {
+    pub commit_rank: i64,
+}
+
+#[derive(Debug)]
+pub enum RevertCommitResponse {
+    Reverted { commit_id: String, commit_rank: i64 },
+    Conflict { file_paths: Vec<String> },
+    NotFound,
+}
+
+#[derive(Debug)]
+pub struct CreateTagRequest {
+    pub tag_name: String,
+    pub commit_rank: i64,
+}
+
+#[derive(Debug)]
+pub enum CreateTagResponse {
+    Created { tag_id: String },
+    AlreadyExists,
+    CommitNotFound,
+}
+
+#[derive(Debug)]
+pub struct GetTagsRequest {}
+
+#[derive(Debug)]
+pub struct GetTagsResponseItem {
+    pub tag_id: String,
+    pub tag_name: String,
+    pub commit_id: String,
+    pub commit_rank: i64,
+    pub branch_name: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Debug)]
+pub struct GetTagsResponse {
+    pub items: Vec<GetTagsResponseItem>,
+}
+
+#[derive(Debug)]
+pub struct DeleteTagRequest {
+    pub tag_name: String,
+}
+
+#[derive(Debug)]
+pub enum DeleteTagResponse {
+    Deleted,
+    NotFound,
+}
+
+#[derive(Debug)]
+pub struct CreateBranchRequest {
+    pub branch_name: String,
+    pub source_branch_name: String,
+}
+
+#[derive(Debug)]
+pub enum CreateBranchResponse {
+    Created {
+        commit_id: String,
+        commit_rank: i64,
+    },
+    AlreadyExists,
+    SourceNotFound,
+    /// The name is not a single path component or starts with `@`.
+    InvalidName,
+    /// The name is already used by a workspace.
+    NameTaken,
+}
+
+#[derive(Debug)]
+pub struct GetBranchesRequest {}
+
+#[derive(Debug)]
+pub struct GetBranchesResponseItem {
+    pub branch_name: String,
+    pub head_commit_rank: i64,
+    pub created_at: SystemTime,
+}
+
+#[derive(Debug)]
+pub struct GetBranchesResponse {
+    pub items: Vec<GetBranchesResponseItem>,
+}
+
+#[derive(Debug)]
+pub struct CherryPickRequest {
+    pub commit_rank: i64,
+    pub branch_name: String,
+}
+
+#[derive(Debug)]
+pub enum CherryPickResponse {
+    Picked { commit_id: String, commit_rank: i64 },
+    NotFound,
+    BranchNotFound,
 }
 
 #[derive(Debug)]
-pub struct CommitResponse {}
+pub struct GetFileHistoryRequest {
+    pub branch_name: String,
+    pub file_path: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct GetFileHistoryResponseItem {
+    pub commit_id: String,
+    pub commit_rank: i64,
+    pub blob_id: Option<String>,
+    pub file_type: SagittaFileType,
+    pub permission: i64,
+    pub deleted: bool,
+    pub created_at: SystemTime,
+    /// Path the file was renamed from in this commit.
+    pub renamed_from: Option<Vec<String>>,
+}
 
 #[derive(Debug)]
-pub struct GetAllTrunkFilesRequest {}
+pub struct GetFileHistoryResponse {
+    pub items: Vec<GetFileHistoryResponseItem>,
+}
+
+#[derive(Debug)]
+pub struct GetAllTrunkFilesRequest {
+    pub branch_name: String,
+}
 
 #[derive(Debug)]
 pub struct GetAllTrunkFilesResponseItem {
@@ -161,6 +334,7 @@ pub struct GetAllTrunkFilesResponse {
 
 #[derive(Debug)]
 pub struct GetCommitHistoryRequest {
+    pub branch_name: Option<String>,
     pub take: u64,
 }
 
@@ -168,6 +342,7 @@ pub struct GetCommitHistoryRequest {
 pub struct GetCommitHistoryResponseItem {
     pub commit_id: String,
     pub commit_rank: i64,
+    pub branch_name: String,
     pub created_at: SystemTime,
 }
 
@@ -179,6 +354,8 @@ pub struct GetCommitHistoryResponse {
 #[derive(Debug)]
 pub struct ReadDirRequest {
     pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
     pub file_path: Vec<String>,
     pub include_deleted: bool,
 }
@@ -203,6 +380,8 @@ pub enum ReadDirResponse {
 #[derive(Debug)]
 pub struct GetAttrRequest {
     pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
     pub file_path: Vec<String>,
 }
 
@@ -213,6 +392,7 @@ pub enum GetAttrResponse {
         size: u64,
         modified_at: SystemTime,
         permission: i64,
+        symlink_target: Option<String>,
     },
     NotFound,
 }
@@ -220,6 +400,8 @@ pub enum GetAttrResponse {
 #[derive(Debug)]
 pub struct GetFileBlobIdRequest {
     pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
     pub file_path: Vec<String>,
 }
 
@@ -229,6 +411,56 @@ pub enum GetFileBlobIdResponse {
     NotFound,
 }
 
+#[derive(Debug)]
+pub struct GetXattrsRequest {
+    pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
+    pub file_path: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum GetXattrsResponse {
+    Found { xattrs: BTreeMap<String, Vec<u8>> },
+    NotFound,
+}
+
+#[derive(Debug)]
+pub struct GetFileStateRequest {
+    pub workspace_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub commit_rank: Option<i64>,
+    pub file_path: Vec<String>,
+}
+
+/// State of a path in a workspace relative to the branch it is based on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SagittaFileState {
+    Clean,
+    Modified,
+    New,
+}
+
+#[derive(Debug, Clone)]
+pub enum GetFileStateResponse {
+    Found {
+        blob_id: Option<String>,
+        sha256: Option<String>,
+        commit_rank: Option<i64>,
+        state: SagittaFileState,
+    },
+    NotFound,
+}
+
+#[derive(Debug)]
+pub struct GetStatsRequest {}
+
+#[derive(Debug, Clone)]
+pub struct GetStatsResponse {
+    pub blob_count: u64,
+    pub blob_total_size: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct GetWorkspaceIdFromNameRequest {
     pub workspace_name: String,
@@ -244,6 +476,9 @@ pub enum GetWorkspaceIdFromNameResponse {
 pub enum SagittaRemoteSystemDBError {
     WorkspaceAlreadyExists,
     WorkspaceNotFound,
+    BranchNotFound,
+    InvalidName,
+    NameTaken,
     InternalError,
 }
 
@@ -285,6 +520,11 @@ pub trait SagittaRemoteSystemDBTrait {
         sync_files_to_workspace_request: SyncFilesToWorkspaceRequest,
     ) -> Result<SyncFilesToWorkspaceResponse, SagittaRemoteSystemDBError>;
 
+    fn get_sync_state(
+        &self,
+        request: GetSyncStateRequest,
+    ) -> Result<GetSyncStateResponse, SagittaRemoteSystemDBError>;
+
     fn get_workspace_changelist(
         &self,
         request: GetWorkspaceChangelistRequest,
@@ -292,6 +532,46 @@ pub trait SagittaRemoteSystemDBTrait {
 
     fn commit(&self, request: CommitRequest) -> Result<CommitResponse, SagittaRemoteSystemDBError>;
 
+    fn revert_commit(
+        &self,
+        request: RevertCommitRequest,
+    ) -> Result<RevertCommitResponse, SagittaRemoteSystemDBError>;
+
+    fn create_tag(
+        &self,
+        request: CreateTagRequest,
+    ) -> Result<CreateTagResponse, SagittaRemoteSystemDBError>;
+
+    fn get_tags(
+        &self,
+        request: GetTagsRequest,
+    ) -> Result<GetTagsResponse, SagittaRemoteSystemDBError>;
+
+    fn delete_tag(
+        &self,
+        request: DeleteTagRequest,
+    ) -> Result<DeleteTagResponse, SagittaRemoteSystemDBError>;
+
+    fn create_branch(
+        &self,
+        request: CreateBranchRequest,
+    ) -> Result<CreateBranchResponse, SagittaRemoteSystemDBError>;
+
+    fn get_branches(
+        &self,
+        request: GetBranchesRequest,
+    ) -> Result<GetBranchesResponse, SagittaRemoteSystemDBError>;
+
+    fn cherry_pick(
+        &self,
+        request: CherryPickRequest,
+    ) -> Result<CherryPickResponse, SagittaRemoteSystemDBError>;
+
+    fn get_file_history(
+        &self,
+        request: GetFileHistoryRequest,
+    ) -> Result<GetFileHistoryResponse, SagittaRemoteSystemDBError>;
+
     fn get_all_trunk_files(
         &self,
         request: GetAllTrunkFilesRequest,
@@ -317,6 +597,21 @@ pub trait SagittaRemoteSystemDBTrait {
         request: GetFileBlobIdRequest,
     ) -> Result<GetFileBlobIdResponse, SagittaRemoteSystemDBError>;
 
+    fn get_xattrs(
+        &self,
+        request: GetXattrsRequest,
+    ) -> Result<GetXattrsResponse, SagittaRemoteSystemDBError>;
+
+    fn get_file_state(
+        &self,
+        request: GetFileStateRequest,
+    ) -> Result<GetFileStateResponse, SagittaRemoteSystemDBError>;
+
+    fn get_stats(
+        &self,
+        request: GetStatsRequest,
+    ) -> Result<GetStatsResponse, SagittaRemoteSystemDBError>;
+
     fn get_workspace_id_from_name(
         &self,
         request: GetWorkspaceIdFromNameRequest,
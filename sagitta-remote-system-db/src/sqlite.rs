@@ -20,6 +20,64 @@ pub struct SagittaRemoteSystemDBBySqlite {
     clock: Clock,
 }
 
+/// Latest revision of a path, as used to compute its [`SagittaFileState`].
+struct FileStateRow {
+    blob_id: Option<String>,
+    sha256: Option<String>,
+    deleted_at: Option<String>,
+}
+
+/// Latest revision of a path under a renamed or deleted dir, as a workspace sees it.
+struct SubtreeRow {
+    revision_id: String,
+    file_path_id: String,
+    path: Vec<String>,
+    blob_id: Option<String>,
+    symlink_target: Option<String>,
+    modified_at: Option<String>,
+    file_type: i64,
+    permission: i64,
+}
+
+/// Version stored in `PRAGMA user_version` once every migration step has run.
+const SCHEMA_VERSION: i64 = 1;
+
+/// Adds `column` to `table` unless it is already there, as in a database created
+/// with the current schema. Returns whether the column was added.
+fn add_column_if_missing(
+    tx: &rusqlite::Transaction,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> bool {
+    let exists: bool = tx
+        .query_row(
+            &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{table}') WHERE name = ?"),
+            rusqlite::params![column],
+            |row| row.get(0),
+        )
+        .unwrap();
+    if !exists {
+        tx.execute(
+            &format!("ALTER TABLE `{table}` ADD COLUMN {column} {definition}"),
+            rusqlite::params![],
+        )
+        .unwrap();
+    }
+    !exists
+}
+
+/// Branches and workspaces share the top level of the mount, so a name must be a
+/// single path component that cannot be mistaken for `@tags` or `.`/`..`.
+fn is_valid_view_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.starts_with('@')
+        && !name.contains('/')
+        && !name.contains('\0')
+}
+
 impl SagittaRemoteSystemDBBySqlite {
     pub fn new<P: AsRef<Path>>(
         sqlite_path: P,
@@ -96,6 +154,214 @@ impl SagittaRemoteSystemDBBySqlite {
             }
         }
     }
+
+    fn get_branch_name_tx(
+        &self,
+        workspace_id: &Option<String>,
+        branch_name: &Option<String>,
+        tx: &rusqlite::Transaction,
+    ) -> String {
+        // a workspace always reads from the branch it targets
+        if let Some(workspace_id) = workspace_id {
+            let res = tx.query_row(
+                "SELECT branch_name FROM workspace WHERE workspace_id = ?",
+                rusqlite::params![workspace_id],
+                |row| row.get(0),
+            );
+            if let Ok(branch_name) = res {
+                return branch_name;
+            }
+        }
+
+        branch_name
+            .clone()
+            .unwrap_or_else(|| TRUNK_BRANCH_NAME.to_string())
+    }
+
+    fn get_max_commit_rank_tx(
+        &self,
+        workspace_id: &Option<String>,
+        commit_rank: Option<i64>,
+        tx: &rusqlite::Transaction,
+    ) -> i64 {
+        // a workspace sees the branch as of the commit it is based on
+        if let Some(workspace_id) = workspace_id {
+            let res = tx.query_row(
+                "SELECT base_commit_rank FROM workspace WHERE workspace_id = ?",
+                rusqlite::params![workspace_id],
+                |row| row.get(0),
+            );
+            if let Ok(base_commit_rank) = res {
+                return base_commit_rank;
+            }
+        }
+
+        commit_rank.unwrap_or(i64::MAX)
+    }
+
+    fn copy_xattrs_tx(
+        &self,
+        from_revision_id: &str,
+        to_revision_id: &str,
+        tx: &rusqlite::Transaction,
+    ) {
+        tx.execute(
+            "INSERT INTO file_revision_xattr (file_revision_id, name, value)
+            SELECT ?, name, value FROM file_revision_xattr WHERE file_revision_id = ?",
+            rusqlite::params![to_revision_id, from_revision_id],
+        )
+        .unwrap();
+    }
+
+    /// Removes the revision of a path written earlier in the same sync, so that the sync
+    /// leaves one revision per path. Returns the `renamed_from` of the removed revision.
+    fn take_revision_at_version_tx(
+        &self,
+        workspace_id: &str,
+        file_path_id: &str,
+        version_number: i64,
+        tx: &rusqlite::Transaction,
+    ) -> Option<String> {
+        let res: Option<(String, Option<String>)> = tx
+            .query_row(
+                "SELECT workspace_file_revision_id, renamed_from FROM workspace_file_revision
+                WHERE workspace_id = ? AND file_path_id = ? AND sync_version_number = ?",
+                rusqlite::params![workspace_id, file_path_id, version_number],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .ok();
+        let (revision_id, renamed_from) = res?;
+        tx.execute(
+            "DELETE FROM workspace_file_revision WHERE workspace_file_revision_id = ?",
+            rusqlite::params![revision_id],
+        )
+        .unwrap();
+        tx.execute(
+            "DELETE FROM file_revision_xattr WHERE file_revision_id = ?",
+            rusqlite::params![revision_id],
+        )
+        .unwrap();
+        renamed_from
+    }
+
+    /// Returns the existing paths at or under `file_path` in a workspace, preferring its own
+    /// revisions over the latest revisions of the branch it targets.
+    fn get_workspace_subtree_tx(
+        &self,
+        workspace_id: &str,
+        file_path: &[String],
+        tx: &rusqlite::Transaction,
+    ) -> Vec<SubtreeRow> {
+        let path = file_path.join("/");
+        let prefix = format!("{}/", path);
+        let branch_name = self.get_branch_name_tx(&Some(workspace_id.to_string()), &None, tx);
+        let max_commit_rank =
+            self.get_max_commit_rank_tx(&Some(workspace_id.to_string()), None, tx);
+
+        let to_row = |row: &rusqlite::Row| {
+            let path: String = row.get(2)?;
+            let deleted_at: Option<String> = row.get(8)?;
+            Ok((
+                SubtreeRow {
+                    revision_id: row.get(0)?,
+                    file_path_id: row.get(1)?,
+                    path: path.split('/').map(|x| x.to_string()).collect(),
+                    blob_id: row.get(3)?,
+                    symlink_target: row.get(4)?,
+                    modified_at: row.get(5)?,
+                    file_type: row.get(6)?,
+                    permission: row.get(7)?,
+                },
+                deleted_at,
+            ))
+        };
+
+        let mut rows: BTreeMap<String, (SubtreeRow, Option<String>)> = BTreeMap::new();
+        {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT
+                        trunk_file_revision.trunk_file_revision_id,
+                        trunk_file_revision.file_path_id,
+                        file_path.path,
+                        trunk_file_revision.blob_id,
+                        trunk_file_revision.symlink_target,
+                        trunk_file_revision.modified_at,
+                        trunk_file_revision.file_type,
+                        trunk_file_revision.permission,
+                        trunk_file_revision.deleted_at
+                    FROM trunk_file_revision
+                    JOIN (
+                        SELECT file_path_id, MAX(commit_rank) AS commit_rank
+                        FROM trunk_file_revision AS trunk_file_revision_2
+                        WHERE trunk_file_revision_2.branch_name = ? AND trunk_file_revision_2.commit_rank <= ?
+                        GROUP BY trunk_file_revision_2.file_path_id
+                    ) AS latest_commit_version
+                    ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
+                    JOIN file_path ON trunk_file_revision.file_path_id = file_path.file_path_id
+                    WHERE trunk_file_revision.branch_name = ? AND (file_path.path = ? OR substr(file_path.path, 1, length(?)) = ?)",
+                )
+                .unwrap();
+            for row in stmt
+                .query_map(
+                    rusqlite::params![
+                        branch_name,
+                        max_commit_rank,
+                        branch_name,
+                        path,
+                        prefix,
+                        prefix
+                    ],
+                    to_row,
+                )
+                .unwrap()
+            {
+                let row = row.unwrap();
+                rows.insert(row.0.path.join("/"), row);
+            }
+        }
+        {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT
+                        workspace_file_revision.workspace_file_revision_id,
+                        workspace_file_revision.file_path_id,
+                        file_path.path,
+                        workspace_file_revision.blob_id,
+                        workspace_file_revision.symlink_target,
+                        workspace_file_revision.modified_at,
+                        workspace_file_revision.file_type,
+                        workspace_file_revision.permission,
+                        workspace_file_revision.deleted_at
+                    FROM workspace_file_revision
+                    JOIN (
+                        SELECT file_path_id, MAX(sync_version_number) AS sync_version_number
+                        FROM workspace_file_revision AS workspace_file_revision_2
+                        WHERE workspace_file_revision_2.workspace_id = ?
+                        GROUP BY workspace_file_revision_2.file_path_id
+                    ) AS latest_sync_version
+                    ON workspace_file_revision.file_path_id = latest_sync_version.file_path_id AND workspace_file_revision.sync_version_number = latest_sync_version.sync_version_number
+                    JOIN file_path ON workspace_file_revision.file_path_id = file_path.file_path_id
+                    WHERE workspace_file_revision.workspace_id = ? AND (file_path.path = ? OR substr(file_path.path, 1, length(?)) = ?)",
+                )
+                .unwrap();
+            for row in stmt
+                .query_map(
+                    rusqlite::params![workspace_id, workspace_id, path, prefix, prefix],
+                    to_row,
+                )
+                .unwrap()
+            {
+                let row = row.unwrap();
+                rows.insert(row.0.path.join("/"), row);
+            }
+        }
+
+        rows.into_values()
+            .filter(|(_, deleted_at)| deleted_at.is_none())
+            .map(|(row, _)| row)
+            .collect()
+    }
 }
 
 impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
@@ -105,6 +371,8 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
             "CREATE TABLE IF NOT EXISTS workspace (
                 workspace_id TEXT PRIMARY KEY,
                 name TEXT NOT NULL UNIQUE,
+                branch_name TEXT NOT NULL,
+                base_commit_rank INTEGER NOT NULL,
                 created_at TEXT NOT NULL,
                 deleted_at TEXT
             )",
@@ -140,10 +408,13 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                 file_path_id TEXT NOT NULL,
                 sync_version_number INTEGER NOT NULL,
                 blob_id TEXT,
+                symlink_target TEXT,
+                modified_at TEXT,
                 file_type INTEGER NOT NULL,
                 created_at TEXT NOT NULL,
                 deleted_at TEXT,
-                permission INTEGER NOT NULL
+                permission INTEGER NOT NULL,
+                renamed_from TEXT
             )",
             rusqlite::params![],
         )
@@ -153,13 +424,30 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
             "CREATE TABLE IF NOT EXISTS trunk_file_revision (
                 trunk_file_revision_id TEXT PRIMARY KEY,
                 file_path_id TEXT NOT NULL,
+                branch_name TEXT NOT NULL,
                 commit_id TEXT NOT NULL,
                 commit_rank INTEGER NOT NULL,
                 blob_id TEXT,
+                symlink_target TEXT,
+                modified_at TEXT,
                 file_type INTEGER NOT NULL,
                 created_at TEXT NOT NULL,
                 deleted_at TEXT,
-                permission INTEGER NOT NULL
+                permission INTEGER NOT NULL,
+                renamed_from TEXT
+            )",
+            rusqlite::params![],
+        )
+        .unwrap();
+
+        // keyed by workspace or trunk revision id; a commit keeps the id of the
+        // workspace revision, so attributes follow it into trunk as they are
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS file_revision_xattr (
+                file_revision_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                value BLOB NOT NULL,
+                PRIMARY KEY (file_revision_id, name)
             )",
             rusqlite::params![],
         )
@@ -169,12 +457,99 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
             "CREATE TABLE IF NOT EXISTS `commit` (
                 commit_id TEXT PRIMARY KEY,
                 commit_rank INTEGER NOT NULL,
+                branch_name TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            rusqlite::params![],
+        )
+        .unwrap();
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS branch (
+                branch_name TEXT PRIMARY KEY,
+                created_at TEXT NOT NULL
+            )",
+            rusqlite::params![],
+        )
+        .unwrap();
+
+        // syncs already applied to a workspace, by the id the client gave them
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS workspace_sync (
+                workspace_id TEXT NOT NULL,
+                sync_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (workspace_id, sync_id)
+            )",
+            rusqlite::params![],
+        )
+        .unwrap();
+
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS tag (
+                tag_id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                commit_id TEXT NOT NULL,
                 created_at TEXT NOT NULL
             )",
             rusqlite::params![],
         )
         .unwrap();
 
+        // bring tables created before branches, symlinks, mtimes and renames up to date
+        {
+            let tx = db.transaction().unwrap();
+
+            let version: i64 = tx
+                .query_row("PRAGMA user_version", rusqlite::params![], |row| row.get(0))
+                .unwrap();
+            if version < 1 {
+                let trunk = format!("TEXT NOT NULL DEFAULT '{TRUNK_BRANCH_NAME}'");
+                add_column_if_missing(&tx, "workspace", "branch_name", &trunk);
+                if add_column_if_missing(
+                    &tx,
+                    "workspace",
+                    "base_commit_rank",
+                    "INTEGER NOT NULL DEFAULT 0",
+                ) {
+                    // existing workspaces have been reading the latest trunk
+                    tx.execute(
+                        "UPDATE workspace SET base_commit_rank = (SELECT COALESCE(MAX(commit_rank), 0) FROM `commit`)",
+                        rusqlite::params![],
+                    )
+                    .unwrap();
+                }
+                for table in ["workspace_file_revision", "trunk_file_revision"] {
+                    add_column_if_missing(&tx, table, "symlink_target", "TEXT");
+                    add_column_if_missing(&tx, table, "modified_at", "TEXT");
+                    add_column_if_missing(&tx, table, "renamed_from", "TEXT");
+                }
+                add_column_if_missing(&tx, "trunk_file_revision", "branch_name", &trunk);
+                add_column_if_missing(&tx, "commit", "branch_name", &trunk);
+
+                let has_commits: bool = tx
+                    .query_row(
+                        "SELECT COUNT(*) > 0 FROM `commit`",
+                        rusqlite::params![],
+                        |row| row.get(0),
+                    )
+                    .unwrap();
+                if has_commits {
+                    let now = self.clock.now();
+                    let now: DateTime<Utc> = now.into();
+                    tx.execute(
+                        "INSERT OR IGNORE INTO branch (branch_name, created_at) VALUES (?, ?)",
+                        rusqlite::params![TRUNK_BRANCH_NAME, now.to_rfc3339()],
+                    )
+                    .unwrap();
+                }
+            }
+            tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+                .unwrap();
+
+            tx.commit().unwrap();
+        }
+
         // create initial commit
         {
             let tx = db.transaction().unwrap();
@@ -189,8 +564,13 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                 let now: DateTime<Utc> = now.into();
                 let now_str = now.to_rfc3339();
                 tx.execute(
-                    "INSERT INTO `commit` (commit_id, commit_rank, created_at) VALUES (?, ?, ?)",
-                    rusqlite::params![self.generate_id(), 0, now_str],
+                    "INSERT INTO `commit` (commit_id, commit_rank, branch_name, created_at) VALUES (?, ?, ?, ?)",
+                    rusqlite::params![self.generate_id(), 0, TRUNK_BRANCH_NAME, now_str],
+                )
+                .unwrap();
+                tx.execute(
+                    "INSERT INTO branch (branch_name, created_at) VALUES (?, ?)",
+                    rusqlite::params![TRUNK_BRANCH_NAME, now_str],
                 )
                 .unwrap();
             }
@@ -234,9 +614,44 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
 
         let db = self.db.lock().unwrap();
 
+        if !is_valid_view_name(&request.workspace_name) {
+            return Err(SagittaRemoteSystemDBError::InvalidName);
+        }
+        let count: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM workspace WHERE name = ? AND deleted_at IS NULL",
+                rusqlite::params![request.workspace_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count > 0 {
+            return Err(SagittaRemoteSystemDBError::WorkspaceAlreadyExists);
+        }
+        let count: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM branch WHERE branch_name = ?",
+                rusqlite::params![request.workspace_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count > 0 {
+            return Err(SagittaRemoteSystemDBError::NameTaken);
+        }
+
+        let base_commit_rank: Option<i64> = db
+            .query_row(
+                "SELECT MAX(commit_rank) FROM `commit` WHERE branch_name = ?",
+                rusqlite::params![request.branch_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let Some(base_commit_rank) = base_commit_rank else {
+            return Err(SagittaRemoteSystemDBError::BranchNotFound);
+        };
+
         db.execute(
-            "INSERT INTO workspace (workspace_id, name, created_at) VALUES (?, ?, ?)",
-            rusqlite::params![id, request.workspace_name, now_str],
+            "INSERT INTO workspace (workspace_id, name, branch_name, base_commit_rank, created_at) VALUES (?, ?, ?, ?, ?)",
+            rusqlite::params![id, request.workspace_name, request.branch_name, base_commit_rank, now_str],
         )
         .unwrap();
 
@@ -251,15 +666,17 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
 
         if request.contains_deleted {
             let mut stmt = db
-                .prepare("SELECT workspace_id, name, created_at, deleted_at FROM workspace")
+                .prepare("SELECT workspace_id, name, branch_name, base_commit_rank, created_at, deleted_at FROM workspace")
                 .unwrap();
             let workspaces = stmt
                 .query_map(rusqlite::params![], |row| {
-                    let created_at: String = row.get(2)?;
-                    let deleted_at: Option<String> = row.get(3)?;
+                    let created_at: String = row.get(4)?;
+                    let deleted_at: Option<String> = row.get(5)?;
                     Ok(GetWorkspacesResponseItem {
                         workspace_id: row.get(0)?,
                         workspace_name: row.get(1)?,
+                        branch_name: row.get(2)?,
+                        base_commit_rank: row.get(3)?,
                         created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().into(),
                         deleted_at: deleted_at
                             .map(|x| DateTime::parse_from_rfc3339(&x).unwrap().into()),
@@ -271,14 +688,16 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
 
             Ok(GetWorkspacesResponse { workspaces })
         } else {
-            let mut stmt = db.prepare("SELECT workspace_id, name, created_at, deleted_at FROM workspace WHERE deleted_at IS NULL").unwrap();
+            let mut stmt = db.prepare("SELECT workspace_id, name, branch_name, base_commit_rank, created_at, deleted_at FROM workspace WHERE deleted_at IS NULL").unwrap();
             let workspaces = stmt
                 .query_map(rusqlite::params![], |row| {
-                    let created_at: String = row.get(2)?;
-                    let deleted_at: Option<String> = row.get(3)?;
+                    let created_at: String = row.get(4)?;
+                    let deleted_at: Option<String> = row.get(5)?;
                     Ok(GetWorkspacesResponseItem {
                         workspace_id: row.get(0)?,
                         workspace_name: row.get(1)?,
+                        branch_name: row.get(2)?,
+                        base_commit_rank: row.get(3)?,
                         created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().into(),
                         deleted_at: deleted_at
                             .map(|x| DateTime::parse_from_rfc3339(&x).unwrap().into()),
@@ -397,6 +816,19 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
         let mut db = self.db.lock().unwrap();
         let mut tx = db.transaction().unwrap();
 
+        // a retried sync that was already applied changes nothing
+        if let Some(sync_id) = &request.sync_id {
+            let inserted = tx
+                .execute(
+                    "INSERT OR IGNORE INTO workspace_sync (workspace_id, sync_id, created_at) VALUES (?, ?, ?)",
+                    rusqlite::params![request.workspace_id, sync_id, now_str],
+                )
+                .unwrap();
+            if inserted == 0 {
+                return Ok(SyncFilesToWorkspaceResponse {});
+            }
+        }
+
         let version_number = tx
             .query_row(
                 "SELECT MAX(sync_version_number) FROM workspace_file_revision WHERE workspace_id = ?",
@@ -413,6 +845,8 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                     file_path,
                     blob_id,
                     permission,
+                    xattrs,
+                    modified_at,
                 } => {
                     for i in 1..file_path.len() {
                         let file_path = self
@@ -443,18 +877,80 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                             &mut tx,
                         )
                         .unwrap();
+                    // a file renamed earlier in this sync keeps where it came from
+                    let renamed_from = self.take_revision_at_version_tx(
+                        &request.workspace_id,
+                        &file_path.file_path_id,
+                        version_number,
+                        &tx,
+                    );
+                    let revision_id = self.generate_id();
+                    let modified_at = modified_at.map(|t| DateTime::<Utc>::from(t).to_rfc3339());
                     tx
                         .execute(
-                            "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, blob_id, file_type, created_at, permission) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-                            rusqlite::params![self.generate_id(), request.workspace_id, file_path.file_path_id, version_number, blob_id, 0, now_str, permission],
+                            "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, blob_id, modified_at, file_type, created_at, permission, renamed_from) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                            rusqlite::params![revision_id, request.workspace_id, file_path.file_path_id, version_number, blob_id, modified_at, 0, now_str, permission, renamed_from],
                         )
                         .unwrap();
+                    for (name, value) in xattrs {
+                        tx.execute(
+                            "INSERT INTO file_revision_xattr (file_revision_id, name, value) VALUES (?, ?, ?)",
+                            rusqlite::params![revision_id, name, value],
+                        )
+                        .unwrap();
+                    }
                 }
                 SyncFilesToWorkspaceRequestItem::UpsertDir {
                     file_path,
                     permission,
                 } => {
-                    for i in 1..=file_path.len() {
+                    for i in 1..file_path.len() {
+                        let file_path = self
+                            .get_or_create_file_path_tx(
+                                GetOrCreateFilePathRequest {
+                                    path: file_path[..i].to_vec(),
+                                },
+                                &mut tx,
+                            )
+                            .unwrap();
+
+                        if inserted.contains(&file_path.file_path_id) {
+                            continue;
+                        }
+                        inserted.insert(file_path.file_path_id.clone());
+
+                        tx
+                            .execute(
+                                "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, file_type, created_at, permission) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                                rusqlite::params![self.generate_id(), request.workspace_id, file_path.file_path_id, version_number, 1, now_str, 0o755],
+                            )
+                            .unwrap();
+                    }
+
+                    // the dir itself always takes its permission, even when created or
+                    // renamed earlier in this sync
+                    let file_path = self
+                        .get_or_create_file_path_tx(
+                            GetOrCreateFilePathRequest { path: file_path },
+                            &mut tx,
+                        )
+                        .unwrap();
+                    let renamed_from = self.take_revision_at_version_tx(
+                        &request.workspace_id,
+                        &file_path.file_path_id,
+                        version_number,
+                        &tx,
+                    );
+                    inserted.insert(file_path.file_path_id.clone());
+                    tx
+                        .execute(
+                            "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, file_type, created_at, permission, renamed_from) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                            rusqlite::params![self.generate_id(), request.workspace_id, file_path.file_path_id, version_number, 1, now_str, permission, renamed_from],
+                        )
+                        .unwrap();
+                }
+                SyncFilesToWorkspaceRequestItem::UpsertSymlink { file_path, target } => {
+                    for i in 1..file_path.len() {
                         let file_path = self
                             .get_or_create_file_path_tx(
                                 GetOrCreateFilePathRequest {
@@ -472,10 +968,29 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         tx
                             .execute(
                                 "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, file_type, created_at, permission) VALUES (?, ?, ?, ?, ?, ?, ?)",
-                                rusqlite::params![self.generate_id(), request.workspace_id, file_path.file_path_id, version_number, 1, now_str, permission],
+                                rusqlite::params![self.generate_id(), request.workspace_id, file_path.file_path_id, version_number, 1, now_str, 0o755],
                             )
                             .unwrap();
                     }
+
+                    let file_path = self
+                        .get_or_create_file_path_tx(
+                            GetOrCreateFilePathRequest { path: file_path },
+                            &mut tx,
+                        )
+                        .unwrap();
+                    let renamed_from = self.take_revision_at_version_tx(
+                        &request.workspace_id,
+                        &file_path.file_path_id,
+                        version_number,
+                        &tx,
+                    );
+                    tx
+                        .execute(
+                            "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, symlink_target, file_type, created_at, permission, renamed_from) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                            rusqlite::params![self.generate_id(), request.workspace_id, file_path.file_path_id, version_number, target, 2, now_str, 0o777, renamed_from],
+                        )
+                        .unwrap();
                 }
                 SyncFilesToWorkspaceRequestItem::DeleteFile { file_path } => {
                     let file_path = self
@@ -484,6 +999,12 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                             &mut tx,
                         )
                         .unwrap();
+                    self.take_revision_at_version_tx(
+                        &request.workspace_id,
+                        &file_path.file_path_id,
+                        version_number,
+                        &tx,
+                    );
                     tx
                         .execute(
                             "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, file_type, created_at, deleted_at, permission) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
@@ -492,6 +1013,27 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         .unwrap();
                 }
                 SyncFilesToWorkspaceRequestItem::DeleteDir { file_path } => {
+                    // everything under the dir goes with it
+                    let descendants =
+                        self.get_workspace_subtree_tx(&request.workspace_id, &file_path, &tx);
+                    for descendant in descendants {
+                        if descendant.path.len() == file_path.len() {
+                            continue;
+                        }
+                        self.take_revision_at_version_tx(
+                            &request.workspace_id,
+                            &descendant.file_path_id,
+                            version_number,
+                            &tx,
+                        );
+                        tx
+                            .execute(
+                                "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, file_type, created_at, deleted_at, permission) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                                rusqlite::params![self.generate_id(), request.workspace_id, descendant.file_path_id, version_number, descendant.file_type, now_str, now_str, 0],
+                            )
+                            .unwrap();
+                    }
+
                     let file_path = self
                         .get_or_create_file_path_tx(
                             GetOrCreateFilePathRequest { path: file_path },
@@ -511,13 +1053,106 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         )
                         .unwrap();
                 }
-            }
-        }
-
-        tx.commit().unwrap();
+                SyncFilesToWorkspaceRequestItem::Rename { from, to } => {
+                    if from.is_empty() || to.is_empty() || to.starts_with(&from) {
+                        continue;
+                    }
+                    let sources = self.get_workspace_subtree_tx(&request.workspace_id, &from, &tx);
+                    if sources.is_empty() {
+                        continue;
+                    }
 
-        Ok(SyncFilesToWorkspaceResponse {})
-    }
+                    for i in 1..to.len() {
+                        let file_path = self
+                            .get_or_create_file_path_tx(
+                                GetOrCreateFilePathRequest {
+                                    path: to[..i].to_vec(),
+                                },
+                                &mut tx,
+                            )
+                            .unwrap();
+
+                        if inserted.contains(&file_path.file_path_id) {
+                            continue;
+                        }
+                        inserted.insert(file_path.file_path_id.clone());
+
+                        tx
+                            .execute(
+                                "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, file_type, created_at, permission) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                                rusqlite::params![self.generate_id(), request.workspace_id, file_path.file_path_id, version_number, 1, now_str, 0o755],
+                            )
+                            .unwrap();
+                    }
+
+                    for source in sources {
+                        let mut path = to.clone();
+                        path.extend_from_slice(&source.path[from.len()..]);
+                        let file_path = self
+                            .get_or_create_file_path_tx(
+                                GetOrCreateFilePathRequest { path },
+                                &mut tx,
+                            )
+                            .unwrap();
+                        self.take_revision_at_version_tx(
+                            &request.workspace_id,
+                            &file_path.file_path_id,
+                            version_number,
+                            &tx,
+                        );
+                        if source.file_type == 1 {
+                            inserted.insert(file_path.file_path_id.clone());
+                        }
+
+                        let revision_id = self.generate_id();
+                        tx
+                            .execute(
+                                "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, blob_id, symlink_target, modified_at, file_type, created_at, permission, renamed_from) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                                rusqlite::params![revision_id, request.workspace_id, file_path.file_path_id, version_number, source.blob_id, source.symlink_target, source.modified_at, source.file_type, now_str, source.permission, source.file_path_id],
+                            )
+                            .unwrap();
+                        self.copy_xattrs_tx(&source.revision_id, &revision_id, &tx);
+
+                        self.take_revision_at_version_tx(
+                            &request.workspace_id,
+                            &source.file_path_id,
+                            version_number,
+                            &tx,
+                        );
+                        tx
+                            .execute(
+                                "INSERT INTO workspace_file_revision (workspace_file_revision_id, workspace_id, file_path_id, sync_version_number, file_type, created_at, deleted_at, permission) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                                rusqlite::params![self.generate_id(), request.workspace_id, source.file_path_id, version_number, source.file_type, now_str, now_str, 0],
+                            )
+                            .unwrap();
+                    }
+                }
+            }
+        }
+
+        tx.commit().unwrap();
+
+        Ok(SyncFilesToWorkspaceResponse {})
+    }
+
+    fn get_sync_state(
+        &self,
+        request: GetSyncStateRequest,
+    ) -> Result<GetSyncStateResponse, SagittaRemoteSystemDBError> {
+        let db = self.db.lock().unwrap();
+        let count: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM workspace_sync WHERE workspace_id = ? AND sync_id = ?",
+                rusqlite::params![request.workspace_id, request.sync_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count > 0 {
+            Ok(GetSyncStateResponse::Applied)
+        } else {
+            Ok(GetSyncStateResponse::NotApplied)
+        }
+    }
 
     fn get_workspace_changelist(
         &self,
@@ -553,6 +1188,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         file_type: match file_type {
                             0 => SagittaFileType::File,
                             1 => SagittaFileType::Dir,
+                            2 => SagittaFileType::Symlink,
                             _ => unreachable!(),
                         },
                     })
@@ -576,40 +1212,238 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
         let now: DateTime<Utc> = now.into();
         let now_str = now.to_rfc3339();
 
+        let (branch_name, base_commit_rank): (String, i64) = tx
+            .query_row(
+                "SELECT branch_name, base_commit_rank FROM workspace WHERE workspace_id = ?",
+                rusqlite::params![request.workspace_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|_| SagittaRemoteSystemDBError::WorkspaceNotFound)?;
+
+        // paths the branch changed after the base of the workspace would be overwritten.
+        // Every pending path is checked, not only the committed ones, because a workspace
+        // left open moves its base past those changes. Every commit rewrites the dirs
+        // above the files it touches, so a dir that stays a dir on both sides is no conflict.
+        let conflicts = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT
+                        file_path.path,
+                        workspace_file_revision.file_type,
+                        workspace_file_revision.deleted_at,
+                        (
+                            SELECT COUNT(*) FROM trunk_file_revision
+                            WHERE trunk_file_revision.file_path_id = workspace_file_revision.file_path_id
+                            AND trunk_file_revision.branch_name = ? AND trunk_file_revision.commit_rank > ?
+                        ),
+                        (
+                            SELECT COUNT(*) FROM trunk_file_revision
+                            WHERE trunk_file_revision.file_path_id = workspace_file_revision.file_path_id
+                            AND trunk_file_revision.branch_name = ? AND trunk_file_revision.commit_rank > ?
+                            AND (trunk_file_revision.file_type != 1 OR trunk_file_revision.deleted_at IS NOT NULL)
+                        )
+                    FROM workspace_file_revision
+                    JOIN (
+                        SELECT file_path_id, MAX(sync_version_number) AS sync_version_number
+                        FROM workspace_file_revision AS workspace_file_revision_2
+                        WHERE workspace_file_revision_2.workspace_id = ?
+                        GROUP BY workspace_file_revision_2.file_path_id
+                    ) AS latest_sync_version
+                    ON workspace_file_revision.file_path_id = latest_sync_version.file_path_id AND workspace_file_revision.sync_version_number = latest_sync_version.sync_version_number
+                    JOIN file_path ON workspace_file_revision.file_path_id = file_path.file_path_id
+                    WHERE workspace_file_revision.workspace_id = ?",
+                )
+                .unwrap();
+            let rows: Vec<(String, i64, Option<String>, i64, i64)> = stmt
+                .query_map(
+                    rusqlite::params![
+                        branch_name,
+                        base_commit_rank,
+                        branch_name,
+                        base_commit_rank,
+                        request.workspace_id,
+                        request.workspace_id
+                    ],
+                    |row| {
+                        Ok((
+                            row.get(0)?,
+                            row.get(1)?,
+                            row.get(2)?,
+                            row.get(3)?,
+                            row.get(4)?,
+                        ))
+                    },
+                )
+                .unwrap()
+                .map(|x| x.unwrap())
+                .collect();
+            let mut conflicts: Vec<String> = rows
+                .into_iter()
+                .filter(|(_, file_type, deleted_at, later, later_non_dirs)| {
+                    let dir = *file_type == 1 && deleted_at.is_none();
+                    *later > 0 && !(dir && *later_non_dirs == 0)
+                })
+                .map(|(path, _, _, _, _)| path)
+                .collect();
+            conflicts.sort();
+            conflicts
+        };
+        if !conflicts.is_empty() {
+            return Ok(CommitResponse::Conflict {
+                file_paths: conflicts,
+            });
+        }
+
         let commit_id = self.generate_id();
         let commit_rank = tx
             .query_row(
-                "SELECT MAX(commit_rank) FROM trunk_file_revision",
+                "SELECT MAX(commit_rank) FROM `commit`",
                 rusqlite::params![],
                 |row| row.get(0),
             )
             .unwrap_or(0)
             + 1;
 
-        {
+        // select revisions under the requested path prefixes (partial commit)
+        let selected_revisions = request.paths.as_ref().map(|prefixes| {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT
+                        workspace_file_revision.workspace_file_revision_id,
+                        workspace_file_revision.file_path_id,
+                        file_path.path,
+                        workspace_file_revision.file_type,
+                        workspace_file_revision.deleted_at
+                    FROM workspace_file_revision
+                    JOIN (
+                        SELECT file_path_id, MAX(sync_version_number) AS sync_version_number
+                        FROM workspace_file_revision AS workspace_file_revision_2
+                        WHERE workspace_file_revision_2.workspace_id = ?
+                        GROUP BY workspace_file_revision_2.file_path_id
+                    ) AS latest_sync_version
+                    ON workspace_file_revision.file_path_id = latest_sync_version.file_path_id AND workspace_file_revision.sync_version_number = latest_sync_version.sync_version_number
+                    JOIN file_path ON workspace_file_revision.file_path_id = file_path.file_path_id
+                    WHERE workspace_file_revision.workspace_id = ?",
+                )
+                .unwrap();
+            let revisions: Vec<(String, String, String, i64, Option<String>)> = stmt
+                .query_map(
+                    rusqlite::params![request.workspace_id, request.workspace_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+                )
+                .unwrap()
+                .map(|x| x.unwrap())
+                .collect();
+
+            let prefixes: Vec<String> = prefixes.iter().map(|x| x.join("/")).collect();
+            let selected_paths: HashSet<String> = revisions
+                .iter()
+                .filter(|(_, _, path, _, _)| {
+                    prefixes.iter().any(|prefix| {
+                        prefix.is_empty()
+                            || path == prefix
+                            || path.starts_with(&format!("{}/", prefix))
+                    })
+                })
+                .map(|(_, _, path, _, _)| path.clone())
+                .collect();
+
+            // parent directories of selected paths must exist in trunk too
+            let mut required_paths = selected_paths.clone();
+            for path in &selected_paths {
+                let chunks: Vec<&str> = path.split('/').collect();
+                for i in 1..chunks.len() {
+                    required_paths.insert(chunks[..i].join("/"));
+                }
+            }
+
+            revisions
+                .into_iter()
+                .filter(|(_, _, path, file_type, deleted_at)| {
+                    selected_paths.contains(path)
+                        || (required_paths.contains(path) && *file_type == 1 && deleted_at.is_none())
+                })
+                .map(|(revision_id, file_path_id, _, _, _)| (revision_id, file_path_id))
+                .collect::<Vec<(String, String)>>()
+        });
+
+        if let Some(selected_revisions) = &selected_revisions {
             let mut stmt = tx
                 .prepare(
                     "INSERT INTO trunk_file_revision (
                         trunk_file_revision_id,
                         file_path_id,
+                        branch_name,
                         commit_id,
                         commit_rank,
                         blob_id,
+                        symlink_target,
+                        modified_at,
                         file_type,
                         created_at,
                         deleted_at,
-                        permission
+                        permission,
+                        renamed_from
+                    )
+                    SELECT
+                        workspace_file_revision_id,
+                        file_path_id,
+                        ?,
+                        ?,
+                        ?,
+                        blob_id,
+                        symlink_target,
+                        modified_at,
+                        file_type,
+                        created_at,
+                        deleted_at,
+                        permission,
+                        renamed_from
+                    FROM workspace_file_revision
+                    WHERE workspace_file_revision_id = ?",
+                )
+                .unwrap();
+            for (revision_id, _) in selected_revisions {
+                stmt.execute(rusqlite::params![
+                    branch_name,
+                    commit_id,
+                    commit_rank,
+                    revision_id
+                ])
+                .unwrap();
+            }
+        } else {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO trunk_file_revision (
+                        trunk_file_revision_id,
+                        file_path_id,
+                        branch_name,
+                        commit_id,
+                        commit_rank,
+                        blob_id,
+                        symlink_target,
+                        modified_at,
+                        file_type,
+                        created_at,
+                        deleted_at,
+                        permission,
+                        renamed_from
                     )
                     SELECT
                         workspace_file_revision_id,
                         workspace_file_revision.file_path_id,
                         ?,
                         ?,
+                        ?,
                         blob_id,
+                        symlink_target,
+                        modified_at,
                         file_type,
                         created_at,
                         deleted_at,
-                        permission
+                        permission,
+                        renamed_from
                     FROM workspace_file_revision
                     JOIN (
                         SELECT file_path_id, MAX(sync_version_number) AS sync_version_number
@@ -622,6 +1456,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                 )
                 .unwrap();
             stmt.execute(rusqlite::params![
+                branch_name,
                 commit_id,
                 commit_rank,
                 request.workspace_id,
@@ -632,28 +1467,694 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
 
         {
             tx.execute(
-                "INSERT INTO `commit` (commit_id, commit_rank, created_at) VALUES (?, ?, ?)",
-                rusqlite::params![commit_id, commit_rank, now_str],
+                "INSERT INTO `commit` (commit_id, commit_rank, branch_name, created_at) VALUES (?, ?, ?, ?)",
+                rusqlite::params![commit_id, commit_rank, branch_name, now_str],
+            )
+            .unwrap();
+        }
+
+        if let Some(selected_revisions) = &selected_revisions {
+            // the rest of the revisions remain pending, so the workspace is kept open
+            // until a commit leaves none of them
+            tx.execute(
+                "UPDATE workspace SET base_commit_rank = ? WHERE workspace_id = ?",
+                rusqlite::params![commit_rank, request.workspace_id],
+            )
+            .unwrap();
+            let mut stmt = tx
+                .prepare(
+                    "DELETE FROM workspace_file_revision WHERE workspace_id = ? AND file_path_id = ?",
+                )
+                .unwrap();
+            for (_, file_path_id) in selected_revisions {
+                stmt.execute(rusqlite::params![request.workspace_id, file_path_id])
+                    .unwrap();
+            }
+            let remaining: i64 = tx
+                .query_row(
+                    "SELECT COUNT(*) FROM workspace_file_revision WHERE workspace_id = ?",
+                    rusqlite::params![request.workspace_id],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            if remaining == 0 && !request.keep_workspace {
+                tx.execute(
+                    "UPDATE workspace SET deleted_at = ? WHERE workspace_id = ?",
+                    rusqlite::params![now_str, request.workspace_id],
+                )
+                .unwrap();
+            }
+        } else if request.keep_workspace {
+            tx.execute(
+                "UPDATE workspace SET base_commit_rank = ? WHERE workspace_id = ?",
+                rusqlite::params![commit_rank, request.workspace_id],
+            )
+            .unwrap();
+            tx.execute(
+                "DELETE FROM workspace_file_revision WHERE workspace_id = ?",
+                rusqlite::params![request.workspace_id],
+            )
+            .unwrap();
+        } else {
+            let mut stmt = tx
+                .prepare("UPDATE workspace SET deleted_at = ? WHERE workspace_id = ?")
+                .unwrap();
+            stmt.execute(rusqlite::params![now_str, request.workspace_id])
+                .unwrap();
+        }
+
+        tx.commit().unwrap();
+
+        Ok(CommitResponse::Committed {
+            commit_id,
+            commit_rank,
+        })
+    }
+
+    fn revert_commit(
+        &self,
+        request: RevertCommitRequest,
+    ) -> Result<RevertCommitResponse, SagittaRemoteSystemDBError> {
+        let mut db = self.db.lock().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let now = self.clock.now();
+        let now: DateTime<Utc> = now.into();
+        let now_str = now.to_rfc3339();
+
+        let branch_name: String = match tx.query_row(
+            "SELECT branch_name FROM `commit` WHERE commit_rank = ?",
+            rusqlite::params![request.commit_rank],
+            |row| row.get(0),
+        ) {
+            Ok(branch_name) => branch_name,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(RevertCommitResponse::NotFound),
+            Err(_) => return Err(SagittaRemoteSystemDBError::InternalError),
+        };
+        if request.commit_rank == 0 {
+            return Ok(RevertCommitResponse::NotFound);
+        }
+
+        let targets: Vec<(String, String, i64)> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT trunk_file_revision.file_path_id, file_path.path, trunk_file_revision.file_type
+                    FROM trunk_file_revision
+                    JOIN file_path ON trunk_file_revision.file_path_id = file_path.file_path_id
+                    WHERE trunk_file_revision.commit_rank = ?",
+                )
+                .unwrap();
+            stmt.query_map(rusqlite::params![request.commit_rank], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect()
+        };
+
+        // paths changed again by later commits cannot be reverted safely. Every commit
+        // rewrites the dirs above the files it touches, so a dir changed later is not a
+        // conflict; it is left as it is instead.
+        let mut conflicts = vec![];
+        let mut changed_later_dirs = HashSet::new();
+        for (file_path_id, path, file_type) in &targets {
+            let later: i64 = tx
+                .query_row(
+                    "SELECT COUNT(*) FROM trunk_file_revision WHERE file_path_id = ? AND branch_name = ? AND commit_rank > ?",
+                    rusqlite::params![file_path_id, branch_name, request.commit_rank],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            if later > 0 && *file_type == 1 {
+                changed_later_dirs.insert(file_path_id.clone());
+            } else if later > 0 {
+                conflicts.push(path.clone());
+            }
+        }
+        if !conflicts.is_empty() {
+            conflicts.sort();
+            return Ok(RevertCommitResponse::Conflict {
+                file_paths: conflicts,
+            });
+        }
+
+        let commit_id = self.generate_id();
+        let commit_rank = tx
+            .query_row(
+                "SELECT MAX(commit_rank) FROM `commit`",
+                rusqlite::params![],
+                |row| row.get(0),
+            )
+            .unwrap_or(0)
+            + 1;
+
+        for (file_path_id, _, file_type) in &targets {
+            if changed_later_dirs.contains(file_path_id) {
+                continue;
+            }
+            let previous = tx.query_row(
+                "SELECT trunk_file_revision_id, blob_id, symlink_target, file_type, deleted_at, permission, modified_at, renamed_from FROM trunk_file_revision
+                WHERE file_path_id = ? AND branch_name = ? AND commit_rank < ?
+                ORDER BY commit_rank DESC LIMIT 1",
+                rusqlite::params![file_path_id, branch_name, request.commit_rank],
+                |row| {
+                    let previous_revision_id: String = row.get(0)?;
+                    let blob_id: Option<String> = row.get(1)?;
+                    let symlink_target: Option<String> = row.get(2)?;
+                    let file_type: i64 = row.get(3)?;
+                    let deleted_at: Option<String> = row.get(4)?;
+                    let permission: i64 = row.get(5)?;
+                    let modified_at: Option<String> = row.get(6)?;
+                    let renamed_from: Option<String> = row.get(7)?;
+                    Ok((
+                        previous_revision_id,
+                        blob_id,
+                        symlink_target,
+                        file_type,
+                        deleted_at,
+                        permission,
+                        modified_at,
+                        renamed_from,
+                    ))
+                },
+            );
+
+            match previous {
+                Ok((
+                    previous_revision_id,
+                    blob_id,
+                    symlink_target,
+                    file_type,
+                    deleted_at,
+                    permission,
+                    modified_at,
+                    renamed_from,
+                )) => {
+                    let deleted_at = deleted_at.map(|_| now_str.clone());
+                    let revision_id = self.generate_id();
+                    tx.execute(
+                        "INSERT INTO trunk_file_revision (trunk_file_revision_id, file_path_id, branch_name, commit_id, commit_rank, blob_id, symlink_target, modified_at, file_type, created_at, deleted_at, permission, renamed_from) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                        rusqlite::params![revision_id, file_path_id, branch_name, commit_id, commit_rank, blob_id, symlink_target, modified_at, file_type, now_str, deleted_at, permission, renamed_from],
+                    )
+                    .unwrap();
+                    self.copy_xattrs_tx(&previous_revision_id, &revision_id, &tx);
+                }
+                Err(rusqlite::Error::QueryReturnedNoRows) => {
+                    // the path was created by the reverted commit
+                    tx.execute(
+                        "INSERT INTO trunk_file_revision (trunk_file_revision_id, file_path_id, branch_name, commit_id, commit_rank, file_type, created_at, deleted_at, permission) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                        rusqlite::params![self.generate_id(), file_path_id, branch_name, commit_id, commit_rank, file_type, now_str, now_str, 0],
+                    )
+                    .unwrap();
+                }
+                Err(_) => return Err(SagittaRemoteSystemDBError::InternalError),
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO `commit` (commit_id, commit_rank, branch_name, created_at) VALUES (?, ?, ?, ?)",
+            rusqlite::params![commit_id, commit_rank, branch_name, now_str],
+        )
+        .unwrap();
+
+        tx.commit().unwrap();
+
+        Ok(RevertCommitResponse::Reverted {
+            commit_id,
+            commit_rank,
+        })
+    }
+
+    fn create_tag(
+        &self,
+        request: CreateTagRequest,
+    ) -> Result<CreateTagResponse, SagittaRemoteSystemDBError> {
+        let mut db = self.db.lock().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let now = self.clock.now();
+        let now: DateTime<Utc> = now.into();
+        let now_str = now.to_rfc3339();
+
+        let count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM tag WHERE name = ?",
+                rusqlite::params![request.tag_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count > 0 {
+            return Ok(CreateTagResponse::AlreadyExists);
+        }
+
+        let commit_id: String = match tx.query_row(
+            "SELECT commit_id FROM `commit` WHERE commit_rank = ?",
+            rusqlite::params![request.commit_rank],
+            |row| row.get(0),
+        ) {
+            Ok(commit_id) => commit_id,
+            Err(_) => return Ok(CreateTagResponse::CommitNotFound),
+        };
+
+        let tag_id = self.generate_id();
+        tx.execute(
+            "INSERT INTO tag (tag_id, name, commit_id, created_at) VALUES (?, ?, ?, ?)",
+            rusqlite::params![tag_id, request.tag_name, commit_id, now_str],
+        )
+        .unwrap();
+
+        tx.commit().unwrap();
+
+        Ok(CreateTagResponse::Created { tag_id })
+    }
+
+    fn get_tags(
+        &self,
+        _request: GetTagsRequest,
+    ) -> Result<GetTagsResponse, SagittaRemoteSystemDBError> {
+        let db = self.db.lock().unwrap();
+
+        let mut stmt = db
+            .prepare(
+                "SELECT tag.tag_id, tag.name, tag.commit_id, `commit`.commit_rank, `commit`.branch_name, tag.created_at FROM tag
+                JOIN `commit` ON tag.commit_id = `commit`.commit_id
+                ORDER BY tag.name",
+            )
+            .unwrap();
+        let res = stmt
+            .query_map(rusqlite::params![], |row| {
+                let created_at: String = row.get(5)?;
+                Ok(GetTagsResponseItem {
+                    tag_id: row.get(0)?,
+                    tag_name: row.get(1)?,
+                    commit_id: row.get(2)?,
+                    commit_rank: row.get(3)?,
+                    branch_name: row.get(4)?,
+                    created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().into(),
+                })
+            })
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+
+        Ok(GetTagsResponse { items: res })
+    }
+
+    fn delete_tag(
+        &self,
+        request: DeleteTagRequest,
+    ) -> Result<DeleteTagResponse, SagittaRemoteSystemDBError> {
+        let db = self.db.lock().unwrap();
+
+        let affected = db
+            .execute(
+                "DELETE FROM tag WHERE name = ?",
+                rusqlite::params![request.tag_name],
+            )
+            .unwrap();
+
+        if affected == 0 {
+            return Ok(DeleteTagResponse::NotFound);
+        }
+
+        Ok(DeleteTagResponse::Deleted)
+    }
+
+    fn create_branch(
+        &self,
+        request: CreateBranchRequest,
+    ) -> Result<CreateBranchResponse, SagittaRemoteSystemDBError> {
+        let mut db = self.db.lock().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let now = self.clock.now();
+        let now: DateTime<Utc> = now.into();
+        let now_str = now.to_rfc3339();
+
+        if !is_valid_view_name(&request.branch_name) {
+            return Ok(CreateBranchResponse::InvalidName);
+        }
+
+        let count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM branch WHERE branch_name = ?",
+                rusqlite::params![request.branch_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count > 0 {
+            return Ok(CreateBranchResponse::AlreadyExists);
+        }
+        let count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM workspace WHERE name = ? AND deleted_at IS NULL",
+                rusqlite::params![request.branch_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count > 0 {
+            return Ok(CreateBranchResponse::NameTaken);
+        }
+
+        let count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM branch WHERE branch_name = ?",
+                rusqlite::params![request.source_branch_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count == 0 {
+            return Ok(CreateBranchResponse::SourceNotFound);
+        }
+
+        let commit_id = self.generate_id();
+        let commit_rank = tx
+            .query_row(
+                "SELECT MAX(commit_rank) FROM `commit`",
+                rusqlite::params![],
+                |row| row.get(0),
+            )
+            .unwrap_or(0)
+            + 1;
+
+        // the first commit of a branch is a snapshot of the source branch
+        let revision_ids: Vec<String> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT trunk_file_revision.trunk_file_revision_id
+                    FROM trunk_file_revision
+                    JOIN (
+                        SELECT file_path_id, MAX(commit_rank) AS commit_rank
+                        FROM trunk_file_revision AS trunk_file_revision_2
+                        WHERE trunk_file_revision_2.branch_name = ?
+                        GROUP BY trunk_file_revision_2.file_path_id
+                    ) AS latest_commit_version
+                    ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
+                    JOIN file_path ON trunk_file_revision.file_path_id = file_path.file_path_id
+                    WHERE trunk_file_revision.deleted_at IS NULL
+                    ORDER BY file_path.path",
+                )
+                .unwrap();
+            stmt.query_map(rusqlite::params![request.source_branch_name], |row| {
+                row.get(0)
+            })
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect()
+        };
+
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO trunk_file_revision (
+                        trunk_file_revision_id,
+                        file_path_id,
+                        branch_name,
+                        commit_id,
+                        commit_rank,
+                        blob_id,
+                        symlink_target,
+                        modified_at,
+                        file_type,
+                        created_at,
+                        permission
+                    )
+                    SELECT
+                        ?,
+                        file_path_id,
+                        ?,
+                        ?,
+                        ?,
+                        blob_id,
+                        symlink_target,
+                        modified_at,
+                        file_type,
+                        created_at,
+                        permission
+                    FROM trunk_file_revision
+                    WHERE trunk_file_revision_id = ?",
+                )
+                .unwrap();
+            for revision_id in &revision_ids {
+                let new_revision_id = self.generate_id();
+                stmt.execute(rusqlite::params![
+                    new_revision_id,
+                    request.branch_name,
+                    commit_id,
+                    commit_rank,
+                    revision_id
+                ])
+                .unwrap();
+                self.copy_xattrs_tx(revision_id, &new_revision_id, &tx);
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO `commit` (commit_id, commit_rank, branch_name, created_at) VALUES (?, ?, ?, ?)",
+            rusqlite::params![commit_id, commit_rank, request.branch_name, now_str],
+        )
+        .unwrap();
+
+        tx.execute(
+            "INSERT INTO branch (branch_name, created_at) VALUES (?, ?)",
+            rusqlite::params![request.branch_name, now_str],
+        )
+        .unwrap();
+
+        tx.commit().unwrap();
+
+        Ok(CreateBranchResponse::Created {
+            commit_id,
+            commit_rank,
+        })
+    }
+
+    fn get_branches(
+        &self,
+        _request: GetBranchesRequest,
+    ) -> Result<GetBranchesResponse, SagittaRemoteSystemDBError> {
+        let db = self.db.lock().unwrap();
+
+        let mut stmt = db
+            .prepare(
+                "SELECT branch.branch_name, MAX(`commit`.commit_rank), branch.created_at FROM branch
+                JOIN `commit` ON branch.branch_name = `commit`.branch_name
+                GROUP BY branch.branch_name
+                ORDER BY branch.branch_name",
+            )
+            .unwrap();
+        let res = stmt
+            .query_map(rusqlite::params![], |row| {
+                let created_at: String = row.get(2)?;
+                Ok(GetBranchesResponseItem {
+                    branch_name: row.get(0)?,
+                    head_commit_rank: row.get(1)?,
+                    created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().into(),
+                })
+            })
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+
+        Ok(GetBranchesResponse { items: res })
+    }
+
+    fn cherry_pick(
+        &self,
+        request: CherryPickRequest,
+    ) -> Result<CherryPickResponse, SagittaRemoteSystemDBError> {
+        let mut db = self.db.lock().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let now = self.clock.now();
+        let now: DateTime<Utc> = now.into();
+        let now_str = now.to_rfc3339();
+
+        let count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM `commit` WHERE commit_rank = ?",
+                rusqlite::params![request.commit_rank],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count == 0 || request.commit_rank == 0 {
+            return Ok(CherryPickResponse::NotFound);
+        }
+
+        let count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM branch WHERE branch_name = ?",
+                rusqlite::params![request.branch_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        if count == 0 {
+            return Ok(CherryPickResponse::BranchNotFound);
+        }
+
+        let revision_ids: Vec<String> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT trunk_file_revision.trunk_file_revision_id
+                    FROM trunk_file_revision
+                    JOIN file_path ON trunk_file_revision.file_path_id = file_path.file_path_id
+                    WHERE trunk_file_revision.commit_rank = ?
+                    ORDER BY file_path.path",
+                )
+                .unwrap();
+            stmt.query_map(rusqlite::params![request.commit_rank], |row| row.get(0))
+                .unwrap()
+                .map(|x| x.unwrap())
+                .collect()
+        };
+
+        let commit_id = self.generate_id();
+        let commit_rank = tx
+            .query_row(
+                "SELECT MAX(commit_rank) FROM `commit`",
+                rusqlite::params![],
+                |row| row.get(0),
+            )
+            .unwrap_or(0)
+            + 1;
+
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO trunk_file_revision (
+                        trunk_file_revision_id,
+                        file_path_id,
+                        branch_name,
+                        commit_id,
+                        commit_rank,
+                        blob_id,
+                        symlink_target,
+                        modified_at,
+                        file_type,
+                        created_at,
+                        deleted_at,
+                        permission,
+                        renamed_from
+                    )
+                    SELECT
+                        ?,
+                        file_path_id,
+                        ?,
+                        ?,
+                        ?,
+                        blob_id,
+                        symlink_target,
+                        modified_at,
+                        file_type,
+                        created_at,
+                        deleted_at,
+                        permission,
+                        renamed_from
+                    FROM trunk_file_revision
+                    WHERE trunk_file_revision_id = ?",
+                )
+                .unwrap();
+            for revision_id in &revision_ids {
+                let new_revision_id = self.generate_id();
+                stmt.execute(rusqlite::params![
+                    new_revision_id,
+                    request.branch_name,
+                    commit_id,
+                    commit_rank,
+                    revision_id
+                ])
+                .unwrap();
+                self.copy_xattrs_tx(revision_id, &new_revision_id, &tx);
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO `commit` (commit_id, commit_rank, branch_name, created_at) VALUES (?, ?, ?, ?)",
+            rusqlite::params![commit_id, commit_rank, request.branch_name, now_str],
+        )
+        .unwrap();
+
+        tx.commit().unwrap();
+
+        Ok(CherryPickResponse::Picked {
+            commit_id,
+            commit_rank,
+        })
+    }
+
+    fn get_file_history(
+        &self,
+        request: GetFileHistoryRequest,
+    ) -> Result<GetFileHistoryResponse, SagittaRemoteSystemDBError> {
+        let db = self.db.lock().unwrap();
+
+        let mut stmt = db
+            .prepare(
+                "SELECT
+                    trunk_file_revision.commit_id,
+                    trunk_file_revision.commit_rank,
+                    trunk_file_revision.blob_id,
+                    trunk_file_revision.file_type,
+                    trunk_file_revision.permission,
+                    trunk_file_revision.deleted_at,
+                    trunk_file_revision.created_at,
+                    renamed_from_path.path
+                FROM trunk_file_revision
+                JOIN file_path ON trunk_file_revision.file_path_id = file_path.file_path_id
+                LEFT JOIN file_path AS renamed_from_path ON trunk_file_revision.renamed_from = renamed_from_path.file_path_id
+                WHERE file_path.path = ? AND trunk_file_revision.branch_name = ? AND trunk_file_revision.commit_rank < ?
+                ORDER BY trunk_file_revision.commit_rank",
+            )
+            .unwrap();
+        let mut query = |path: &str, before_commit_rank: i64| {
+            stmt.query_map(
+                rusqlite::params![path, request.branch_name, before_commit_rank],
+                |row| {
+                    let file_type: i64 = row.get(3)?;
+                    let deleted_at: Option<String> = row.get(5)?;
+                    let created_at: String = row.get(6)?;
+                    let renamed_from: Option<String> = row.get(7)?;
+                    Ok(GetFileHistoryResponseItem {
+                        commit_id: row.get(0)?,
+                        commit_rank: row.get(1)?,
+                        blob_id: row.get(2)?,
+                        file_type: match file_type {
+                            0 => SagittaFileType::File,
+                            1 => SagittaFileType::Dir,
+                            2 => SagittaFileType::Symlink,
+                            _ => unreachable!(),
+                        },
+                        permission: row.get(4)?,
+                        deleted: deleted_at.is_some(),
+                        created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().into(),
+                        renamed_from: renamed_from
+                            .map(|path| path.split('/').map(|x| x.to_string()).collect()),
+                    })
+                },
             )
-            .unwrap();
-        }
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect::<Vec<GetFileHistoryResponseItem>>()
+        };
 
+        // follow the file back through the paths it was renamed from
+        let mut res = query(&request.file_path.join("/"), i64::MAX);
+        let mut oldest = res.len();
+        while let Some((commit_rank, from)) = res[..oldest]
+            .iter()
+            .find_map(|item| Some((item.commit_rank, item.renamed_from.clone()?)))
         {
-            let mut stmt = tx
-                .prepare("UPDATE workspace SET deleted_at = ? WHERE workspace_id = ?")
-                .unwrap();
-            stmt.execute(rusqlite::params![now_str, request.workspace_id])
-                .unwrap();
+            let older = query(&from.join("/"), commit_rank);
+            oldest = older.len();
+            res.splice(0..0, older);
         }
 
-        tx.commit().unwrap();
-
-        Ok(CommitResponse {})
+        Ok(GetFileHistoryResponse { items: res })
     }
 
     fn get_all_trunk_files(
         &self,
-        _request: GetAllTrunkFilesRequest,
+        request: GetAllTrunkFilesRequest,
     ) -> Result<GetAllTrunkFilesResponse, SagittaRemoteSystemDBError> {
         let mut db = self.db.lock().unwrap();
         let tx = db.transaction().unwrap();
@@ -665,6 +2166,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                     JOIN (
                         SELECT file_path_id, MAX(commit_rank) AS commit_rank
                         FROM trunk_file_revision AS trunk_file_revision_2
+                        WHERE trunk_file_revision_2.branch_name = ?
                         GROUP BY trunk_file_revision_2.file_path_id
                     ) AS latest_commit_version
                     ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
@@ -672,7 +2174,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                     ",
                 )
                 .unwrap();
-            stmt.query_map(rusqlite::params![], |row| {
+            stmt.query_map(rusqlite::params![request.branch_name], |row| {
                 let deleted_at: Option<String> = row.get(2)?;
                 let file_type: i64 = row.get(3)?;
                 Ok(GetAllTrunkFilesResponseItem {
@@ -682,6 +2184,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                     file_type: match file_type {
                         0 => SagittaFileType::File,
                         1 => SagittaFileType::Dir,
+                        2 => SagittaFileType::Symlink,
                         _ => unreachable!(),
                     },
                 })
@@ -703,17 +2206,21 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
         let db = self.db.lock().unwrap();
 
         let mut stmt = db
-            .prepare("SELECT commit_id, commit_rank, created_at FROM `commit` ORDER BY commit_rank DESC LIMIT ?")
+            .prepare("SELECT commit_id, commit_rank, branch_name, created_at FROM `commit` WHERE ?1 IS NULL OR branch_name = ?1 ORDER BY commit_rank DESC LIMIT ?2")
             .unwrap();
         let res = stmt
-            .query_map(rusqlite::params![request.take], |row| {
-                let created_at: String = row.get(2)?;
-                Ok(GetCommitHistoryResponseItem {
-                    commit_id: row.get(0)?,
-                    commit_rank: row.get(1)?,
-                    created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().into(),
-                })
-            })
+            .query_map(
+                rusqlite::params![request.branch_name, request.take],
+                |row| {
+                    let created_at: String = row.get(3)?;
+                    Ok(GetCommitHistoryResponseItem {
+                        commit_id: row.get(0)?,
+                        commit_rank: row.get(1)?,
+                        branch_name: row.get(2)?,
+                        created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().into(),
+                    })
+                },
+            )
             .unwrap()
             .map(|x| x.unwrap())
             .collect();
@@ -727,6 +2234,9 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
     ) -> Result<ReadDirResponse, SagittaRemoteSystemDBError> {
         let mut db = self.db.lock().unwrap();
         let tx = db.transaction().unwrap();
+        let max_commit_rank =
+            self.get_max_commit_rank_tx(&request.workspace_id, request.commit_rank, &tx);
+        let branch_name = self.get_branch_name_tx(&request.workspace_id, &request.branch_name, &tx);
 
         let mut res: BTreeMap<String, ReadDirResponseItem> = BTreeMap::new();
 
@@ -759,8 +2269,8 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         workspace_file_revision.deleted_at,
                         workspace_file_revision.file_type,
                         file_path.name,
-                        blob.size,
-                        workspace_file_revision.created_at,
+                        COALESCE(blob.size, LENGTH(CAST(workspace_file_revision.symlink_target AS BLOB))),
+                        COALESCE(workspace_file_revision.modified_at, workspace_file_revision.created_at),
                         workspace_file_revision.permission
                     FROM workspace_file_revision
                     JOIN (
@@ -794,6 +2304,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                             file_type: match file_type {
                                 0 => SagittaFileType::File,
                                 1 => SagittaFileType::Dir,
+                                2 => SagittaFileType::Symlink,
                                 _ => unreachable!(),
                             },
                             deleted_at,
@@ -827,13 +2338,14 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         trunk_file_revision.deleted_at,
                         trunk_file_revision.file_type,
                         file_path.name,
-                        blob.size,
-                        trunk_file_revision.created_at,
+                        COALESCE(blob.size, LENGTH(CAST(trunk_file_revision.symlink_target AS BLOB))),
+                        COALESCE(trunk_file_revision.modified_at, trunk_file_revision.created_at),
                         trunk_file_revision.permission
                     FROM trunk_file_revision
                     JOIN (
                         SELECT file_path_id, MAX(commit_rank) AS commit_rank
                         FROM trunk_file_revision AS trunk_file_revision_2
+                        WHERE trunk_file_revision_2.branch_name = ? AND trunk_file_revision_2.commit_rank <= ?
                         GROUP BY trunk_file_revision_2.file_path_id
                     ) AS latest_commit_version
                     ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
@@ -843,31 +2355,35 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                 )
                 .unwrap();
             let res_trunk: Vec<ReadDirResponseItem> = stmt
-                .query_map(rusqlite::params![parent_id], |row| {
-                    let deleted_at: Option<String> = row.get(2)?;
-                    let deleted_at =
-                        deleted_at.map(|x| DateTime::parse_from_rfc3339(&x).unwrap().into());
-                    let file_type: i64 = row.get(3)?;
-                    let file_name: String = row.get(4)?;
-                    let size: Option<u64> = row.get(5)?;
-                    let created_at: String = row.get(6)?;
-                    let created_at: SystemTime =
-                        DateTime::parse_from_rfc3339(&created_at).unwrap().into();
-                    let permission: i64 = row.get(7)?;
-                    Ok(ReadDirResponseItem {
-                        file_path: row.get(0)?,
-                        file_type: match file_type {
-                            0 => SagittaFileType::File,
-                            1 => SagittaFileType::Dir,
-                            _ => unreachable!(),
-                        },
-                        deleted_at,
-                        file_name,
-                        size: size.unwrap_or(0),
-                        modified_at: created_at,
-                        permission,
-                    })
-                })
+                .query_map(
+                    rusqlite::params![branch_name, max_commit_rank, parent_id],
+                    |row| {
+                        let deleted_at: Option<String> = row.get(2)?;
+                        let deleted_at =
+                            deleted_at.map(|x| DateTime::parse_from_rfc3339(&x).unwrap().into());
+                        let file_type: i64 = row.get(3)?;
+                        let file_name: String = row.get(4)?;
+                        let size: Option<u64> = row.get(5)?;
+                        let created_at: String = row.get(6)?;
+                        let created_at: SystemTime =
+                            DateTime::parse_from_rfc3339(&created_at).unwrap().into();
+                        let permission: i64 = row.get(7)?;
+                        Ok(ReadDirResponseItem {
+                            file_path: row.get(0)?,
+                            file_type: match file_type {
+                                0 => SagittaFileType::File,
+                                1 => SagittaFileType::Dir,
+                                2 => SagittaFileType::Symlink,
+                                _ => unreachable!(),
+                            },
+                            deleted_at,
+                            file_name,
+                            size: size.unwrap_or(0),
+                            modified_at: created_at,
+                            permission,
+                        })
+                    },
+                )
                 .unwrap()
                 .map(|x| x.unwrap())
                 .collect();
@@ -893,13 +2409,14 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         trunk_file_revision.deleted_at,
                         trunk_file_revision.file_type,
                         file_path.name,
-                        blob.size,
-                        trunk_file_revision.created_at,
+                        COALESCE(blob.size, LENGTH(CAST(trunk_file_revision.symlink_target AS BLOB))),
+                        COALESCE(trunk_file_revision.modified_at, trunk_file_revision.created_at),
                         trunk_file_revision.permission
                     FROM trunk_file_revision
                     JOIN (
                         SELECT file_path_id, MAX(commit_rank) AS commit_rank
                         FROM trunk_file_revision AS trunk_file_revision_2
+                        WHERE trunk_file_revision_2.branch_name = ? AND trunk_file_revision_2.commit_rank <= ?
                         GROUP BY trunk_file_revision_2.file_path_id
                     ) AS latest_commit_version
                     ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
@@ -909,31 +2426,35 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                 )
                 .unwrap();
             let res_trunk: Vec<ReadDirResponseItem> = stmt
-                .query_map(rusqlite::params![parent_id], |row| {
-                    let deleted_at: Option<String> = row.get(2)?;
-                    let deleted_at =
-                        deleted_at.map(|x| DateTime::parse_from_rfc3339(&x).unwrap().into());
-                    let file_type: i64 = row.get(3)?;
-                    let file_name: String = row.get(4)?;
-                    let size: Option<u64> = row.get(5)?;
-                    let created_at: String = row.get(6)?;
-                    let created_at: SystemTime =
-                        DateTime::parse_from_rfc3339(&created_at).unwrap().into();
-                    let permission: i64 = row.get(7)?;
-                    Ok(ReadDirResponseItem {
-                        file_path: row.get(0)?,
-                        file_type: match file_type {
-                            0 => SagittaFileType::File,
-                            1 => SagittaFileType::Dir,
-                            _ => unreachable!(),
-                        },
-                        deleted_at,
-                        file_name,
-                        size: size.unwrap_or(0),
-                        modified_at: created_at,
-                        permission,
-                    })
-                })
+                .query_map(
+                    rusqlite::params![branch_name, max_commit_rank, parent_id],
+                    |row| {
+                        let deleted_at: Option<String> = row.get(2)?;
+                        let deleted_at =
+                            deleted_at.map(|x| DateTime::parse_from_rfc3339(&x).unwrap().into());
+                        let file_type: i64 = row.get(3)?;
+                        let file_name: String = row.get(4)?;
+                        let size: Option<u64> = row.get(5)?;
+                        let created_at: String = row.get(6)?;
+                        let created_at: SystemTime =
+                            DateTime::parse_from_rfc3339(&created_at).unwrap().into();
+                        let permission: i64 = row.get(7)?;
+                        Ok(ReadDirResponseItem {
+                            file_path: row.get(0)?,
+                            file_type: match file_type {
+                                0 => SagittaFileType::File,
+                                1 => SagittaFileType::Dir,
+                                2 => SagittaFileType::Symlink,
+                                _ => unreachable!(),
+                            },
+                            deleted_at,
+                            file_name,
+                            size: size.unwrap_or(0),
+                            modified_at: created_at,
+                            permission,
+                        })
+                    },
+                )
                 .unwrap()
                 .map(|x| x.unwrap())
                 .collect();
@@ -953,8 +2474,8 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         workspace_file_revision.deleted_at,
                         workspace_file_revision.file_type,
                         file_path.name,
-                        blob.size,
-                        workspace_file_revision.created_at,
+                        COALESCE(blob.size, LENGTH(CAST(workspace_file_revision.symlink_target AS BLOB))),
+                        COALESCE(workspace_file_revision.modified_at, workspace_file_revision.created_at),
                         workspace_file_revision.permission
                     FROM workspace_file_revision
                     JOIN (
@@ -988,6 +2509,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                             file_type: match file_type {
                                 0 => SagittaFileType::File,
                                 1 => SagittaFileType::Dir,
+                                2 => SagittaFileType::Symlink,
                                 _ => unreachable!(),
                             },
                             deleted_at,
@@ -1023,6 +2545,9 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
     ) -> Result<GetAttrResponse, SagittaRemoteSystemDBError> {
         let mut db = self.db.lock().unwrap();
         let tx = db.transaction().unwrap();
+        let max_commit_rank =
+            self.get_max_commit_rank_tx(&request.workspace_id, request.commit_rank, &tx);
+        let branch_name = self.get_branch_name_tx(&request.workspace_id, &request.branch_name, &tx);
 
         let path_id = {
             let mut stmt = tx
@@ -1050,9 +2575,10 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                             workspace_file_revision.blob_id,
                             workspace_file_revision.deleted_at,
                             workspace_file_revision.file_type,
-                            blob.size,
-                            workspace_file_revision.created_at,
-                            workspace_file_revision.permission
+                            COALESCE(blob.size, LENGTH(CAST(workspace_file_revision.symlink_target AS BLOB))),
+                            COALESCE(workspace_file_revision.modified_at, workspace_file_revision.created_at),
+                            workspace_file_revision.permission,
+                            workspace_file_revision.symlink_target
                         FROM workspace_file_revision
                         JOIN (
                             SELECT file_path_id, MAX(sync_version_number) AS sync_version_number
@@ -1077,6 +2603,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                             let created_at: SystemTime =
                                 DateTime::parse_from_rfc3339(&created_at).unwrap().into();
                             let permission: i64 = row.get(5)?;
+                            let symlink_target: Option<String> = row.get(6)?;
                             if deleted_at.is_some() {
                                 Ok(GetAttrResponse::NotFound)
                             } else {
@@ -1084,11 +2611,13 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                                     file_type: match file_type {
                                         0 => SagittaFileType::File,
                                         1 => SagittaFileType::Dir,
+                                        2 => SagittaFileType::Symlink,
                                         _ => unreachable!(),
                                     },
                                     size: size.unwrap_or(0),
                                     modified_at: created_at,
                                     permission,
+                                    symlink_target,
                                 })
                             }
                         },
@@ -1113,13 +2642,15 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                         trunk_file_revision.blob_id,
                         trunk_file_revision.deleted_at,
                         trunk_file_revision.file_type,
-                        blob.size,
-                        trunk_file_revision.created_at,
-                        trunk_file_revision.permission
+                        COALESCE(blob.size, LENGTH(CAST(trunk_file_revision.symlink_target AS BLOB))),
+                        COALESCE(trunk_file_revision.modified_at, trunk_file_revision.created_at),
+                        trunk_file_revision.permission,
+                        trunk_file_revision.symlink_target
                     FROM trunk_file_revision
                     JOIN (
                         SELECT file_path_id, MAX(commit_rank) AS commit_rank
                         FROM trunk_file_revision AS trunk_file_revision_2
+                        WHERE trunk_file_revision_2.branch_name = ? AND trunk_file_revision_2.commit_rank <= ?
                         GROUP BY trunk_file_revision_2.file_path_id
                     ) AS latest_commit_version
                     ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
@@ -1129,29 +2660,35 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                 )
                 .unwrap();
             let res_trunk: Vec<GetAttrResponse> = stmt
-                .query_map(rusqlite::params![path_id], |row| {
-                    let deleted_at: Option<String> = row.get(1)?;
-                    let file_type: i64 = row.get(2)?;
-                    let size: Option<u64> = row.get(3)?;
-                    let created_at: String = row.get(4)?;
-                    let created_at: SystemTime =
-                        DateTime::parse_from_rfc3339(&created_at).unwrap().into();
-                    let permission: i64 = row.get(5)?;
-                    if deleted_at.is_some() {
-                        Ok(GetAttrResponse::NotFound)
-                    } else {
-                        Ok(GetAttrResponse::Found {
-                            file_type: match file_type {
-                                0 => SagittaFileType::File,
-                                1 => SagittaFileType::Dir,
-                                _ => unreachable!(),
-                            },
-                            size: size.unwrap_or(0),
-                            modified_at: created_at,
-                            permission,
-                        })
-                    }
-                })
+                .query_map(
+                    rusqlite::params![branch_name, max_commit_rank, path_id],
+                    |row| {
+                        let deleted_at: Option<String> = row.get(1)?;
+                        let file_type: i64 = row.get(2)?;
+                        let size: Option<u64> = row.get(3)?;
+                        let created_at: String = row.get(4)?;
+                        let created_at: SystemTime =
+                            DateTime::parse_from_rfc3339(&created_at).unwrap().into();
+                        let permission: i64 = row.get(5)?;
+                        let symlink_target: Option<String> = row.get(6)?;
+                        if deleted_at.is_some() {
+                            Ok(GetAttrResponse::NotFound)
+                        } else {
+                            Ok(GetAttrResponse::Found {
+                                file_type: match file_type {
+                                    0 => SagittaFileType::File,
+                                    1 => SagittaFileType::Dir,
+                                    2 => SagittaFileType::Symlink,
+                                    _ => unreachable!(),
+                                },
+                                size: size.unwrap_or(0),
+                                modified_at: created_at,
+                                permission,
+                                symlink_target,
+                            })
+                        }
+                    },
+                )
                 .unwrap()
                 .map(|x| x.unwrap())
                 .collect();
@@ -1174,6 +2711,9 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
     ) -> Result<GetFileBlobIdResponse, SagittaRemoteSystemDBError> {
         let mut db = self.db.lock().unwrap();
         let tx = db.transaction().unwrap();
+        let max_commit_rank =
+            self.get_max_commit_rank_tx(&request.workspace_id, request.commit_rank, &tx);
+        let branch_name = self.get_branch_name_tx(&request.workspace_id, &request.branch_name, &tx);
 
         let path_id = {
             let mut stmt = tx
@@ -1245,6 +2785,7 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                     JOIN (
                         SELECT file_path_id, MAX(commit_rank) AS commit_rank
                         FROM trunk_file_revision AS trunk_file_revision_2
+                        WHERE trunk_file_revision_2.branch_name = ? AND trunk_file_revision_2.commit_rank <= ?
                         GROUP BY trunk_file_revision_2.file_path_id
                     ) AS latest_commit_version
                     ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
@@ -1253,14 +2794,17 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
                 )
                 .unwrap();
             let res_trunk: Vec<GetFileBlobIdResponse> = stmt
-                .query_map(rusqlite::params![path_id], |row| {
-                    let blob_id: Option<String> = row.get(0)?;
-                    if let Some(blob_id) = blob_id {
-                        Ok(GetFileBlobIdResponse::Found { blob_id })
-                    } else {
-                        Ok(GetFileBlobIdResponse::NotFound)
-                    }
-                })
+                .query_map(
+                    rusqlite::params![branch_name, max_commit_rank, path_id],
+                    |row| {
+                        let blob_id: Option<String> = row.get(0)?;
+                        if let Some(blob_id) = blob_id {
+                            Ok(GetFileBlobIdResponse::Found { blob_id })
+                        } else {
+                            Ok(GetFileBlobIdResponse::NotFound)
+                        }
+                    },
+                )
                 .unwrap()
                 .map(|x| x.unwrap())
                 .collect();
@@ -1277,6 +2821,238 @@ impl SagittaRemoteSystemDBTrait for SagittaRemoteSystemDBBySqlite {
         Ok(GetFileBlobIdResponse::NotFound)
     }
 
+    fn get_xattrs(
+        &self,
+        request: GetXattrsRequest,
+    ) -> Result<GetXattrsResponse, SagittaRemoteSystemDBError> {
+        let mut db = self.db.lock().unwrap();
+        let tx = db.transaction().unwrap();
+        let max_commit_rank =
+            self.get_max_commit_rank_tx(&request.workspace_id, request.commit_rank, &tx);
+        let branch_name = self.get_branch_name_tx(&request.workspace_id, &request.branch_name, &tx);
+
+        let path_id = {
+            let mut stmt = tx
+                .prepare("SELECT file_path_id FROM file_path WHERE path = ?")
+                .unwrap();
+            let ids = stmt
+                .query_map(rusqlite::params![request.file_path.join("/")], |row| {
+                    let id: String = row.get(0)?;
+                    Ok(id)
+                })
+                .unwrap()
+                .map(|x| x.unwrap())
+                .collect::<Vec<String>>();
+            if ids.is_empty() {
+                return Ok(GetXattrsResponse::NotFound);
+            }
+            ids[0].clone()
+        };
+
+        let mut revision: Option<(String, Option<String>)> = None;
+
+        if request.workspace_id.is_some() {
+            revision = tx
+                .query_row(
+                    "SELECT
+                        workspace_file_revision.workspace_file_revision_id,
+                        workspace_file_revision.deleted_at
+                    FROM workspace_file_revision
+                    JOIN (
+                        SELECT file_path_id, MAX(sync_version_number) AS sync_version_number
+                        FROM workspace_file_revision AS workspace_file_revision_2
+                        WHERE workspace_file_revision_2.workspace_id = ?
+                        GROUP BY workspace_file_revision_2.file_path_id
+                    ) AS latest_sync_version
+                    ON workspace_file_revision.file_path_id = latest_sync_version.file_path_id AND workspace_file_revision.sync_version_number = latest_sync_version.sync_version_number
+                    WHERE workspace_file_revision.workspace_id = ? AND workspace_file_revision.file_path_id = ?",
+                    rusqlite::params![request.workspace_id, request.workspace_id, path_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .ok();
+        }
+
+        if revision.is_none() {
+            revision = tx
+                .query_row(
+                    "SELECT
+                        trunk_file_revision.trunk_file_revision_id,
+                        trunk_file_revision.deleted_at
+                    FROM trunk_file_revision
+                    JOIN (
+                        SELECT file_path_id, MAX(commit_rank) AS commit_rank
+                        FROM trunk_file_revision AS trunk_file_revision_2
+                        WHERE trunk_file_revision_2.branch_name = ? AND trunk_file_revision_2.commit_rank <= ?
+                        GROUP BY trunk_file_revision_2.file_path_id
+                    ) AS latest_commit_version
+                    ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
+                    WHERE trunk_file_revision.file_path_id = ?",
+                    rusqlite::params![branch_name, max_commit_rank, path_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .ok();
+        }
+
+        let Some((revision_id, None)) = revision else {
+            tx.commit().unwrap();
+            return Ok(GetXattrsResponse::NotFound);
+        };
+
+        let xattrs = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT name, value FROM file_revision_xattr WHERE file_revision_id = ? ORDER BY name",
+                )
+                .unwrap();
+            stmt.query_map(rusqlite::params![revision_id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect()
+        };
+
+        tx.commit().unwrap();
+        Ok(GetXattrsResponse::Found { xattrs })
+    }
+
+    fn get_file_state(
+        &self,
+        request: GetFileStateRequest,
+    ) -> Result<GetFileStateResponse, SagittaRemoteSystemDBError> {
+        let mut db = self.db.lock().unwrap();
+        let tx = db.transaction().unwrap();
+        let max_commit_rank =
+            self.get_max_commit_rank_tx(&request.workspace_id, request.commit_rank, &tx);
+        let branch_name = self.get_branch_name_tx(&request.workspace_id, &request.branch_name, &tx);
+
+        let path_id = {
+            let mut stmt = tx
+                .prepare("SELECT file_path_id FROM file_path WHERE path = ?")
+                .unwrap();
+            let ids = stmt
+                .query_map(rusqlite::params![request.file_path.join("/")], |row| {
+                    let id: String = row.get(0)?;
+                    Ok(id)
+                })
+                .unwrap()
+                .map(|x| x.unwrap())
+                .collect::<Vec<String>>();
+            if ids.is_empty() {
+                return Ok(GetFileStateResponse::NotFound);
+            }
+            ids[0].clone()
+        };
+
+        let res_workspace: Option<FileStateRow> = if request.workspace_id.is_some() {
+            tx.query_row(
+                    "SELECT
+                        workspace_file_revision.blob_id,
+                        blob.hash,
+                        workspace_file_revision.deleted_at
+                    FROM workspace_file_revision
+                    JOIN (
+                        SELECT file_path_id, MAX(sync_version_number) AS sync_version_number
+                        FROM workspace_file_revision AS workspace_file_revision_2
+                        WHERE workspace_file_revision_2.workspace_id = ?
+                        GROUP BY workspace_file_revision_2.file_path_id
+                    ) AS latest_sync_version
+                    ON workspace_file_revision.file_path_id = latest_sync_version.file_path_id AND workspace_file_revision.sync_version_number = latest_sync_version.sync_version_number
+                    LEFT JOIN blob ON workspace_file_revision.blob_id = blob.blob_id
+                    WHERE workspace_file_revision.workspace_id = ? AND workspace_file_revision.file_path_id = ?",
+                    rusqlite::params![request.workspace_id, request.workspace_id, path_id],
+                    |row| {
+                        Ok(FileStateRow {
+                            blob_id: row.get(0)?,
+                            sha256: row.get(1)?,
+                            deleted_at: row.get(2)?,
+                        })
+                    },
+                )
+                .ok()
+        } else {
+            None
+        };
+
+        let res_trunk: Option<(FileStateRow, i64)> = tx
+            .query_row(
+                "SELECT
+                    trunk_file_revision.blob_id,
+                    blob.hash,
+                    trunk_file_revision.deleted_at,
+                    trunk_file_revision.commit_rank
+                FROM trunk_file_revision
+                JOIN (
+                    SELECT file_path_id, MAX(commit_rank) AS commit_rank
+                    FROM trunk_file_revision AS trunk_file_revision_2
+                    WHERE trunk_file_revision_2.branch_name = ? AND trunk_file_revision_2.commit_rank <= ?
+                    GROUP BY trunk_file_revision_2.file_path_id
+                ) AS latest_commit_version
+                ON trunk_file_revision.file_path_id = latest_commit_version.file_path_id AND trunk_file_revision.commit_rank = latest_commit_version.commit_rank
+                LEFT JOIN blob ON trunk_file_revision.blob_id = blob.blob_id
+                WHERE trunk_file_revision.file_path_id = ?",
+                rusqlite::params![branch_name, max_commit_rank, path_id],
+                |row| {
+                    let file_state_row = FileStateRow {
+                        blob_id: row.get(0)?,
+                        sha256: row.get(1)?,
+                        deleted_at: row.get(2)?,
+                    };
+                    Ok((file_state_row, row.get(3)?))
+                },
+            )
+            .ok();
+
+        tx.commit().unwrap();
+
+        let res_trunk = res_trunk.filter(|(row, _)| row.deleted_at.is_none());
+        let commit_rank = res_trunk.as_ref().map(|(_, commit_rank)| *commit_rank);
+
+        match (res_workspace, res_trunk) {
+            (Some(row), _) if row.deleted_at.is_some() => Ok(GetFileStateResponse::NotFound),
+            (Some(row), res_trunk) => {
+                let state = match res_trunk {
+                    Some((trunk_row, _)) if trunk_row.blob_id == row.blob_id => {
+                        SagittaFileState::Clean
+                    }
+                    Some(_) => SagittaFileState::Modified,
+                    None => SagittaFileState::New,
+                };
+                Ok(GetFileStateResponse::Found {
+                    blob_id: row.blob_id,
+                    sha256: row.sha256,
+                    commit_rank,
+                    state,
+                })
+            }
+            (None, Some((trunk_row, _))) => Ok(GetFileStateResponse::Found {
+                blob_id: trunk_row.blob_id,
+                sha256: trunk_row.sha256,
+                commit_rank,
+                state: SagittaFileState::Clean,
+            }),
+            (None, None) => Ok(GetFileStateResponse::NotFound),
+        }
+    }
+
+    fn get_stats(
+        &self,
+        _request: GetStatsRequest,
+    ) -> Result<GetStatsResponse, SagittaRemoteSystemDBError> {
+        let db = self.db.lock().unwrap();
+        let (blob_count, blob_total_size): (i64, i64) = db
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM blob",
+                rusqlite::params![],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        Ok(GetStatsResponse {
+            blob_count: blob_count as u64,
+            blob_total_size: blob_total_size as u64,
+        })
+    }
+
     fn get_workspace_id_from_name(
         &self,
         request: GetWorkspaceIdFromNameRequest,
@@ -4,6 +4,7 @@ use rand_pcg::Pcg64Mcg;
 use sagitta_common::clock::Clock;
 use sagitta_remote_system_db::{sqlite::SagittaRemoteSystemDBBySqlite, *};
 use std::{
+    collections::BTreeMap,
     path::PathBuf,
     time::{Duration, SystemTime},
 };
@@ -30,6 +31,7 @@ fn test_sqlite_workspace_1() {
     let res1 = db
         .create_workspace(CreateWorkspaceRequest {
             workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
         })
         .unwrap();
     insta::assert_debug_snapshot!(res1);
@@ -119,11 +121,14 @@ fn test_sqlite_workspace_4() {
     let res1 = db
         .sync_files_to_workspace(SyncFilesToWorkspaceRequest {
             workspace_id: "workspace1".to_string(),
+            sync_id: None,
             items: vec![
                 SyncFilesToWorkspaceRequestItem::UpsertFile {
                     file_path: vec!["foo".to_string(), "test.txt".to_string()],
                     blob_id: "blob1".to_string(),
                     permission: 0o644,
+                    xattrs: BTreeMap::new(),
+                    modified_at: None,
                 },
                 SyncFilesToWorkspaceRequestItem::UpsertDir {
                     file_path: vec!["bar".to_string()],
@@ -148,10 +153,13 @@ fn test_sqlite_workspace_4() {
     let res2 = db
         .sync_files_to_workspace(SyncFilesToWorkspaceRequest {
             workspace_id: "workspace1".to_string(),
+            sync_id: None,
             items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
                 file_path: vec!["foo".to_string(), "test.txt".to_string()],
                 blob_id: "blob2".to_string(),
                 permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
             }],
         })
         .unwrap();
@@ -167,6 +175,7 @@ fn test_sqlite_workspace_4() {
     let res3 = db
         .sync_files_to_workspace(SyncFilesToWorkspaceRequest {
             workspace_id: "workspace1".to_string(),
+            sync_id: None,
             items: vec![
                 SyncFilesToWorkspaceRequestItem::DeleteFile {
                     file_path: vec!["foo".to_string(), "test.txt".to_string()],
@@ -200,6 +209,7 @@ fn test_sqlite_workspace_5() {
     let res1 = db
         .create_workspace(CreateWorkspaceRequest {
             workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
         })
         .unwrap();
     insta::assert_debug_snapshot!(res1);
@@ -224,11 +234,14 @@ fn test_sqlite_workspace_5() {
     let res2 = db
         .sync_files_to_workspace(SyncFilesToWorkspaceRequest {
             workspace_id: workspace_id.clone(),
+            sync_id: None,
             items: vec![
                 SyncFilesToWorkspaceRequestItem::UpsertFile {
                     file_path: vec!["foo".to_string(), "test.txt".to_string()],
                     blob_id: blob_id_1.blob_id().to_string(),
                     permission: 0o644,
+                    xattrs: BTreeMap::new(),
+                    modified_at: None,
                 },
                 SyncFilesToWorkspaceRequestItem::UpsertDir {
                     file_path: vec!["bar".to_string()],
@@ -253,16 +266,24 @@ fn test_sqlite_workspace_5() {
     let res3 = db
         .commit(CommitRequest {
             workspace_id: workspace_id.clone(),
+            keep_workspace: false,
+            paths: None,
         })
         .unwrap();
     insta::assert_debug_snapshot!(res3);
 
-    let res3_files = db.get_all_trunk_files(GetAllTrunkFilesRequest {}).unwrap();
+    let res3_files = db
+        .get_all_trunk_files(GetAllTrunkFilesRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
     insta::assert_debug_snapshot!(res3_files);
 
     let res3_dir_files_1 = db
         .read_dir(ReadDirRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec![],
             include_deleted: false,
         })
@@ -272,6 +293,8 @@ fn test_sqlite_workspace_5() {
     let res3_dir_files_2 = db
         .read_dir(ReadDirRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["foo".to_string()],
             include_deleted: false,
         })
@@ -281,6 +304,8 @@ fn test_sqlite_workspace_5() {
     let res3_dir_files_3 = db
         .read_dir(ReadDirRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["bar".to_string()],
             include_deleted: false,
         })
@@ -290,6 +315,7 @@ fn test_sqlite_workspace_5() {
     let res4 = db
         .create_workspace(CreateWorkspaceRequest {
             workspace_name: "workspace2".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
         })
         .unwrap();
     insta::assert_debug_snapshot!(res4);
@@ -298,10 +324,13 @@ fn test_sqlite_workspace_5() {
     let res5 = db
         .sync_files_to_workspace(SyncFilesToWorkspaceRequest {
             workspace_id: workspace_id.clone(),
+            sync_id: None,
             items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
                 file_path: vec!["foo".to_string(), "test.txt".to_string()],
                 blob_id: blob_id_2.blob_id().to_string(),
                 permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
             }],
         })
         .unwrap();
@@ -317,16 +346,23 @@ fn test_sqlite_workspace_5() {
     let res6 = db
         .commit(CommitRequest {
             workspace_id: workspace_id.clone(),
+            keep_workspace: false,
+            paths: None,
         })
         .unwrap();
     insta::assert_debug_snapshot!(res6);
 
-    let res6_files = db.get_all_trunk_files(GetAllTrunkFilesRequest {}).unwrap();
+    let res6_files = db
+        .get_all_trunk_files(GetAllTrunkFilesRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
     insta::assert_debug_snapshot!(res6_files);
 
     let res7 = db
         .create_workspace(CreateWorkspaceRequest {
             workspace_name: "workspace3".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
         })
         .unwrap();
     insta::assert_debug_snapshot!(res7);
@@ -335,6 +371,7 @@ fn test_sqlite_workspace_5() {
     let res8 = db
         .sync_files_to_workspace(SyncFilesToWorkspaceRequest {
             workspace_id: workspace_id.clone(),
+            sync_id: None,
             items: vec![
                 SyncFilesToWorkspaceRequestItem::DeleteFile {
                     file_path: vec!["foo".to_string(), "test.txt".to_string()],
@@ -360,6 +397,8 @@ fn test_sqlite_workspace_5() {
     let res8_dir_files_1 = db
         .read_dir(ReadDirRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec![],
             include_deleted: false,
         })
@@ -369,6 +408,8 @@ fn test_sqlite_workspace_5() {
     let res8_dir_files_1d = db
         .read_dir(ReadDirRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec![],
             include_deleted: true,
         })
@@ -378,6 +419,8 @@ fn test_sqlite_workspace_5() {
     let res8_dir_files_2 = db
         .read_dir(ReadDirRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["foo".to_string()],
             include_deleted: false,
         })
@@ -387,6 +430,8 @@ fn test_sqlite_workspace_5() {
     let res8_dir_files_2d = db
         .read_dir(ReadDirRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["foo".to_string()],
             include_deleted: true,
         })
@@ -396,6 +441,8 @@ fn test_sqlite_workspace_5() {
     let res8_dir_files_3 = db
         .read_dir(ReadDirRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["bar".to_string()],
             include_deleted: false,
         })
@@ -405,6 +452,8 @@ fn test_sqlite_workspace_5() {
     let res8_dir_files_3d = db
         .read_dir(ReadDirRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["bar".to_string()],
             include_deleted: true,
         })
@@ -414,16 +463,24 @@ fn test_sqlite_workspace_5() {
     let res9 = db
         .commit(CommitRequest {
             workspace_id: workspace_id.clone(),
+            keep_workspace: false,
+            paths: None,
         })
         .unwrap();
     insta::assert_debug_snapshot!(res9);
 
-    let res9_files = db.get_all_trunk_files(GetAllTrunkFilesRequest {}).unwrap();
+    let res9_files = db
+        .get_all_trunk_files(GetAllTrunkFilesRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
     insta::assert_debug_snapshot!(res9_files);
 
     let res9_dir_files_1 = db
         .read_dir(ReadDirRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec![],
             include_deleted: false,
         })
@@ -433,6 +490,8 @@ fn test_sqlite_workspace_5() {
     let res9_dir_files_2 = db
         .read_dir(ReadDirRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["foo".to_string()],
             include_deleted: false,
         })
@@ -442,6 +501,8 @@ fn test_sqlite_workspace_5() {
     let res9_dir_files_3 = db
         .read_dir(ReadDirRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["bar".to_string()],
             include_deleted: false,
         })
@@ -449,7 +510,10 @@ fn test_sqlite_workspace_5() {
     insta::assert_debug_snapshot!(res9_dir_files_3);
 
     let res10 = db
-        .get_commit_history(GetCommitHistoryRequest { take: 10 })
+        .get_commit_history(GetCommitHistoryRequest {
+            branch_name: None,
+            take: 10,
+        })
         .unwrap();
     insta::assert_debug_snapshot!(res10);
 }
@@ -464,6 +528,7 @@ fn test_sqlite_workspace_6() {
     let res1 = db
         .create_workspace(CreateWorkspaceRequest {
             workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
         })
         .unwrap();
     insta::assert_debug_snapshot!(res1);
@@ -480,11 +545,14 @@ fn test_sqlite_workspace_6() {
     let res2 = db
         .sync_files_to_workspace(SyncFilesToWorkspaceRequest {
             workspace_id: workspace_id.clone(),
+            sync_id: None,
             items: vec![
                 SyncFilesToWorkspaceRequestItem::UpsertFile {
                     file_path: vec!["foo".to_string(), "test.txt".to_string()],
                     blob_id: blob_id_1.blob_id().to_string(),
                     permission: 0o644,
+                    xattrs: BTreeMap::new(),
+                    modified_at: None,
                 },
                 SyncFilesToWorkspaceRequestItem::UpsertDir {
                     file_path: vec!["bar".to_string()],
@@ -502,6 +570,8 @@ fn test_sqlite_workspace_6() {
     let res2_attr1 = db
         .get_attr(GetAttrRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["foo".to_string(), "test.txt".to_string()],
         })
         .unwrap();
@@ -510,6 +580,8 @@ fn test_sqlite_workspace_6() {
     let res2_attr2 = db
         .get_attr(GetAttrRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["foo".to_string()],
         })
         .unwrap();
@@ -518,6 +590,8 @@ fn test_sqlite_workspace_6() {
     let res2_attr3 = db
         .get_attr(GetAttrRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["bar".to_string()],
         })
         .unwrap();
@@ -526,6 +600,8 @@ fn test_sqlite_workspace_6() {
     let res2_attr4 = db
         .get_attr(GetAttrRequest {
             workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["baz".to_string()],
         })
         .unwrap();
@@ -534,6 +610,8 @@ fn test_sqlite_workspace_6() {
     let res3 = db
         .commit(CommitRequest {
             workspace_id: workspace_id.clone(),
+            keep_workspace: false,
+            paths: None,
         })
         .unwrap();
     insta::assert_debug_snapshot!(res3);
@@ -541,6 +619,8 @@ fn test_sqlite_workspace_6() {
     let res3_attr1 = db
         .get_attr(GetAttrRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["foo".to_string(), "test.txt".to_string()],
         })
         .unwrap();
@@ -549,6 +629,8 @@ fn test_sqlite_workspace_6() {
     let res3_attr2 = db
         .get_attr(GetAttrRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["foo".to_string()],
         })
         .unwrap();
@@ -557,6 +639,8 @@ fn test_sqlite_workspace_6() {
     let res3_attr3 = db
         .get_attr(GetAttrRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["bar".to_string()],
         })
         .unwrap();
@@ -565,8 +649,2093 @@ fn test_sqlite_workspace_6() {
     let res3_attr4 = db
         .get_attr(GetAttrRequest {
             workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
             file_path: vec!["baz".to_string()],
         })
         .unwrap();
     insta::assert_debug_snapshot!(res3_attr4);
 }
+
+#[test]
+fn test_sqlite_workspace_7() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let res1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "alice-dev".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    let workspace_id = res1.workspace_id;
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo".to_string(), "test.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: true,
+        paths: None,
+    })
+    .unwrap();
+
+    let res2_workspaces = db
+        .get_workspaces(GetWorkspacesRequest {
+            contains_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2_workspaces);
+
+    let res2_changelist = db
+        .get_workspace_changelist(GetWorkspaceChangelistRequest {
+            workspace_id: workspace_id.clone(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2_changelist);
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo".to_string(), "test2.txt".to_string()],
+            blob_id: blob_id_2.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+
+    let res3_dir_files = db
+        .read_dir(ReadDirRequest {
+            workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo".to_string()],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3_dir_files);
+
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: true,
+        paths: None,
+    })
+    .unwrap();
+
+    let res4_workspaces = db
+        .get_workspaces(GetWorkspacesRequest {
+            contains_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res4_workspaces);
+
+    let res4_files = db
+        .get_all_trunk_files(GetAllTrunkFilesRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res4_files);
+
+    let res4_history = db
+        .get_commit_history(GetCommitHistoryRequest {
+            branch_name: None,
+            take: 10,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res4_history);
+}
+
+#[test]
+fn test_sqlite_workspace_8() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let res1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    let workspace_id = res1.workspace_id;
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["foo".to_string(), "bar".to_string(), "a.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["foo".to_string(), "b.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["baz".to_string(), "c.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: Some(vec![vec!["foo".to_string(), "bar".to_string()]]),
+    })
+    .unwrap();
+
+    let res2_files = db
+        .get_all_trunk_files(GetAllTrunkFilesRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2_files);
+
+    let res2_changelist = db
+        .get_workspace_changelist(GetWorkspaceChangelistRequest {
+            workspace_id: workspace_id.clone(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2_changelist);
+
+    let res2_workspaces = db
+        .get_workspaces(GetWorkspacesRequest {
+            contains_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2_workspaces);
+
+    let res2_dir_files = db
+        .read_dir(ReadDirRequest {
+            workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo".to_string()],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2_dir_files);
+
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res3_files = db
+        .get_all_trunk_files(GetAllTrunkFilesRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3_files);
+
+    let res3_history = db
+        .get_commit_history(GetCommitHistoryRequest {
+            branch_name: None,
+            take: 10,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3_history);
+}
+
+#[test]
+fn test_sqlite_workspace_9() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["foo.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["bar.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace2".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["foo.txt".to_string()],
+                blob_id: blob_id_2.blob_id().to_string(),
+                permission: 0o755,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::DeleteFile {
+                file_path: vec!["bar.txt".to_string()],
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["baz.txt".to_string()],
+                blob_id: blob_id_2.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res1 = db
+        .revert_commit(RevertCommitRequest { commit_rank: 2 })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res1_dir_files = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec![],
+            include_deleted: true,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1_dir_files);
+
+    let res2 = db
+        .revert_commit(RevertCommitRequest { commit_rank: 1 })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .revert_commit(RevertCommitRequest { commit_rank: 42 })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+}
+
+#[test]
+fn test_sqlite_workspace_10() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res1 = db
+        .create_tag(CreateTagRequest {
+            tag_name: "v1".to_string(),
+            commit_rank: 1,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace2".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["foo.txt".to_string()],
+                blob_id: blob_id_2.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["bar.txt".to_string()],
+                blob_id: blob_id_2.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res2 = db
+        .create_tag(CreateTagRequest {
+            tag_name: "v1".to_string(),
+            commit_rank: 2,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .create_tag(CreateTagRequest {
+            tag_name: "v2".to_string(),
+            commit_rank: 42,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+
+    db.create_tag(CreateTagRequest {
+        tag_name: "v2".to_string(),
+        commit_rank: 2,
+    })
+    .unwrap();
+
+    let res4 = db.get_tags(GetTagsRequest {}).unwrap();
+    insta::assert_debug_snapshot!(res4);
+
+    let res4_dir_files = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: Some(1),
+            file_path: vec![],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res4_dir_files);
+
+    let res4_attr = db
+        .get_attr(GetAttrRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: Some(1),
+            file_path: vec!["bar.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res4_attr);
+
+    let res4_blob_id = db
+        .get_file_blob_id(GetFileBlobIdRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: Some(1),
+            file_path: vec!["foo.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res4_blob_id);
+
+    let res5 = db
+        .delete_tag(DeleteTagRequest {
+            tag_name: "v1".to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res5);
+
+    let res6 = db
+        .delete_tag(DeleteTagRequest {
+            tag_name: "v1".to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res6);
+
+    let res6_tags = db.get_tags(GetTagsRequest {}).unwrap();
+    insta::assert_debug_snapshot!(res6_tags);
+}
+
+#[test]
+fn test_sqlite_workspace_11() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res1 = db
+        .create_branch(CreateBranchRequest {
+            branch_name: "release".to_string(),
+            source_branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .create_branch(CreateBranchRequest {
+            branch_name: "release".to_string(),
+            source_branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .create_branch(CreateBranchRequest {
+            branch_name: "release2".to_string(),
+            source_branch_name: "unknown".to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+
+    let res4 = db.create_workspace(CreateWorkspaceRequest {
+        workspace_name: "workspace2".to_string(),
+        branch_name: "unknown".to_string(),
+    });
+    insta::assert_debug_snapshot!(res4);
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace3".to_string(),
+            branch_name: "release".to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo.txt".to_string()],
+            blob_id: blob_id_2.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res5_trunk = db
+        .get_file_blob_id(GetFileBlobIdRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res5_trunk);
+
+    let res5_release = db
+        .get_file_blob_id(GetFileBlobIdRequest {
+            workspace_id: None,
+            branch_name: Some("release".to_string()),
+            commit_rank: None,
+            file_path: vec!["foo.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res5_release);
+
+    let res5_history = db
+        .get_commit_history(GetCommitHistoryRequest {
+            branch_name: Some("release".to_string()),
+            take: 10,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res5_history);
+
+    let res6 = db
+        .cherry_pick(CherryPickRequest {
+            commit_rank: 3,
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res6);
+
+    let res6_trunk = db
+        .get_file_blob_id(GetFileBlobIdRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res6_trunk);
+
+    let res7 = db
+        .cherry_pick(CherryPickRequest {
+            commit_rank: 42,
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res7);
+
+    let res8 = db
+        .cherry_pick(CherryPickRequest {
+            commit_rank: 3,
+            branch_name: "unknown".to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res8);
+
+    let res9 = db.get_branches(GetBranchesRequest {}).unwrap();
+    insta::assert_debug_snapshot!(res9);
+}
+
+#[test]
+fn test_sqlite_workspace_12() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    let items_list = vec![
+        vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+        vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["bar.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+        vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo.txt".to_string()],
+            blob_id: blob_id_2.blob_id().to_string(),
+            permission: 0o755,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+        vec![SyncFilesToWorkspaceRequestItem::DeleteFile {
+            file_path: vec!["foo.txt".to_string()],
+        }],
+    ];
+    for (i, items) in items_list.into_iter().enumerate() {
+        let workspace_id = db
+            .create_workspace(CreateWorkspaceRequest {
+                workspace_name: format!("workspace{}", i),
+                branch_name: TRUNK_BRANCH_NAME.to_string(),
+            })
+            .unwrap()
+            .workspace_id;
+        db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+            workspace_id: workspace_id.clone(),
+            sync_id: None,
+            items,
+        })
+        .unwrap();
+        db.commit(CommitRequest {
+            workspace_id: workspace_id.clone(),
+            keep_workspace: false,
+            paths: None,
+        })
+        .unwrap();
+    }
+
+    let res1 = db
+        .get_file_history(GetFileHistoryRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+            file_path: vec!["foo.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .get_file_history(GetFileHistoryRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+            file_path: vec!["unknown.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+}
+
+#[test]
+fn test_sqlite_workspace_13() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertSymlink {
+            file_path: vec!["dir1".to_string(), "link.txt".to_string()],
+            target: "../foo.txt".to_string(),
+        }],
+    })
+    .unwrap();
+
+    let res1 = db
+        .get_attr(GetAttrRequest {
+            workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["dir1".to_string(), "link.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res2 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["dir1".to_string()],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .get_attr(GetAttrRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["dir1".to_string(), "link.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+
+    let res4 = db
+        .get_file_blob_id(GetFileBlobIdRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["dir1".to_string(), "link.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res4);
+}
+
+#[test]
+fn test_sqlite_workspace_14() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+
+    let blob_id = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo.txt".to_string()],
+            blob_id: blob_id.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::from([
+                ("user.comment".to_string(), b"hello".to_vec()),
+                ("user.mime_type".to_string(), b"text/plain".to_vec()),
+            ]),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+
+    let res1 = db
+        .get_xattrs(GetXattrsRequest {
+            workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    db.create_branch(CreateBranchRequest {
+        branch_name: "feature".to_string(),
+        source_branch_name: TRUNK_BRANCH_NAME.to_string(),
+    })
+    .unwrap();
+
+    let res2 = db
+        .get_xattrs(GetXattrsRequest {
+            workspace_id: None,
+            branch_name: Some("feature".to_string()),
+            commit_rank: None,
+            file_path: vec!["foo.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .get_xattrs(GetXattrsRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["unknown.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+}
+
+#[test]
+fn test_sqlite_workspace_15() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    let workspace_id_1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_1.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["foo.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["bar.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_1.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let workspace_id_2 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace2".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_2.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["foo.txt".to_string()],
+                blob_id: blob_id_2.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["baz.txt".to_string()],
+                blob_id: blob_id_2.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+
+    let paths = ["foo.txt", "bar.txt", "baz.txt", "unknown.txt"];
+    let res1: Vec<GetFileStateResponse> = paths
+        .iter()
+        .map(|path| {
+            db.get_file_state(GetFileStateRequest {
+                workspace_id: Some(workspace_id_2.clone()),
+                branch_name: None,
+                commit_rank: None,
+                file_path: vec![path.to_string()],
+            })
+            .unwrap()
+        })
+        .collect();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .get_file_state(GetFileStateRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+}
+
+#[test]
+fn test_sqlite_workspace_16() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo".to_string(), "test.txt".to_string()],
+            blob_id: blob_id.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000)),
+        }],
+    })
+    .unwrap();
+
+    let res1 = db
+        .get_attr(GetAttrRequest {
+            workspace_id: Some(workspace_id.clone()),
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo".to_string(), "test.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res2 = db
+        .get_attr(GetAttrRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo".to_string(), "test.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["foo".to_string()],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+}
+
+#[test]
+fn test_sqlite_workspace_17() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let res1 = db.get_stats(GetStatsRequest {}).unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    for (hash, size) in [("hash1", 10), ("hash2", 20), ("hash1", 10)] {
+        db.create_or_get_blob(CreateOrGetBlobRequest {
+            hash: hash.to_string(),
+            size,
+        })
+        .unwrap();
+    }
+
+    let res2 = db.get_stats(GetStatsRequest {}).unwrap();
+    insta::assert_debug_snapshot!(res2);
+}
+
+#[test]
+fn test_sqlite_workspace_18() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    let workspace_id_1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_1.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["dir1".to_string(), "a.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["dir1".to_string(), "b.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["c.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_1.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    // a file moved out of a renamed dir, the dir itself, and a file renamed and modified
+    let workspace_id_2 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace2".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_2.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::Rename {
+                from: vec!["dir1".to_string(), "b.txt".to_string()],
+                to: vec!["b.txt".to_string()],
+            },
+            SyncFilesToWorkspaceRequestItem::Rename {
+                from: vec!["dir1".to_string()],
+                to: vec!["dir2".to_string()],
+            },
+            SyncFilesToWorkspaceRequestItem::Rename {
+                from: vec!["c.txt".to_string()],
+                to: vec!["d.txt".to_string()],
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["d.txt".to_string()],
+                blob_id: blob_id_2.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_2.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res1 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec![],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["dir2".to_string()],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .get_file_history(GetFileHistoryRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+            file_path: vec!["dir2".to_string(), "a.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+
+    let res4 = db
+        .get_file_history(GetFileHistoryRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+            file_path: vec!["d.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res4);
+}
+
+#[test]
+fn test_sqlite_workspace_19() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let workspace_id_1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_1.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["dir1".to_string(), "a.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["dir1".to_string(), "sub".to_string(), "b.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["c.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+        ],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_1.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    // deleting a dir deletes everything under it
+    let workspace_id_2 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace2".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_2.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::DeleteDir {
+            file_path: vec!["dir1".to_string()],
+        }],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_2.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res1 = db
+        .get_all_trunk_files(GetAllTrunkFilesRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+}
+
+#[test]
+fn test_sqlite_workspace_20() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    // an empty dir, and a dir whose permission arrives after one of its files
+    let workspace_id_1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_1.clone(),
+        sync_id: None,
+        items: vec![
+            SyncFilesToWorkspaceRequestItem::UpsertDir {
+                file_path: vec!["empty".to_string(), "sub".to_string()],
+                permission: 0o750,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["dir1".to_string(), "a.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertDir {
+                file_path: vec!["dir1".to_string()],
+                permission: 0o700,
+            },
+        ],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_1.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let res1 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec![],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["empty".to_string()],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+}
+
+#[test]
+fn test_sqlite_workspace_21() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    // a sync retried with the same id is applied only once
+    let workspace_id_1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    for file_name in ["a.txt", "b.txt"] {
+        db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+            workspace_id: workspace_id_1.clone(),
+            sync_id: Some("sync1".to_string()),
+            items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec![file_name.to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            }],
+        })
+        .unwrap();
+    }
+
+    let res1 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: Some(workspace_id_1.clone()),
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec![],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .get_sync_state(GetSyncStateRequest {
+            workspace_id: workspace_id_1.clone(),
+            sync_id: "sync1".to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .get_sync_state(GetSyncStateRequest {
+            workspace_id: workspace_id_1.clone(),
+            sync_id: "sync2".to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+}
+
+#[test]
+fn test_sqlite_workspace_22() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    // two commits touching different files of the same dir
+    for file_name in ["a.txt", "b.txt"] {
+        let workspace_id = db
+            .create_workspace(CreateWorkspaceRequest {
+                workspace_name: format!("workspace-{}", file_name),
+                branch_name: TRUNK_BRANCH_NAME.to_string(),
+            })
+            .unwrap()
+            .workspace_id;
+        db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+            workspace_id: workspace_id.clone(),
+            sync_id: None,
+            items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["dir1".to_string(), file_name.to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            }],
+        })
+        .unwrap();
+        db.commit(CommitRequest {
+            workspace_id: workspace_id.clone(),
+            keep_workspace: false,
+            paths: None,
+        })
+        .unwrap();
+    }
+
+    // the dir rewritten by the second commit is not a conflict
+    let res1 = db
+        .revert_commit(RevertCommitRequest { commit_rank: 1 })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["dir1".to_string()],
+            include_deleted: true,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec![],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+}
+
+#[test]
+fn test_sqlite_migration_1() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+
+    // a database written before branches, symlinks, mtimes and renames
+    {
+        let db = rusqlite::Connection::open(&path).unwrap();
+        db.execute_batch(
+            "CREATE TABLE workspace (
+                workspace_id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL,
+                deleted_at TEXT
+            );
+            CREATE TABLE workspace_file_revision (
+                workspace_file_revision_id TEXT PRIMARY KEY,
+                workspace_id TEXT NOT NULL,
+                file_path_id TEXT NOT NULL,
+                sync_version_number INTEGER NOT NULL,
+                blob_id TEXT,
+                file_type INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                deleted_at TEXT,
+                permission INTEGER NOT NULL
+            );
+            CREATE TABLE trunk_file_revision (
+                trunk_file_revision_id TEXT PRIMARY KEY,
+                file_path_id TEXT NOT NULL,
+                commit_id TEXT NOT NULL,
+                commit_rank INTEGER NOT NULL,
+                blob_id TEXT,
+                file_type INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                deleted_at TEXT,
+                permission INTEGER NOT NULL
+            );
+            CREATE TABLE `commit` (
+                commit_id TEXT PRIMARY KEY,
+                commit_rank INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            INSERT INTO `commit` VALUES ('commit0', 0, '2000-01-01T00:00:00+00:00');
+            INSERT INTO `commit` VALUES ('commit1', 1, '2000-01-01T00:00:00+00:00');
+            INSERT INTO workspace VALUES ('workspace1', 'workspace1', '2000-01-01T00:00:00+00:00', NULL);",
+        )
+        .unwrap();
+    }
+
+    // running the migration twice leaves the upgraded database as it is
+    let db = setup_db(path.clone());
+    db.migration().unwrap();
+
+    let res1 = db
+        .get_workspaces(GetWorkspacesRequest {
+            contains_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db.get_branches(GetBranchesRequest {}).unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    db.commit(CommitRequest {
+        workspace_id: "workspace1".to_string(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+    let res3 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec![],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+
+    let version: i64 = rusqlite::Connection::open(&path)
+        .unwrap()
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, 1);
+}
+
+#[test]
+fn test_sqlite_workspace_23() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let workspace_id = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["foo".to_string(), "a.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+
+    // a partial commit that takes every pending change closes the workspace
+    db.commit(CommitRequest {
+        workspace_id: workspace_id.clone(),
+        keep_workspace: false,
+        paths: Some(vec![vec!["foo".to_string()]]),
+    })
+    .unwrap();
+
+    let res1 = db
+        .get_workspaces(GetWorkspacesRequest {
+            contains_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .get_all_trunk_files(GetAllTrunkFilesRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+}
+
+#[test]
+fn test_sqlite_workspace_24() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let workspace_id_1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    let workspace_id_2 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace2".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_1.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["a.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_1.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    // workspace2 still reads the trunk as of the commit it is based on
+    let res1 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: Some(workspace_id_2.clone()),
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec![],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_2.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["b.txt".to_string()],
+            blob_id: blob_id_2.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_2.clone(),
+        keep_workspace: true,
+        paths: None,
+    })
+    .unwrap();
+
+    // keeping the workspace open moves its base to the new commit
+    let res2 = db
+        .read_dir(ReadDirRequest {
+            workspace_id: Some(workspace_id_2.clone()),
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec![],
+            include_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+}
+
+#[test]
+fn test_sqlite_workspace_25() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let workspace_ids: Vec<String> = ["workspace1", "workspace2"]
+        .iter()
+        .map(|workspace_name| {
+            db.create_workspace(CreateWorkspaceRequest {
+                workspace_name: workspace_name.to_string(),
+                branch_name: TRUNK_BRANCH_NAME.to_string(),
+            })
+            .unwrap()
+            .workspace_id
+        })
+        .collect();
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    for workspace_id in &workspace_ids {
+        db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+            workspace_id: workspace_id.clone(),
+            sync_id: None,
+            items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["foo".to_string(), "a.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: None,
+            }],
+        })
+        .unwrap();
+    }
+    db.commit(CommitRequest {
+        workspace_id: workspace_ids[0].clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    // the file was committed after workspace2 was created; their common dir is no conflict
+    let res1 = db
+        .commit(CommitRequest {
+            workspace_id: workspace_ids[1].clone(),
+            keep_workspace: false,
+            paths: None,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .get_workspaces(GetWorkspacesRequest {
+            contains_deleted: false,
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+}
+
+#[test]
+fn test_sqlite_workspace_26() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let workspace_id_1 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace1".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_1.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["c.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+    })
+    .unwrap();
+    db.commit(CommitRequest {
+        workspace_id: workspace_id_1.clone(),
+        keep_workspace: false,
+        paths: None,
+    })
+    .unwrap();
+
+    let workspace_id_2 = db
+        .create_workspace(CreateWorkspaceRequest {
+            workspace_name: "workspace2".to_string(),
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+        })
+        .unwrap()
+        .workspace_id;
+
+    // branches and workspaces share the top level of the mount
+    let res1: Vec<_> = ["release", "@tags", "a/b", "workspace2"]
+        .iter()
+        .map(|branch_name| {
+            db.create_branch(CreateBranchRequest {
+                branch_name: branch_name.to_string(),
+                source_branch_name: TRUNK_BRANCH_NAME.to_string(),
+            })
+            .unwrap()
+        })
+        .collect();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2: Vec<_> = ["workspace2", "release", "@tags", "a/b"]
+        .iter()
+        .map(|workspace_name| {
+            db.create_workspace(CreateWorkspaceRequest {
+                workspace_name: workspace_name.to_string(),
+                branch_name: TRUNK_BRANCH_NAME.to_string(),
+            })
+        })
+        .collect();
+    insta::assert_debug_snapshot!(res2);
+
+    db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+        workspace_id: workspace_id_2.clone(),
+        sync_id: None,
+        items: vec![SyncFilesToWorkspaceRequestItem::Rename {
+            from: vec!["c.txt".to_string()],
+            to: vec!["d.txt".to_string()],
+        }],
+    })
+    .unwrap();
+    let CommitResponse::Committed { commit_rank, .. } = db
+        .commit(CommitRequest {
+            workspace_id: workspace_id_2.clone(),
+            keep_workspace: false,
+            paths: None,
+        })
+        .unwrap()
+    else {
+        panic!("unexpected conflict");
+    };
+
+    // the rename is kept on the target branch
+    db.cherry_pick(CherryPickRequest {
+        commit_rank,
+        branch_name: "release".to_string(),
+    })
+    .unwrap();
+    let res3 = db
+        .get_file_history(GetFileHistoryRequest {
+            branch_name: "release".to_string(),
+            file_path: vec!["d.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+}
+
+#[test]
+fn test_sqlite_workspace_27() {
+    let file = NamedTempFile::new().unwrap();
+    let path = file.into_temp_path();
+    let path = path.to_path_buf();
+    let db = setup_db(path);
+
+    let blob_id_1 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash1".to_string(),
+            size: 10,
+        })
+        .unwrap();
+
+    let blob_id_2 = db
+        .create_or_get_blob(CreateOrGetBlobRequest {
+            hash: "hash2".to_string(),
+            size: 20,
+        })
+        .unwrap();
+
+    let items = vec![
+        vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["c.txt".to_string()],
+            blob_id: blob_id_1.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: None,
+        }],
+        vec![
+            SyncFilesToWorkspaceRequestItem::Rename {
+                from: vec!["c.txt".to_string()],
+                to: vec!["d.txt".to_string()],
+            },
+            SyncFilesToWorkspaceRequestItem::UpsertFile {
+                file_path: vec!["d.txt".to_string()],
+                blob_id: blob_id_1.blob_id().to_string(),
+                permission: 0o644,
+                xattrs: BTreeMap::new(),
+                modified_at: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000)),
+            },
+        ],
+        vec![SyncFilesToWorkspaceRequestItem::UpsertFile {
+            file_path: vec!["d.txt".to_string()],
+            blob_id: blob_id_2.blob_id().to_string(),
+            permission: 0o644,
+            xattrs: BTreeMap::new(),
+            modified_at: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000)),
+        }],
+    ];
+    for (i, items) in items.into_iter().enumerate() {
+        let workspace_id = db
+            .create_workspace(CreateWorkspaceRequest {
+                workspace_name: format!("workspace{}", i + 1),
+                branch_name: TRUNK_BRANCH_NAME.to_string(),
+            })
+            .unwrap()
+            .workspace_id;
+        db.sync_files_to_workspace(SyncFilesToWorkspaceRequest {
+            workspace_id: workspace_id.clone(),
+            sync_id: None,
+            items,
+        })
+        .unwrap();
+        db.commit(CommitRequest {
+            workspace_id,
+            keep_workspace: false,
+            paths: None,
+        })
+        .unwrap();
+    }
+
+    // the restored revision keeps its mtime and where it was renamed from
+    let res1 = db
+        .revert_commit(RevertCommitRequest { commit_rank: 3 })
+        .unwrap();
+    insta::assert_debug_snapshot!(res1);
+
+    let res2 = db
+        .get_attr(GetAttrRequest {
+            workspace_id: None,
+            branch_name: None,
+            commit_rank: None,
+            file_path: vec!["d.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res2);
+
+    let res3 = db
+        .get_file_history(GetFileHistoryRequest {
+            branch_name: TRUNK_BRANCH_NAME.to_string(),
+            file_path: vec!["d.txt".to_string()],
+        })
+        .unwrap();
+    insta::assert_debug_snapshot!(res3);
+}
@@ -1,10 +1,13 @@
 use std::path::PathBuf;
 
+use sagitta_common::diff::lcs_line_pairs;
 use sagitta_remote_system_db::{
     db::SagittaRemoteSystemDB, CommitRequest, CommitResponse, CreateWorkspaceRequest,
     CreateWorkspaceResponse, GetAttrRequest, GetAttrResponse, GetFileBlobIdRequest,
-    GetFileBlobIdResponse, GetWorkspacesRequest, GetWorkspacesResponse, ReadDirRequest,
-    ReadDirResponse, SagittaRemoteSystemDBTrait,
+    GetFileBlobIdResponse, GetFileHistoryRequest, GetFileHistoryResponse, GetFileStateRequest,
+    GetFileStateResponse, GetStatsRequest, GetStatsResponse, GetWorkspacesRequest,
+    GetWorkspacesResponse, GetXattrsRequest, GetXattrsResponse, ReadDirRequest, ReadDirResponse,
+    SagittaRemoteSystemDBTrait,
 };
 
 #[derive(Debug, Clone)]
@@ -43,6 +46,35 @@ pub struct WriteBlobResponse {
     pub blob_id: String,
 }
 
+#[derive(Debug)]
+pub struct BlameRequest {
+    pub branch_name: String,
+    pub file_path: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlameResponseLine {
+    pub commit_id: String,
+    pub commit_rank: i64,
+    pub line: String,
+}
+
+#[derive(Debug)]
+pub enum BlameResponse {
+    Found {
+        lines: Vec<BlameResponseLine>,
+    },
+    NotFound,
+    /// The current revision of the file is not UTF-8 or is larger than
+    /// [`BLAME_MAX_BLOB_SIZE`].
+    NotText {
+        commit_id: String,
+    },
+}
+
+/// Largest revision `blame` splits into lines and diffs.
+pub const BLAME_MAX_BLOB_SIZE: usize = 4 * 1024 * 1024;
+
 impl RemoteSystemWorkspaceManager {
     pub async fn new(base_url: PathBuf, db: SagittaRemoteSystemDB) -> Self {
         Self {
@@ -72,6 +104,27 @@ impl RemoteSystemWorkspaceManager {
             .map_err(Error::SagittaRemoteSystemDBError)
     }
 
+    pub fn get_xattrs(&self, request: GetXattrsRequest) -> Result<GetXattrsResponse, Error> {
+        self.db
+            .get_xattrs(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn get_file_state(
+        &self,
+        request: GetFileStateRequest,
+    ) -> Result<GetFileStateResponse, Error> {
+        self.db
+            .get_file_state(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn get_stats(&self, request: GetStatsRequest) -> Result<GetStatsResponse, Error> {
+        self.db
+            .get_stats(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
     pub fn get_workspaces(
         &self,
         request: GetWorkspacesRequest,
@@ -90,19 +143,33 @@ impl RemoteSystemWorkspaceManager {
             .map_err(Error::SagittaRemoteSystemDBError)
     }
 
-    pub fn read_blob(&self, request: ReadBlobRequest) -> Result<ReadBlobResponse, Error> {
-        let hierarchy1 = &request.blob_id[0..2];
-        let hierarchy2 = &request.blob_id[2..4];
-        let object_path = self
-            .base_url
+    fn blob_path(&self, blob_id: &str) -> PathBuf {
+        let hierarchy1 = &blob_id[0..2];
+        let hierarchy2 = &blob_id[2..4];
+        self.base_url
             .join("objects")
             .join(hierarchy1)
             .join(hierarchy2)
-            .join(&request.blob_id);
-        let r = std::fs::read(object_path).map_err(Error::IOError)?;
+            .join(blob_id)
+    }
+
+    pub fn read_blob(&self, request: ReadBlobRequest) -> Result<ReadBlobResponse, Error> {
+        let r = std::fs::read(self.blob_path(&request.blob_id)).map_err(Error::IOError)?;
         Ok(ReadBlobResponse::Found { blob: r })
     }
 
+    /// Reads a blob as text for `blame`, or `None` if it is too large or not UTF-8.
+    /// The size is checked before the blob is read.
+    fn read_blame_text(&self, blob_id: &str) -> Result<Option<String>, Error> {
+        let path = self.blob_path(blob_id);
+        let size = std::fs::metadata(&path).map_err(Error::IOError)?.len();
+        if size > BLAME_MAX_BLOB_SIZE as u64 {
+            return Ok(None);
+        }
+        let blob = std::fs::read(path).map_err(Error::IOError)?;
+        Ok(String::from_utf8(blob).ok())
+    }
+
     pub fn write_blob(&self, request: WriteBlobRequest) -> Result<WriteBlobResponse, Error> {
         let blob_id = request.blob_id;
         let hierarchy1 = &blob_id[0..2];
@@ -124,6 +191,132 @@ impl RemoteSystemWorkspaceManager {
             .map_err(Error::SagittaRemoteSystemDBError)
     }
 
+    pub fn revert_commit(
+        &self,
+        request: sagitta_remote_system_db::RevertCommitRequest,
+    ) -> Result<sagitta_remote_system_db::RevertCommitResponse, Error> {
+        self.db
+            .revert_commit(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn create_tag(
+        &self,
+        request: sagitta_remote_system_db::CreateTagRequest,
+    ) -> Result<sagitta_remote_system_db::CreateTagResponse, Error> {
+        self.db
+            .create_tag(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn get_tags(
+        &self,
+        request: sagitta_remote_system_db::GetTagsRequest,
+    ) -> Result<sagitta_remote_system_db::GetTagsResponse, Error> {
+        self.db
+            .get_tags(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn delete_tag(
+        &self,
+        request: sagitta_remote_system_db::DeleteTagRequest,
+    ) -> Result<sagitta_remote_system_db::DeleteTagResponse, Error> {
+        self.db
+            .delete_tag(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn create_branch(
+        &self,
+        request: sagitta_remote_system_db::CreateBranchRequest,
+    ) -> Result<sagitta_remote_system_db::CreateBranchResponse, Error> {
+        self.db
+            .create_branch(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn get_branches(
+        &self,
+        request: sagitta_remote_system_db::GetBranchesRequest,
+    ) -> Result<sagitta_remote_system_db::GetBranchesResponse, Error> {
+        self.db
+            .get_branches(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn cherry_pick(
+        &self,
+        request: sagitta_remote_system_db::CherryPickRequest,
+    ) -> Result<sagitta_remote_system_db::CherryPickResponse, Error> {
+        self.db
+            .cherry_pick(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn get_file_history(
+        &self,
+        request: GetFileHistoryRequest,
+    ) -> Result<GetFileHistoryResponse, Error> {
+        self.db
+            .get_file_history(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
+    pub fn blame(&self, request: BlameRequest) -> Result<BlameResponse, Error> {
+        let history = self.get_file_history(GetFileHistoryRequest {
+            branch_name: request.branch_name,
+            file_path: request.file_path,
+        })?;
+
+        // replay the revisions, carrying attribution over lines kept unchanged
+        let last_index = history.items.len().checked_sub(1);
+        let mut lines: Option<Vec<BlameResponseLine>> = None;
+        for (index, item) in history.items.into_iter().enumerate() {
+            if item.deleted {
+                lines = None;
+                continue;
+            }
+            let Some(blob_id) = item.blob_id else {
+                continue;
+            };
+
+            let Some(text) = self.read_blame_text(&blob_id)? else {
+                if Some(index) == last_index {
+                    return Ok(BlameResponse::NotText {
+                        commit_id: item.commit_id,
+                    });
+                }
+                // an older binary revision: attribution restarts at the next text one
+                lines = None;
+                continue;
+            };
+            let mut next_lines: Vec<BlameResponseLine> = text
+                .lines()
+                .map(|line| BlameResponseLine {
+                    commit_id: item.commit_id.clone(),
+                    commit_rank: item.commit_rank,
+                    line: line.to_string(),
+                })
+                .collect();
+            if let Some(prev_lines) = &lines {
+                let prev_text: Vec<&str> = prev_lines.iter().map(|x| x.line.as_str()).collect();
+                let next_text: Vec<&str> = next_lines.iter().map(|x| x.line.as_str()).collect();
+                let pairs = lcs_line_pairs(&prev_text, &next_text);
+                for (i, j) in pairs {
+                    next_lines[j].commit_id = prev_lines[i].commit_id.clone();
+                    next_lines[j].commit_rank = prev_lines[i].commit_rank;
+                }
+            }
+            lines = Some(next_lines);
+        }
+
+        match lines {
+            Some(lines) => Ok(BlameResponse::Found { lines }),
+            None => Ok(BlameResponse::NotFound),
+        }
+    }
+
     pub fn create_or_get_blob(
         &self,
         request: sagitta_remote_system_db::CreateOrGetBlobRequest,
@@ -142,6 +335,15 @@ impl RemoteSystemWorkspaceManager {
             .map_err(Error::SagittaRemoteSystemDBError)
     }
 
+    pub fn get_sync_state(
+        &self,
+        request: sagitta_remote_system_db::GetSyncStateRequest,
+    ) -> Result<sagitta_remote_system_db::GetSyncStateResponse, Error> {
+        self.db
+            .get_sync_state(request)
+            .map_err(Error::SagittaRemoteSystemDBError)
+    }
+
     pub fn get_workspace_id_from_name(
         &self,
         request: sagitta_remote_system_db::GetWorkspaceIdFromNameRequest,
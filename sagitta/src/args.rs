@@ -8,6 +8,18 @@ pub struct Args {
     #[arg(long)]
     pub mount: Option<String>,
 
+    /// Sync workspaces in the background while mounted
+    #[arg(long)]
+    pub auto_sync: bool,
+
+    /// Seconds a workspace has to stay unchanged before it is synced in the background
+    #[arg(long, default_value_t = 5)]
+    pub auto_sync_debounce_secs: u64,
+
+    /// Number of files a sync uploads at once
+    #[arg(long, default_value_t = 4)]
+    pub upload_concurrency: usize,
+
     #[command(subcommand)]
     pub subcommand: Option<Commands>,
 }
@@ -20,11 +32,70 @@ pub enum Commands {
     },
     Sync {
         workspace_name: String,
+        /// List what would be synced without syncing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    SyncStatus {
+        workspace_name: String,
+    },
+    Commit {
+        workspace_name: String,
+        #[arg(long)]
+        keep_workspace: bool,
+        #[arg(last = true)]
+        paths: Vec<String>,
+    },
+    Revert {
+        commit_rank: i64,
+    },
+    Tag {
+        #[command(subcommand)]
+        subcommand: Option<TagSubcommands>,
+    },
+    Branch {
+        #[command(subcommand)]
+        subcommand: Option<BranchSubcommands>,
+    },
+    CherryPick {
+        commit_rank: i64,
+        branch_name: String,
+    },
+    History {
+        path: String,
+        #[arg(long, default_value = "trunk")]
+        branch: String,
+    },
+    Blame {
+        path: String,
+        #[arg(long, default_value = "trunk")]
+        branch: String,
     },
 }
 
 #[derive(Subcommand, Debug)]
 pub enum WorkspaceSubcommands {
-    Create { name: String },
+    Create {
+        name: String,
+        #[arg(long, default_value = "trunk")]
+        branch: String,
+    },
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TagSubcommands {
+    Create { name: String, commit_rank: i64 },
+    List,
+    Delete { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BranchSubcommands {
+    Create {
+        name: String,
+        #[arg(long, default_value = "trunk")]
+        from: String,
+    },
     List,
 }
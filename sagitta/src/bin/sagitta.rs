@@ -1,16 +1,33 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{path::PathBuf, str::FromStr, time::Duration};
 
 use clap::Parser;
 use sagitta::args::Args;
 use sagitta::fs::{run_fs, SagittaConfig};
 use sagitta_common::clock::Clock;
-use sagitta_local_api_schema::v1::sync::V1SyncRequest;
+use sagitta_local_api_schema::v1::rebase::{V1RebaseRequest, V1RebaseResponse};
+use sagitta_local_api_schema::v1::sync::{V1SyncProgress, V1SyncRequest, V1SyncResponse};
+use sagitta_local_api_schema::v1::sync_status::{V1SyncStatusRequest, V1SyncStatusResponse};
 use sagitta_local_server::api::ServerConfig;
-use sagitta_remote_api_schema::v2::create_workspace::V2CreateWorkspaceRequest;
+use sagitta_local_server::auto_sync::AutoSyncConfig;
+use sagitta_remote_api_schema::v2::blame::{V2BlameRequest, V2BlameResponse};
+use sagitta_remote_api_schema::v2::cherry_pick::{V2CherryPickRequest, V2CherryPickResponse};
+use sagitta_remote_api_schema::v2::commit::{V2CommitRequest, V2CommitResponse};
+use sagitta_remote_api_schema::v2::create_branch::{V2CreateBranchRequest, V2CreateBranchResponse};
+use sagitta_remote_api_schema::v2::create_tag::{V2CreateTagRequest, V2CreateTagResponse};
+use sagitta_remote_api_schema::v2::create_workspace::{
+    V2CreateWorkspaceRequest, V2CreateWorkspaceResponse,
+};
+use sagitta_remote_api_schema::v2::delete_tag::{V2DeleteTagRequest, V2DeleteTagResponse};
+use sagitta_remote_api_schema::v2::get_branches::{V2GetBranchesRequest, V2GetBranchesResponse};
+use sagitta_remote_api_schema::v2::get_file_history::{
+    V2GetFileHistoryRequest, V2GetFileHistoryResponse,
+};
+use sagitta_remote_api_schema::v2::get_tags::{V2GetTagsRequest, V2GetTagsResponse};
 use sagitta_remote_api_schema::v2::get_workspace_id_from_name::V2GetWorkspaceIdFromNameRequest;
 use sagitta_remote_api_schema::v2::get_workspaces::{
     V2GetWorkspacesRequest, V2GetWorkspacesResponse,
 };
+use sagitta_remote_api_schema::v2::revert_commit::{V2RevertCommitRequest, V2RevertCommitResponse};
 
 #[tokio::main]
 async fn main() {
@@ -37,6 +54,11 @@ async fn main() {
             port: 8513,
             local_system_workspace_base_path: config.local_system_workspace_base_path.clone(),
             remote_api_base_url: config.base_url.clone(),
+            upload_concurrency: args.upload_concurrency,
+            auto_sync: args.auto_sync.then(|| AutoSyncConfig {
+                debounce: Duration::from_secs(args.auto_sync_debounce_secs),
+                max_backoff: Duration::from_secs(300),
+            }),
         };
         tokio::spawn(async move {
             sagitta_local_server::api::run_local_api_server(api_config).await;
@@ -51,7 +73,7 @@ async fn main() {
         let command = args.subcommand.unwrap();
         match command {
             sagitta::args::Commands::Workspace { subcommand } => match subcommand.unwrap() {
-                sagitta::args::WorkspaceSubcommands::Create { name } => {
+                sagitta::args::WorkspaceSubcommands::Create { name, branch } => {
                     let list = api_client
                         .v2_get_workspaces(V2GetWorkspacesRequest {})
                         .unwrap();
@@ -67,9 +89,27 @@ async fn main() {
                             return;
                         }
                     }
-                    api_client
-                        .v2_create_workspace(V2CreateWorkspaceRequest { name })
+                    let res = api_client
+                        .v2_create_workspace(V2CreateWorkspaceRequest {
+                            name: name.clone(),
+                            branch_name: branch.clone(),
+                        })
                         .unwrap();
+                    match res {
+                        V2CreateWorkspaceResponse::Ok { .. } => {}
+                        V2CreateWorkspaceResponse::AlreadyExists => {
+                            eprintln!("Workspace {} already exists", name);
+                        }
+                        V2CreateWorkspaceResponse::BranchNotFound => {
+                            eprintln!("Branch {} not found", branch);
+                        }
+                        V2CreateWorkspaceResponse::InvalidName => {
+                            eprintln!("Invalid workspace name {}", name);
+                        }
+                        V2CreateWorkspaceResponse::NameTaken => {
+                            eprintln!("{} is already used by a branch", name);
+                        }
+                    }
                 }
                 sagitta::args::WorkspaceSubcommands::List => {
                     let list = api_client
@@ -87,7 +127,10 @@ async fn main() {
                     }
                 }
             },
-            sagitta::args::Commands::Sync { workspace_name } => {
+            sagitta::args::Commands::Sync {
+                workspace_name,
+                dry_run,
+            } => {
                 let workspace_id = api_client
                     .v2_get_workspace_id_from_name(V2GetWorkspaceIdFromNameRequest {
                         workspace_name: workspace_name.clone(),
@@ -100,12 +143,385 @@ async fn main() {
                         return;
                     }
                 };
-                local_api_client
-                    .v1_sync(V1SyncRequest {
+                let mut drew_progress_bar = false;
+                let res = local_api_client.v1_sync_with_progress(
+                    V1SyncRequest {
                         workspace_id: workspace_id.clone(),
+                        dry_run,
+                    },
+                    |progress| {
+                        if progress.files_total > 0 {
+                            eprint!("\r{}", progress_bar(progress));
+                            drew_progress_bar = true;
+                        }
+                    },
+                );
+                if drew_progress_bar {
+                    eprintln!();
+                }
+                let res = match res {
+                    Ok(res) => res,
+                    Err(e) => {
+                        eprintln!("Failed to sync: {:?}", e);
+                        return;
+                    }
+                };
+                match res {
+                    V1SyncResponse::Ok {
+                        upsert_files,
+                        upsert_dirs,
+                        upsert_symlinks,
+                        reference_files,
+                        delete_files,
+                        rename_files,
+                        skipped_files,
+                        ignored_files,
+                    } => {
+                        if dry_run {
+                            for (from, to) in &rename_files {
+                                println!("rename {} -> {}", from.join("/"), to.join("/"));
+                            }
+                            for path in &upsert_dirs {
+                                println!("dir {}", path.join("/"));
+                            }
+                            for path in &upsert_files {
+                                println!("upload {}", path.join("/"));
+                            }
+                            for path in &reference_files {
+                                println!("reference {}", path.join("/"));
+                            }
+                            for path in &upsert_symlinks {
+                                println!("symlink {}", path.join("/"));
+                            }
+                            for path in &delete_files {
+                                println!("delete {}", path.join("/"));
+                            }
+                            for path in &ignored_files {
+                                println!("ignore {}", path.join("/"));
+                            }
+                        }
+                        println!(
+                            "{}{} uploaded, {} referenced, {} dirs, {} symlinks, {} deleted, {} renamed, {} skipped, {} ignored",
+                            if dry_run { "dry run: " } else { "" },
+                            upsert_files.len(),
+                            reference_files.len(),
+                            upsert_dirs.len(),
+                            upsert_symlinks.len(),
+                            delete_files.len(),
+                            rename_files.len(),
+                            skipped_files.len(),
+                            ignored_files.len()
+                        );
+                    }
+                    V1SyncResponse::Err { message } => {
+                        eprintln!("Failed to sync: {}", message);
+                    }
+                }
+            }
+            sagitta::args::Commands::SyncStatus { workspace_name } => {
+                let workspace_id = api_client
+                    .v2_get_workspace_id_from_name(V2GetWorkspaceIdFromNameRequest {
+                        workspace_name: workspace_name.clone(),
                     })
                     .unwrap();
+                let workspace_id = match workspace_id {
+                    sagitta_remote_api_schema::v2::get_workspace_id_from_name::V2GetWorkspaceIdFromNameResponse::Found { workspace_id } => workspace_id,
+                    _ => {
+                        eprintln!("Workspace not found");
+                        return;
+                    }
+                };
+                let res = local_api_client
+                    .v1_sync_status(V1SyncStatusRequest { workspace_id })
+                    .unwrap();
+                match res {
+                    V1SyncStatusResponse::Ok {
+                        last_synced_at,
+                        pending_changes,
+                        last_error,
+                    } => {
+                        match last_synced_at {
+                            Some(last_synced_at) => {
+                                let elapsed = last_synced_at.elapsed().unwrap_or_default();
+                                println!("last synced: {}s ago", elapsed.as_secs());
+                            }
+                            None => println!("last synced: never"),
+                        }
+                        println!("pending changes: {}", pending_changes);
+                        if let Some(last_error) = last_error {
+                            println!("last error: {}", last_error);
+                        }
+                    }
+                    V1SyncStatusResponse::NotFound => {
+                        eprintln!("Workspace not found");
+                    }
+                    V1SyncStatusResponse::Err { message } => {
+                        eprintln!("Failed to get the sync status: {}", message);
+                    }
+                }
+            }
+            sagitta::args::Commands::Commit {
+                workspace_name,
+                keep_workspace,
+                paths,
+            } => {
+                let workspace_id = api_client
+                    .v2_get_workspace_id_from_name(V2GetWorkspaceIdFromNameRequest {
+                        workspace_name: workspace_name.clone(),
+                    })
+                    .unwrap();
+                let workspace_id = match workspace_id {
+                    sagitta_remote_api_schema::v2::get_workspace_id_from_name::V2GetWorkspaceIdFromNameResponse::Found { workspace_id } => workspace_id,
+                    _ => {
+                        eprintln!("Workspace not found");
+                        return;
+                    }
+                };
+                let paths = if paths.is_empty() {
+                    None
+                } else {
+                    Some(paths.iter().map(|path| split_path(path)).collect())
+                };
+                let res = api_client.v2_commit(V2CommitRequest {
+                    workspace_id: workspace_id.clone(),
+                    keep_workspace,
+                    paths,
+                });
+                match res {
+                    Ok(V2CommitResponse::Ok { commit_rank, .. }) => {
+                        println!("Committed as commit {}", commit_rank);
+                        // the committed dirs are read from the branch from now on
+                        let res = local_api_client.v1_rebase(V1RebaseRequest { workspace_id });
+                        match res {
+                            Ok(V1RebaseResponse::Ok { .. }) => {}
+                            Ok(V1RebaseResponse::Err { message }) => {
+                                eprintln!("Failed to rebase the local workspace: {}", message);
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to rebase the local workspace: {:?}", e);
+                            }
+                        }
+                    }
+                    Ok(V2CommitResponse::Conflict { file_paths }) => {
+                        eprintln!("Conflict: changed by commits after the workspace was created");
+                        for file_path in file_paths {
+                            eprintln!("  {}", file_path);
+                        }
+                    }
+                    Ok(V2CommitResponse::NotFound) => {
+                        eprintln!("Workspace not found");
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to commit: {:?}", e);
+                    }
+                }
+            }
+            sagitta::args::Commands::Revert { commit_rank } => {
+                let res = api_client
+                    .v2_revert_commit(V2RevertCommitRequest { commit_rank })
+                    .unwrap();
+                match res {
+                    V2RevertCommitResponse::Ok { commit_rank, .. } => {
+                        println!("Reverted as commit {}", commit_rank);
+                    }
+                    V2RevertCommitResponse::Conflict { file_paths } => {
+                        eprintln!("Conflict: changed by later commits");
+                        for file_path in file_paths {
+                            eprintln!("  {}", file_path);
+                        }
+                    }
+                    V2RevertCommitResponse::NotFound => {
+                        eprintln!("Commit {} not found", commit_rank);
+                    }
+                }
+            }
+            sagitta::args::Commands::Tag { subcommand } => match subcommand.unwrap() {
+                sagitta::args::TagSubcommands::Create { name, commit_rank } => {
+                    let res = api_client
+                        .v2_create_tag(V2CreateTagRequest {
+                            tag_name: name.clone(),
+                            commit_rank,
+                        })
+                        .unwrap();
+                    match res {
+                        V2CreateTagResponse::Ok { .. } => {}
+                        V2CreateTagResponse::AlreadyExists => {
+                            eprintln!("Tag {} already exists", name);
+                        }
+                        V2CreateTagResponse::CommitNotFound => {
+                            eprintln!("Commit {} not found", commit_rank);
+                        }
+                    }
+                }
+                sagitta::args::TagSubcommands::List => {
+                    let res = api_client.v2_get_tags(V2GetTagsRequest {}).unwrap();
+                    match res {
+                        V2GetTagsResponse::Ok { items } => {
+                            for tag in items {
+                                println!("{}\t{}", tag.name, tag.commit_rank);
+                            }
+                        }
+                    }
+                }
+                sagitta::args::TagSubcommands::Delete { name } => {
+                    let res = api_client
+                        .v2_delete_tag(V2DeleteTagRequest {
+                            tag_name: name.clone(),
+                        })
+                        .unwrap();
+                    match res {
+                        V2DeleteTagResponse::Ok => {}
+                        V2DeleteTagResponse::NotFound => {
+                            eprintln!("Tag {} not found", name);
+                        }
+                    }
+                }
+            },
+            sagitta::args::Commands::Branch { subcommand } => match subcommand.unwrap() {
+                sagitta::args::BranchSubcommands::Create { name, from } => {
+                    let res = api_client
+                        .v2_create_branch(V2CreateBranchRequest {
+                            branch_name: name.clone(),
+                            source_branch_name: from.clone(),
+                        })
+                        .unwrap();
+                    match res {
+                        V2CreateBranchResponse::Ok { .. } => {}
+                        V2CreateBranchResponse::AlreadyExists => {
+                            eprintln!("Branch {} already exists", name);
+                        }
+                        V2CreateBranchResponse::SourceNotFound => {
+                            eprintln!("Branch {} not found", from);
+                        }
+                        V2CreateBranchResponse::InvalidName => {
+                            eprintln!("Invalid branch name {}", name);
+                        }
+                        V2CreateBranchResponse::NameTaken => {
+                            eprintln!("{} is already used by a workspace", name);
+                        }
+                    }
+                }
+                sagitta::args::BranchSubcommands::List => {
+                    let res = api_client.v2_get_branches(V2GetBranchesRequest {}).unwrap();
+                    match res {
+                        V2GetBranchesResponse::Ok { items } => {
+                            for branch in items {
+                                println!("{}\t{}", branch.name, branch.head_commit_rank);
+                            }
+                        }
+                    }
+                }
+            },
+            sagitta::args::Commands::CherryPick {
+                commit_rank,
+                branch_name,
+            } => {
+                let res = api_client
+                    .v2_cherry_pick(V2CherryPickRequest {
+                        commit_rank,
+                        branch_name: branch_name.clone(),
+                    })
+                    .unwrap();
+                match res {
+                    V2CherryPickResponse::Ok { commit_rank, .. } => {
+                        println!("Picked as commit {}", commit_rank);
+                    }
+                    V2CherryPickResponse::NotFound => {
+                        eprintln!("Commit {} not found", commit_rank);
+                    }
+                    V2CherryPickResponse::BranchNotFound => {
+                        eprintln!("Branch {} not found", branch_name);
+                    }
+                }
+            }
+            sagitta::args::Commands::History { path, branch } => {
+                let res = api_client
+                    .v2_get_file_history(V2GetFileHistoryRequest {
+                        branch_name: branch,
+                        path: split_path(&path),
+                    })
+                    .unwrap();
+                match res {
+                    V2GetFileHistoryResponse::Ok { items } => {
+                        for item in items {
+                            let state = match (&item.renamed_from, item.deleted) {
+                                (_, true) => "deleted".to_string(),
+                                (Some(from), false) => format!("renamed from {}", from.join("/")),
+                                (None, false) => "exists".to_string(),
+                            };
+                            println!(
+                                "{}\t{}\t{:o}\t{}",
+                                item.commit_rank,
+                                item.blob_id.unwrap_or_else(|| "-".to_string()),
+                                item.permission,
+                                state
+                            );
+                        }
+                    }
+                }
+            }
+            sagitta::args::Commands::Blame { path, branch } => {
+                let res = api_client
+                    .v2_blame(V2BlameRequest {
+                        branch_name: branch,
+                        path: split_path(&path),
+                    })
+                    .unwrap();
+                match res {
+                    V2BlameResponse::Ok { lines } => {
+                        for line in lines {
+                            println!("{:>6} {}", line.commit_rank, line.line);
+                        }
+                    }
+                    V2BlameResponse::NotFound => {
+                        eprintln!("File {} not found", path);
+                    }
+                    V2BlameResponse::NotText { commit_id } => {
+                        eprintln!(
+                            "File {} is not a text file as of commit {}",
+                            path, commit_id
+                        );
+                    }
+                }
             }
         }
     }
 }
+
+fn progress_bar(progress: &V1SyncProgress) -> String {
+    const WIDTH: u64 = 30;
+    let filled = (progress.bytes_done * WIDTH)
+        .checked_div(progress.bytes_total)
+        .unwrap_or(WIDTH)
+        .min(WIDTH);
+    format!(
+        "[{}{}] {}/{} files, {}/{}",
+        "#".repeat(filled as usize),
+        "-".repeat((WIDTH - filled) as usize),
+        progress.files_done,
+        progress.files_total,
+        format_bytes(progress.bytes_done),
+        format_bytes(progress.bytes_total)
+    )
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|chunk| !chunk.is_empty())
+        .map(|chunk| chunk.to_string())
+        .collect()
+}
@@ -1,32 +1,123 @@
 use std::{
-    collections::{HashMap, HashSet},
-    ffi::OsStr,
-    path::PathBuf,
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::{CString, OsStr},
+    os::unix::{ffi::OsStrExt, fs::FileExt},
+    path::{Path, PathBuf},
     time::SystemTime,
 };
 
 use fuser::{
-    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyCreate, ReplyDirectory, ReplyOpen,
-    ReplyWrite, TimeOrNow,
+    consts::{FUSE_ATOMIC_O_TRUNC, FUSE_FLOCK_LOCKS, FUSE_POSIX_LOCKS},
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyCreate, ReplyDirectory,
+    ReplyLseek, ReplyOpen, ReplyWrite, TimeOrNow,
 };
-use libc::{ENOENT, EOPNOTSUPP, EPERM};
-use log::info;
-use sagitta_common::clock::Clock;
-use sagitta_local_system_workspace::LocalSystemWorkspaceManager;
+use libc::{
+    EAGAIN, EBADF, EEXIST, EINTR, EINVAL, EIO, EISDIR, ENODATA, ENOENT, ENOTDIR, ENOTEMPTY, ENXIO,
+    EPERM, ERANGE, EXDEV, F_UNLCK, O_ACCMODE, O_APPEND, O_RDONLY, O_TRUNC, SEEK_DATA, SEEK_END,
+    SEEK_HOLE, SEEK_SET, XATTR_CREATE, XATTR_REPLACE,
+};
+use log::{error, info};
+use sagitta_common::{
+    clock::Clock,
+    sha256::{calc_sha256_from_reader, calc_sha256_from_slice},
+};
+use sagitta_local_system_workspace::{CowBlobRef, JournalOp, LocalSystemWorkspaceManager};
 use sagitta_remote_api_schema::v2::{
     get_attr::{V2GetAttrRequest, V2GetAttrResponse},
+    get_branches::{V2GetBranchesRequest, V2GetBranchesResponse},
     get_file_blob_id::{V2GetFileBlobIdRequest, V2GetFileBlobIdResponse},
+    get_file_state::{V2FileState, V2GetFileStateRequest, V2GetFileStateResponse},
+    get_tags::{V2GetTagsRequest, V2GetTagsResponse},
     get_workspace_id_from_name::{
         V2GetWorkspaceIdFromNameRequest, V2GetWorkspaceIdFromNameResponse,
     },
     get_workspaces::{V2GetWorkspacesRequest, V2GetWorkspacesResponse},
+    get_xattrs::{V2GetXattrsRequest, V2GetXattrsResponse},
     read_blob::{V2ReadBlobRequest, V2ReadBlobResponse},
     read_dir::{V2ReadDirRequest, V2ReadDirResponse},
+    stats::V2StatsRequest,
 };
 use std::time::Duration;
 
 use sagitta_remote_api_client::SagittaApiClient;
 
+use crate::lock::{FileLock, LockTable};
+
+/// Top-level directory listing tags as read-only snapshots of their branch.
+pub const TAGS_DIR_NAME: &str = "@tags";
+
+/// Prefix of the read-only extended attributes describing the VCS state of a file.
+pub const VCS_XATTR_PREFIX: &str = "user.sagitta.";
+
+/// Block size reported by `statfs` for branches and tags.
+const STATFS_BLOCK_SIZE: u64 = 4096;
+
+/// Which tree a mounted path is served from.
+#[derive(Debug, Clone)]
+pub enum SagittaView {
+    Branch {
+        branch_name: String,
+    },
+    Tag {
+        branch_name: String,
+        commit_rank: i64,
+    },
+    Workspace {
+        workspace_id: String,
+    },
+}
+
+impl SagittaView {
+    pub fn branch_name(&self) -> Option<String> {
+        match self {
+            SagittaView::Branch { branch_name } => Some(branch_name.clone()),
+            SagittaView::Tag { branch_name, .. } => Some(branch_name.clone()),
+            SagittaView::Workspace { .. } => None,
+        }
+    }
+
+    pub fn workspace_id(&self) -> Option<String> {
+        match self {
+            SagittaView::Workspace { workspace_id } => Some(workspace_id.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn commit_rank(&self) -> Option<i64> {
+        match self {
+            SagittaView::Tag { commit_rank, .. } => Some(*commit_rank),
+            _ => None,
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        !matches!(self, SagittaView::Workspace { .. })
+    }
+}
+
+/// What an open file handle reads from, resolved once when it is opened.
+#[derive(Debug)]
+pub enum FileHandleSource {
+    /// A cow file of a workspace, kept open so renames and syncs don't affect the handle.
+    Cow {
+        file: std::fs::File,
+    },
+    /// A synced or committed file, whose blob is pinned for the lifetime of the handle
+    /// in an unlinked local file shared by the handles of the same blob.
+    Blob {
+        blob_id: String,
+        file: std::fs::File,
+    },
+    Dir,
+}
+
+#[derive(Debug)]
+pub struct FileHandle {
+    pub ino: u64,
+    pub flags: i32,
+    pub source: FileHandleSource,
+}
+
 #[derive(Debug)]
 pub struct SagittaFS {
     pub config: SagittaConfig,
@@ -37,8 +128,16 @@ pub struct SagittaFS {
     pub clock: Clock,
     pub local_system_workspace_manager: LocalSystemWorkspaceManager,
     pub next_fh: u64,
+    pub file_handles: HashMap<u64, FileHandle>,
     pub workspace_name_to_id: HashMap<String, String>,
+    pub branch_names: HashSet<String>,
+    pub tag_name_to_commit: HashMap<String, (String, i64)>,
     pub lookup_count: HashMap<u64, i64>,
+    pub cow_ino_to_ino: HashMap<u64, u64>,
+    /// All known paths of the inodes that are hard linked, in the order they were seen.
+    pub hard_link_paths: HashMap<u64, Vec<Vec<String>>>,
+    /// Advisory locks and the blocking `setlk` requests waiting for them.
+    pub locks: LockTable<fuser::ReplyEmpty>,
 }
 
 impl Filesystem for SagittaFS {
@@ -72,22 +171,38 @@ impl Filesystem for SagittaFS {
     //     reply.error(ENOSYS);
     // }
 
-    // fn copy_file_range(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     ino_in: u64,
-    //     fh_in: u64,
-    //     offset_in: i64,
-    //     ino_out: u64,
-    //     fh_out: u64,
-    //     offset_out: i64,
-    //     len: u64,
-    //     flags: u32,
-    //     reply: ReplyWrite,
-    // ) {
-    //     info!("copy_file_range(ino_in={}, fh_in={}, offset_in={}, ino_out={}, fh_out={}, offset_out={}, len={}, flags={})", ino_in, fh_in, offset_in, ino_out, fh_out, offset_out, len, flags);
-    //     reply.error(ENOSYS);
-    // }
+    fn copy_file_range(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        ino_in: u64,
+        fh_in: u64,
+        offset_in: i64,
+        ino_out: u64,
+        fh_out: u64,
+        offset_out: i64,
+        len: u64,
+        flags: u32,
+        reply: ReplyWrite,
+    ) {
+        self.debug_sleep();
+        info!("copy_file_range(ino_in={}, fh_in={}, offset_in={}, ino_out={}, fh_out={}, offset_out={}, len={}, flags={})", ino_in, fh_in, offset_in, ino_out, fh_out, offset_out, len, flags);
+
+        let path_out = self.ino_to_path.get(&ino_out).unwrap().clone();
+        if self.is_read_only_path(&path_out) {
+            reply.error(EPERM);
+            return;
+        }
+
+        let len = std::cmp::min(len, u32::MAX as u64);
+        let copied = self.copy_range(fh_in, offset_in, ino_out, fh_out, offset_out, len);
+        match copied.and_then(|copied| {
+            self.append_journal(JournalOp::Write, &path_out, None)?;
+            Ok(copied)
+        }) {
+            Ok(copied) => reply.written(copied),
+            Err(errno) => reply.error(errno),
+        }
+    }
 
     fn create(
         &mut self,
@@ -109,45 +224,88 @@ impl Filesystem for SagittaFS {
         let mut file_path = parent_path.clone();
         file_path.push(name.to_str().unwrap().to_string());
 
-        if file_path[0] == "trunk" {
+        if self.is_read_only_path(&file_path) {
             reply.error(EPERM);
             return;
         }
 
         let workspace_id = self.get_workspace_id_from_name(&file_path[0]).unwrap();
-        self.local_system_workspace_manager
-            .create_cow_file(&workspace_id, &file_path[1..], &[], Some(mode))
-            .unwrap();
+        let res = self.local_system_workspace_manager.create_cow_file(
+            &workspace_id,
+            &file_path[1..],
+            &[],
+            Some(mode),
+        );
+        if let Err(e) = res {
+            reply.error(errno_of(e));
+            return;
+        }
+        if let Err(errno) = self.append_journal(JournalOp::Create, &file_path, None) {
+            reply.error(errno);
+            return;
+        }
 
         let attr = self.get_file_attr(
             &file_path[..file_path.len() - 1],
             &file_path[file_path.len() - 1],
         );
         let attr = attr.unwrap();
+        let fh = match self.open_file_handle(attr.ino, flags) {
+            Ok(fh) => fh,
+            Err(errno) => {
+                reply.error(errno);
+                return;
+            }
+        };
         *self.lookup_count.entry(attr.ino).or_insert(0) += 1;
-        reply.created(&Duration::from_secs(0), &attr, 0, 0, 0);
+        reply.created(&Duration::from_secs(0), &attr, 0, fh, 0);
     }
 
     // fn destroy(&mut self) {
     //     info!("destroy()");
     // }
 
-    // fn fallocate(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     ino: u64,
-    //     fh: u64,
-    //     offset: i64,
-    //     length: i64,
-    //     mode: i32,
-    //     reply: fuser::ReplyEmpty,
-    // ) {
-    //     info!(
-    //         "fallocate(ino={}, fh={}, offset={}, length={}, mode={})",
-    //         ino, fh, offset, length, mode
-    //     );
-    //     reply.error(ENOSYS);
-    // }
+    fn fallocate(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        length: i64,
+        mode: i32,
+        reply: fuser::ReplyEmpty,
+    ) {
+        self.debug_sleep();
+        info!(
+            "fallocate(ino={}, fh={}, offset={}, length={}, mode={})",
+            ino, fh, offset, length, mode
+        );
+
+        if let Err(errno) = self.ensure_cow_handle(fh) {
+            reply.error(errno);
+            return;
+        }
+        // the handle keeps the file it opened even if the path was renamed or unlinked since
+        let Some(FileHandle {
+            source: FileHandleSource::Cow { file },
+            ..
+        }) = self.file_handles.get(&fh)
+        else {
+            reply.error(EBADF);
+            return;
+        };
+        let res = LocalSystemWorkspaceManager::fallocate_file(file, mode, offset, length);
+        if let Err(e) = res {
+            reply.error(errno_of(e));
+            return;
+        }
+        let path = self.ino_to_path.get(&ino).unwrap().clone();
+        if let Err(errno) = self.append_journal(JournalOp::Write, &path, None) {
+            reply.error(errno);
+            return;
+        }
+        reply.ok();
+    }
 
     fn flush(
         &mut self,
@@ -159,6 +317,11 @@ impl Filesystem for SagittaFS {
     ) {
         self.debug_sleep();
         info!("flush(ino={}, fh={}, lock_owner={})", ino, fh, lock_owner);
+
+        // closing any descriptor drops the POSIX locks its owner holds on the file
+        if self.locks.release_owner(ino, lock_owner) {
+            self.wake_lock_waiters();
+        }
         reply.ok();
     }
 
@@ -211,24 +374,37 @@ impl Filesystem for SagittaFS {
         }
     }
 
-    // fn getlk(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     ino: u64,
-    //     fh: u64,
-    //     lock_owner: u64,
-    //     start: u64,
-    //     end: u64,
-    //     typ: i32,
-    //     pid: u32,
-    //     reply: fuser::ReplyLock,
-    // ) {
-    //     info!(
-    //         "getlk(ino={}, fh={}, lock_owner={}, start={}, end={}, typ={}, pid={})",
-    //         ino, fh, lock_owner, start, end, typ, pid
-    //     );
-    //     reply.error(ENOSYS);
-    // }
+    fn getlk(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        ino: u64,
+        fh: u64,
+        lock_owner: u64,
+        start: u64,
+        end: u64,
+        typ: i32,
+        pid: u32,
+        reply: fuser::ReplyLock,
+    ) {
+        self.debug_sleep();
+        info!(
+            "getlk(ino={}, fh={}, lock_owner={}, start={}, end={}, typ={}, pid={})",
+            ino, fh, lock_owner, start, end, typ, pid
+        );
+
+        let lock = FileLock {
+            fh,
+            lock_owner,
+            start,
+            end,
+            typ,
+            pid,
+        };
+        match self.locks.find_conflict(ino, &lock) {
+            Some(held) => reply.locked(held.start, held.end, held.typ, held.pid),
+            None => reply.locked(start, end, F_UNLCK, 0),
+        }
+    }
 
     fn getxattr(
         &mut self,
@@ -240,16 +416,39 @@ impl Filesystem for SagittaFS {
     ) {
         self.debug_sleep();
         info!("getxattr(ino={}, name={:?}, size={})", ino, name, size);
-        reply.error(EOPNOTSUPP);
+
+        let path = self.ino_to_path.get(&ino).unwrap().clone();
+        let name = name.to_str().unwrap();
+        if name.starts_with(VCS_XATTR_PREFIX) {
+            match self.get_vcs_xattr(&path, name) {
+                Some(value) => reply_xattr(size, &value, reply),
+                None => reply.error(ENODATA),
+            }
+            return;
+        }
+
+        let xattrs = self.get_xattrs(&path);
+        match xattrs.get(name) {
+            Some(value) => reply_xattr(size, value, reply),
+            None => reply.error(ENODATA),
+        }
     }
 
     fn init(
         &mut self,
         _req: &fuser::Request<'_>,
-        _config: &mut fuser::KernelConfig,
+        config: &mut fuser::KernelConfig,
     ) -> Result<(), libc::c_int> {
         self.debug_sleep();
         info!("init()");
+
+        // locks are tracked by `SagittaFS` instead of the kernel's local lock tables,
+        // and `O_TRUNC` is handled by `open` on the file handle
+        for capability in [FUSE_POSIX_LOCKS, FUSE_FLOCK_LOCKS, FUSE_ATOMIC_O_TRUNC] {
+            if config.add_capabilities(capability).is_err() {
+                info!("init: kernel does not support capability {:#x}", capability);
+            }
+        }
         Ok(())
     }
 
@@ -271,20 +470,75 @@ impl Filesystem for SagittaFS {
     //     reply.error(ENOSYS);
     // }
 
-    // fn link(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     ino: u64,
-    //     newparent: u64,
-    //     newname: &OsStr,
-    //     reply: fuser::ReplyEntry,
-    // ) {
-    //     info!(
-    //         "link(ino={}, newparent={}, newname={:?})",
-    //         ino, newparent, newname
-    //     );
-    //     reply.error(ENOSYS);
-    // }
+    fn link(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        ino: u64,
+        newparent: u64,
+        newname: &OsStr,
+        reply: fuser::ReplyEntry,
+    ) {
+        self.debug_sleep();
+        info!(
+            "link(ino={}, newparent={}, newname={:?})",
+            ino, newparent, newname
+        );
+
+        let old_path = self.ino_to_path.get(&ino).unwrap().clone();
+        let mut new_path = self.ino_to_path.get(&newparent).unwrap().clone();
+        new_path.push(newname.to_str().unwrap().to_string());
+
+        if self.is_read_only_path(&old_path) || self.is_read_only_path(&new_path) {
+            reply.error(EPERM);
+            return;
+        }
+        if old_path[0] != new_path[0] {
+            reply.error(EXDEV);
+            return;
+        }
+
+        let Some(attr) = self.get_file_attr(
+            &old_path[..old_path.len() - 1],
+            &old_path[old_path.len() - 1],
+        ) else {
+            reply.error(ENOENT);
+            return;
+        };
+        if attr.kind != FileType::RegularFile {
+            reply.error(EPERM);
+            return;
+        }
+
+        let workspace_id = self.get_workspace_id_from_name(&old_path[0]).unwrap();
+
+        if !self.copy_file_to_cow(&workspace_id, &old_path[1..]) {
+            reply.error(ENOENT);
+            return;
+        }
+
+        let res = self.local_system_workspace_manager.link_cow_file(
+            &workspace_id,
+            &old_path[1..],
+            &new_path[1..],
+        );
+        if let Err(e) = res {
+            reply.error(errno_of(e));
+            return;
+        }
+        self.add_hard_link_path(ino, &new_path);
+        if let Err(errno) = self.append_journal(JournalOp::Create, &new_path, None) {
+            reply.error(errno);
+            return;
+        }
+
+        let attr = self.get_file_attr(
+            &new_path[..new_path.len() - 1],
+            &new_path[new_path.len() - 1],
+        );
+        let attr = attr.unwrap();
+        *self.lookup_count.entry(attr.ino).or_insert(0) += 1;
+        reply.entry(&Duration::from_secs(0), &attr, 0);
+    }
 
     fn listxattr(
         &mut self,
@@ -295,7 +549,15 @@ impl Filesystem for SagittaFS {
     ) {
         self.debug_sleep();
         info!("listxattr(ino={}, size={})", ino, size);
-        reply.error(EOPNOTSUPP);
+
+        // virtual attributes are not listed so that `cp -a` or `rsync -X` don't try to copy them
+        let path = self.ino_to_path.get(&ino).unwrap().clone();
+        let mut names = vec![];
+        for name in self.get_xattrs(&path).keys() {
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+        reply_xattr(size, &names, reply);
     }
 
     fn lookup(
@@ -320,21 +582,50 @@ impl Filesystem for SagittaFS {
         }
     }
 
-    // fn lseek(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     ino: u64,
-    //     fh: u64,
-    //     offset: i64,
-    //     whence: i32,
-    //     reply: ReplyLseek,
-    // ) {
-    //     info!(
-    //         "lseek(ino={}, fh={}, offset={}, whence={})",
-    //         ino, fh, offset, whence
-    //     );
-    //     reply.error(ENOSYS);
-    // }
+    fn lseek(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        whence: i32,
+        reply: ReplyLseek,
+    ) {
+        self.debug_sleep();
+        info!(
+            "lseek(ino={}, fh={}, offset={}, whence={})",
+            ino, fh, offset, whence
+        );
+
+        let Some(handle) = self.file_handles.get(&fh) else {
+            reply.error(EBADF);
+            return;
+        };
+        match &handle.source {
+            FileHandleSource::Cow { file } => {
+                let res = LocalSystemWorkspaceManager::seek_file(file, offset, whence);
+                match res {
+                    Ok(offset) => reply.offset(offset),
+                    Err(e) => reply.error(errno_of(e)),
+                }
+            }
+            // a blob has no holes: all of it is data, followed by the hole at its end
+            FileHandleSource::Blob { file, .. } => {
+                let len = file.metadata().unwrap().len() as i64;
+                match whence {
+                    SEEK_DATA if (0..len).contains(&offset) => reply.offset(offset),
+                    SEEK_HOLE if (0..len).contains(&offset) => reply.offset(len),
+                    SEEK_DATA | SEEK_HOLE => reply.error(ENXIO),
+                    SEEK_SET if offset >= 0 => reply.offset(offset),
+                    SEEK_END if len + offset >= 0 => reply.offset(len + offset),
+                    _ => reply.error(EINVAL),
+                }
+            }
+            FileHandleSource::Dir => {
+                reply.error(EISDIR);
+            }
+        }
+    }
 
     fn mkdir(
         &mut self,
@@ -355,16 +646,26 @@ impl Filesystem for SagittaFS {
         let mut file_path = parent_path.clone();
         file_path.push(name.to_str().unwrap().to_string());
 
-        if file_path[0] == "trunk" {
+        if self.is_read_only_path(&file_path) {
             reply.error(EPERM);
             return;
         }
 
         let workspace_id = self.get_workspace_id_from_name(&file_path[0]).unwrap();
 
-        self.local_system_workspace_manager
-            .create_cow_dir(&workspace_id, &file_path[1..])
-            .unwrap();
+        let res = self.local_system_workspace_manager.create_cow_dir(
+            &workspace_id,
+            &file_path[1..],
+            Some(mode & !umask),
+        );
+        if let Err(e) = res {
+            reply.error(errno_of(e));
+            return;
+        }
+        if let Err(errno) = self.append_journal(JournalOp::Mkdir, &file_path, None) {
+            reply.error(errno);
+            return;
+        }
 
         let attr = self.get_file_attr(
             &file_path[..file_path.len() - 1],
@@ -396,22 +697,29 @@ impl Filesystem for SagittaFS {
         self.debug_sleep();
         info!("open(ino={}, flags={})", ino, flags);
 
-        reply.opened(self.next_fh, 0);
-        self.next_fh += 1;
+        match self.open_file_handle(ino, flags) {
+            Ok(fh) => reply.opened(fh, 0),
+            Err(errno) => reply.error(errno),
+        }
     }
 
-    fn opendir(&mut self, _req: &fuser::Request<'_>, _ino: u64, _flags: i32, reply: ReplyOpen) {
+    fn opendir(&mut self, _req: &fuser::Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
         self.debug_sleep();
-        info!("opendir(ino={}, flags={})", _ino, _flags);
-        reply.opened(self.next_fh, 0);
-        self.next_fh += 1;
+        info!("opendir(ino={}, flags={})", ino, flags);
+
+        let fh = self.insert_file_handle(FileHandle {
+            ino,
+            flags,
+            source: FileHandleSource::Dir,
+        });
+        reply.opened(fh, 0);
     }
 
     fn read(
         &mut self,
         _req: &fuser::Request<'_>,
         ino: u64,
-        _fh: u64,
+        fh: u64,
         offset: i64,
         size: u32,
         _flags: i32,
@@ -419,59 +727,24 @@ impl Filesystem for SagittaFS {
         reply: fuser::ReplyData,
     ) {
         self.debug_sleep();
-        info!("read(ino={}, offset={}, size={})", ino, offset, size);
-        let path = self.ino_to_path.get(&ino).unwrap().clone();
-
-        if path[0] != "trunk" {
-            let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
-            let cow_file_exists = self
-                .local_system_workspace_manager
-                .check_cow_file(&workspace_id, &path[1..])
-                .unwrap();
-            if cow_file_exists {
-                let data = self
-                    .local_system_workspace_manager
-                    .read_cow_file(&workspace_id, &path[1..], offset, size)
-                    .unwrap();
-                reply.data(&data);
-                return;
-            }
-        }
+        info!(
+            "read(ino={}, fh={}, offset={}, size={})",
+            ino, fh, offset, size
+        );
 
-        let v2_get_file_blob_id_request = V2GetFileBlobIdRequest {
-            workspace_id: if path[0] == "trunk" {
-                None
-            } else {
-                Some(path[0].clone())
-            },
-            path: path[1..].to_vec(),
+        let Some(handle) = self.file_handles.get(&fh) else {
+            reply.error(EBADF);
+            return;
         };
-        let v2_get_file_blob_id_response = self
-            .client
-            .v2_get_file_blob_id(v2_get_file_blob_id_request)
-            .unwrap();
-
-        match v2_get_file_blob_id_response {
-            V2GetFileBlobIdResponse::Found { blob_id } => {
-                let data = self
-                    .client
-                    .v2_read_blob_request(V2ReadBlobRequest {
-                        blob_id: blob_id.clone(),
-                    })
-                    .unwrap();
-                match data {
-                    V2ReadBlobResponse::Direct { blob } => {
-                        let begin = offset as usize;
-                        let end = std::cmp::min(blob.len(), (offset + size as i64) as usize);
-                        reply.data(&blob[begin..end]);
-                    }
-                    V2ReadBlobResponse::NotFound => {
-                        reply.error(ENOENT);
-                    }
-                }
+        match &handle.source {
+            FileHandleSource::Cow { file } | FileHandleSource::Blob { file, .. } => {
+                let mut data = vec![0; size as usize];
+                let len = file.read_at(&mut data, offset as u64).unwrap();
+                data.truncate(len);
+                reply.data(&data);
             }
-            V2GetFileBlobIdResponse::NotFound => {
-                reply.error(ENOENT);
+            FileHandleSource::Dir => {
+                reply.error(EISDIR);
             }
         }
     }
@@ -491,8 +764,13 @@ impl Filesystem for SagittaFS {
             entries.push((ino, FileType::Directory, ".".to_string()));
             entries.push((ino, FileType::Directory, "..".to_string()));
 
-            let trunk = self.record_ino(&vec!["trunk".to_string()]);
-            entries.push((trunk, FileType::Directory, "trunk".to_string()));
+            for branch_name in self.refresh_branches() {
+                let branch = self.record_ino(&vec![branch_name.clone()]);
+                entries.push((branch, FileType::Directory, branch_name));
+            }
+
+            let tags = self.record_ino(&vec![TAGS_DIR_NAME.to_string()]);
+            entries.push((tags, FileType::Directory, TAGS_DIR_NAME.to_string()));
 
             let mut entry_offset = 0;
             for entry in entries.into_iter() {
@@ -537,14 +815,35 @@ impl Filesystem for SagittaFS {
         let path = self.ino_to_path.get(&ino).unwrap().clone();
         assert!(!path.is_empty());
 
-        let v2_read_dir_request = V2ReadDirRequest {
-            workspace_id: if path[0] == "trunk" {
-                None
-            } else {
-                let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
-                Some(workspace_id)
-            },
-            path: path[1..].to_vec(),
+        if path.len() == 1 && path[0] == TAGS_DIR_NAME {
+            let mut entries = vec![];
+            entries.push((ino, FileType::Directory, ".".to_string()));
+            entries.push((1, FileType::Directory, "..".to_string()));
+
+            for tag_name in self.refresh_tags() {
+                let ino_child = self.record_ino(&vec![TAGS_DIR_NAME.to_string(), tag_name.clone()]);
+                entries.push((ino_child, FileType::Directory, tag_name));
+            }
+
+            for (i, entry) in entries.iter().enumerate().skip(offset as usize) {
+                if reply.add(entry.0, (i + 1) as i64, entry.1, entry.2.as_str()) {
+                    break;
+                }
+            }
+            reply.ok();
+            return;
+        }
+
+        let Some((view, view_path)) = self.resolve_view(&path) else {
+            reply.error(ENOENT);
+            return;
+        };
+
+        let v2_read_dir_request = V2ReadDirRequest {
+            workspace_id: view.workspace_id(),
+            branch_name: view.branch_name(),
+            commit_rank: view.commit_rank(),
+            path: self.remote_view_path(&view, view_path.clone()),
             include_deleted: false,
         };
         let a = self.client.v2_read_dir(v2_read_dir_request).unwrap();
@@ -560,15 +859,29 @@ impl Filesystem for SagittaFS {
         entries.push((parent, FileType::Directory, "..".to_string()));
 
         let mut not_found_flag = false;
+        // names in both the remote and the cow dir are listed once
+        let mut visited = HashSet::new();
 
         match a {
             V2ReadDirResponse::Found { items } => {
                 for item in items {
                     let mut path = path.clone();
                     path.push(item.name.clone());
+                    if let SagittaView::Workspace { workspace_id } = &view {
+                        let deleted = self
+                            .local_system_workspace_manager
+                            .check_cow_deleted(workspace_id, &path[1..])
+                            .unwrap();
+                        if deleted {
+                            continue;
+                        }
+                    }
+                    visited.insert(item.name.clone());
                     let ino_child = self.record_ino(&path);
                     if item.is_dir {
                         entries.push((ino_child, FileType::Directory, item.name.clone()));
+                    } else if item.is_symlink {
+                        entries.push((ino_child, FileType::Symlink, item.name.clone()));
                     } else {
                         entries.push((ino_child, FileType::RegularFile, item.name.clone()));
                     }
@@ -579,12 +892,10 @@ impl Filesystem for SagittaFS {
             }
         }
 
-        let mut visited = HashSet::new();
-        if path[0] != "trunk" {
-            let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
+        if let SagittaView::Workspace { workspace_id } = &view {
             let local_entries = self
                 .local_system_workspace_manager
-                .read_cow_dir(&workspace_id, &path[1..]);
+                .read_cow_dir(workspace_id, &view_path);
             if let Ok(local_entries) = local_entries {
                 for entry in local_entries {
                     if visited.contains(&entry.name) {
@@ -595,7 +906,20 @@ impl Filesystem for SagittaFS {
                     let mut path = path.clone();
                     path.push(entry.name.clone());
                     let ino_child = self.record_ino(&path);
-                    entries.push((ino_child, FileType::RegularFile, entry.name.clone()));
+                    let mut cow_path = view_path.clone();
+                    cow_path.push(entry.name.clone());
+                    let is_dir = self
+                        .local_system_workspace_manager
+                        .check_cow_dir(workspace_id, &cow_path)
+                        .unwrap();
+                    let kind = if entry.is_symlink {
+                        FileType::Symlink
+                    } else if is_dir {
+                        FileType::Directory
+                    } else {
+                        FileType::RegularFile
+                    };
+                    entries.push((ino_child, kind, entry.name.clone()));
                     not_found_flag = false;
                 }
             }
@@ -626,10 +950,55 @@ impl Filesystem for SagittaFS {
     //     reply.error(ENOSYS);
     // }
 
-    // fn readlink(&mut self, _req: &fuser::Request<'_>, ino: u64, reply: fuser::ReplyData) {
-    //     info!("readlink(ino={})", ino);
-    //     reply.error(ENOSYS);
-    // }
+    fn readlink(&mut self, _req: &fuser::Request<'_>, ino: u64, reply: fuser::ReplyData) {
+        self.debug_sleep();
+        info!("readlink(ino={})", ino);
+
+        let path = self.ino_to_path.get(&ino).unwrap().clone();
+        let Some((view, view_path)) = self.resolve_view(&path) else {
+            reply.error(ENOENT);
+            return;
+        };
+
+        if let SagittaView::Workspace { workspace_id } = &view {
+            let cow_symlink_exists = self
+                .local_system_workspace_manager
+                .check_cow_symlink(workspace_id, &view_path)
+                .unwrap();
+            if cow_symlink_exists {
+                let target = self
+                    .local_system_workspace_manager
+                    .read_cow_symlink(workspace_id, &view_path)
+                    .unwrap();
+                reply.data(target.as_bytes());
+                return;
+            }
+        }
+
+        let attr = self
+            .client
+            .v2_get_attr(V2GetAttrRequest {
+                workspace_id: view.workspace_id(),
+                branch_name: view.branch_name(),
+                commit_rank: view.commit_rank(),
+                path: self.remote_view_path(&view, view_path),
+            })
+            .unwrap();
+        match attr {
+            V2GetAttrResponse::Found {
+                symlink_target: Some(target),
+                ..
+            } => {
+                reply.data(target.as_bytes());
+            }
+            V2GetAttrResponse::Found { .. } => {
+                reply.error(EINVAL);
+            }
+            V2GetAttrResponse::NotFound => {
+                reply.error(ENOENT);
+            }
+        }
+    }
 
     fn release(
         &mut self,
@@ -646,32 +1015,74 @@ impl Filesystem for SagittaFS {
             "release(ino={}, fh={}, flags={}, lock_owner={:?}, flush={})",
             ino, fh, flags, lock_owner, flush
         );
+
+        self.file_handles.remove(&fh);
+
+        // requests still blocked on the released file would otherwise never be answered
+        for reply in self.locks.cancel_waiters(ino, fh, lock_owner) {
+            reply.error(EINTR);
+        }
+
+        // `lock_owner` is only given when the kernel asks to drop the `flock` lock
+        let mut released = self.locks.release_handle(ino, fh);
+        if let Some(lock_owner) = lock_owner {
+            released |= self.locks.release_owner(ino, lock_owner);
+        }
+        if released {
+            self.wake_lock_waiters();
+        }
         reply.ok();
     }
 
     fn releasedir(
         &mut self,
         _req: &fuser::Request<'_>,
-        _ino: u64,
-        _fh: u64,
-        _flags: i32,
+        ino: u64,
+        fh: u64,
+        flags: i32,
         reply: fuser::ReplyEmpty,
     ) {
         self.debug_sleep();
-        info!("releasedir(ino={}, fh={}, flags={})", _ino, _fh, _flags);
+        info!("releasedir(ino={}, fh={}, flags={})", ino, fh, flags);
+        self.file_handles.remove(&fh);
         reply.ok();
     }
 
-    // fn removexattr(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     ino: u64,
-    //     name: &OsStr,
-    //     reply: fuser::ReplyEmpty,
-    // ) {
-    //     info!("removexattr(ino={}, name={:?})", ino, name);
-    //     reply.error(ENOSYS);
-    // }
+    fn removexattr(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        ino: u64,
+        name: &OsStr,
+        reply: fuser::ReplyEmpty,
+    ) {
+        self.debug_sleep();
+        info!("removexattr(ino={}, name={:?})", ino, name);
+
+        if name.to_str().unwrap().starts_with(VCS_XATTR_PREFIX) {
+            reply.error(EPERM);
+            return;
+        }
+
+        let path = self.ino_to_path.get(&ino).unwrap().clone();
+        let Some(workspace_id) = self.prepare_xattr_write(&path) else {
+            reply.error(EPERM);
+            return;
+        };
+
+        let removed = self
+            .local_system_workspace_manager
+            .remove_cow_xattr(&workspace_id, &path[1..], name.to_str().unwrap())
+            .unwrap();
+        if !removed {
+            reply.error(ENODATA);
+            return;
+        }
+        if let Err(errno) = self.append_journal(JournalOp::Setattr, &path, None) {
+            reply.error(errno);
+            return;
+        }
+        reply.ok();
+    }
 
     fn rename(
         &mut self,
@@ -697,7 +1108,7 @@ impl Filesystem for SagittaFS {
         new_path.push(newname.to_str().unwrap().to_string());
         let new_path = new_path;
 
-        if old_path[0] == "trunk" || new_path[0] == "trunk" {
+        if self.is_read_only_path(&old_path) || self.is_read_only_path(&new_path) {
             reply.error(EPERM);
             return;
         }
@@ -705,6 +1116,23 @@ impl Filesystem for SagittaFS {
         let old_workspace_id = self.get_workspace_id_from_name(&old_path[0]).unwrap();
         let new_workspace_id = self.get_workspace_id_from_name(&new_path[0]).unwrap();
 
+        let Some(attr) = self.get_file_attr(
+            &old_path[..old_path.len() - 1],
+            &old_path[old_path.len() - 1],
+        ) else {
+            reply.error(ENOENT);
+            return;
+        };
+        // a dir only moves by its index entry, which can't point into another workspace
+        if attr.kind == FileType::Directory && old_workspace_id != new_workspace_id {
+            reply.error(EXDEV);
+            return;
+        }
+        if let Err(errno) = self.reference_entry(&old_workspace_id, &old_path[1..]) {
+            reply.error(errno);
+            return;
+        }
+
         let res = self.local_system_workspace_manager.rename_cow_file(
             &old_workspace_id,
             &old_path[1..],
@@ -714,10 +1142,19 @@ impl Filesystem for SagittaFS {
 
         self.ino_change_path(&old_path, &new_path);
 
-        if res.is_ok() {
-            reply.ok();
-        } else {
+        if res.is_err() {
             reply.error(ENOENT);
+            return;
+        }
+        let journaled = if old_workspace_id == new_workspace_id {
+            self.append_journal(JournalOp::Rename, &old_path, Some(&new_path))
+        } else {
+            self.append_journal(JournalOp::Unlink, &old_path, None)
+                .and_then(|()| self.append_journal(JournalOp::Create, &new_path, None))
+        };
+        match journaled {
+            Ok(()) => reply.ok(),
+            Err(errno) => reply.error(errno),
         }
     }
 
@@ -732,14 +1169,38 @@ impl Filesystem for SagittaFS {
         info!("rmdir(parent={}, name={:?})", parent, name);
         let mut path = self.ino_to_path.get(&parent).unwrap().clone();
         path.push(name.to_str().unwrap().to_string());
-        if path[0] == "trunk" {
-            reply.ok();
+        // the root of a view is removed through the CLI, never by rmdir
+        if path.len() == 1 || self.is_read_only_path(&path) {
+            reply.error(EPERM);
             return;
         }
+        match self.get_file_attr(&path[..path.len() - 1], &path[path.len() - 1]) {
+            None => {
+                reply.error(ENOENT);
+                return;
+            }
+            Some(attr) if attr.kind != FileType::Directory => {
+                reply.error(ENOTDIR);
+                return;
+            }
+            Some(_) => {}
+        }
         let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
-        self.local_system_workspace_manager
-            .delete_cow_dir(&workspace_id, &path[1..])
-            .unwrap();
+        if !self.is_cow_dir_empty(&workspace_id, &path[1..]) {
+            reply.error(ENOTEMPTY);
+            return;
+        }
+        let res = self
+            .local_system_workspace_manager
+            .delete_cow_dir(&workspace_id, &path[1..]);
+        if let Err(e) = res {
+            reply.error(errno_of(e));
+            return;
+        }
+        if let Err(errno) = self.append_journal(JournalOp::Rmdir, &path, None) {
+            reply.error(errno);
+            return;
+        }
         reply.ok();
     }
 
@@ -772,19 +1233,84 @@ impl Filesystem for SagittaFS {
 
         let path = self.ino_to_path.get(&ino).unwrap().clone();
 
+        if (size == Some(0) || mode.is_some() || mtime.is_some()) && self.is_read_only_path(&path) {
+            reply.error(EPERM);
+            return;
+        }
+
         // truncate
         if size == Some(0) {
             let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
+            let blob_ref = self.reference_file_blob(&workspace_id, &path[1..]);
+            let mode = mode.or(blob_ref.map(|blob_ref| blob_ref.mode));
             self.local_system_workspace_manager
                 .create_cow_file(&workspace_id, &path[1..], &[], mode)
                 .unwrap();
         }
 
+        // metadata of an unmodified file is changed on a reference to its blob, without a copy
         if let Some(mode) = mode {
             let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
-            self.local_system_workspace_manager
-                .change_cow_file_mode(&workspace_id, &path[1..], mode)
-                .unwrap();
+            match self.reference_file_blob(&workspace_id, &path[1..]) {
+                Some(blob_ref) => {
+                    let blob_ref = CowBlobRef {
+                        mode: mode & 0o7777,
+                        ..blob_ref
+                    };
+                    self.local_system_workspace_manager
+                        .create_cow_blob_ref(&workspace_id, &path[1..], &blob_ref)
+                        .unwrap();
+                }
+                None => {
+                    let attr = self.get_file_attr(&path[..path.len() - 1], &path[path.len() - 1]);
+                    if attr.is_some_and(|attr| attr.kind == FileType::Directory) {
+                        self.local_system_workspace_manager
+                            .set_cow_dir_mode(&workspace_id, &path[1..], mode)
+                            .unwrap();
+                    } else {
+                        self.local_system_workspace_manager
+                            .change_cow_file_mode(&workspace_id, &path[1..], mode)
+                            .unwrap();
+                    }
+                }
+            }
+        }
+
+        // atime is not tracked; mtime is kept in the cow file and synced from there
+        if let Some(mtime) = mtime {
+            let mtime = match mtime {
+                TimeOrNow::SpecificTime(time) => time,
+                TimeOrNow::Now => self.clock.now(),
+            };
+            let attr = self.get_file_attr(&path[..path.len() - 1], &path[path.len() - 1]);
+            if attr.is_some_and(|attr| attr.kind == FileType::RegularFile) {
+                let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
+                if let Some(blob_ref) = self.reference_file_blob(&workspace_id, &path[1..]) {
+                    let blob_ref = CowBlobRef {
+                        modified_at: mtime,
+                        ..blob_ref
+                    };
+                    self.local_system_workspace_manager
+                        .create_cow_blob_ref(&workspace_id, &path[1..], &blob_ref)
+                        .unwrap();
+                } else {
+                    self.local_system_workspace_manager
+                        .set_cow_file_mtime(&workspace_id, &path[1..], mtime)
+                        .unwrap();
+                }
+            }
+        }
+
+        let journaled = if size == Some(0) {
+            self.append_journal(JournalOp::Write, &path, None)
+        } else if mode.is_some() || mtime.is_some() {
+            self.append_journal(JournalOp::Setattr, &path, None)
+        } else {
+            Ok(())
+        };
+        if let Err(errno) = journaled {
+            reply.error(errno);
+            return;
         }
 
         let attr = self.get_file_attr(&path[..path.len() - 1], &path[path.len() - 1]);
@@ -795,122 +1321,266 @@ impl Filesystem for SagittaFS {
         }
     }
 
-    // fn setlk(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     ino: u64,
-    //     fh: u64,
-    //     lock_owner: u64,
-    //     start: u64,
-    //     end: u64,
-    //     typ: i32,
-    //     pid: u32,
-    //     sleep: bool,
-    //     reply: fuser::ReplyEmpty,
-    // ) {
-    //     info!(
-    //         "setlk(ino={}, fh={}, lock_owner={}, start={}, end={}, typ={}, pid={}, sleep={})",
-    //         ino, fh, lock_owner, start, end, typ, pid, sleep
-    //     );
-    //     reply.error(ENOSYS);
-    // }
-
-    // fn setxattr(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     ino: u64,
-    //     name: &OsStr,
-    //     _value: &[u8],
-    //     flags: i32,
-    //     position: u32,
-    //     reply: fuser::ReplyEmpty,
-    // ) {
-    //     info!(
-    //         "setxattr(ino={}, name={:?}, flags={}, position={})",
-    //         ino, name, flags, position
-    //     );
-    //     reply.error(ENOSYS);
-    // }
-
-    // fn statfs(&mut self, _req: &fuser::Request<'_>, _ino: u64, reply: fuser::ReplyStatfs) {
-    //     info!("statfs(ino={})", _ino);
-    //     unimplemented!()
-    // }
+    fn setlk(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        ino: u64,
+        fh: u64,
+        lock_owner: u64,
+        start: u64,
+        end: u64,
+        typ: i32,
+        pid: u32,
+        sleep: bool,
+        reply: fuser::ReplyEmpty,
+    ) {
+        self.debug_sleep();
+        info!(
+            "setlk(ino={}, fh={}, lock_owner={}, start={}, end={}, typ={}, pid={}, sleep={})",
+            ino, fh, lock_owner, start, end, typ, pid, sleep
+        );
 
-    // fn symlink(
-    //     &mut self,
-    //     _req: &fuser::Request<'_>,
-    //     parent: u64,
-    //     link_name: &OsStr,
-    //     target: &std::path::Path,
-    //     reply: fuser::ReplyEntry,
-    // ) {
-    //     info!(
-    //         "symlink(parent={}, link_name={:?}, target={:?})",
-    //         parent, link_name, target
-    //     );
-    //     reply.error(ENOSYS);
-    // }
+        let lock = FileLock {
+            fh,
+            lock_owner,
+            start,
+            end,
+            typ,
+            pid,
+        };
+        if self.locks.set_lock(ino, lock.clone()) {
+            reply.ok();
+            if typ == F_UNLCK {
+                self.wake_lock_waiters();
+            }
+        } else if sleep {
+            // the reply is held back until the lock can be taken
+            self.locks.wait(ino, lock, reply);
+        } else {
+            reply.error(EAGAIN);
+        }
+    }
 
-    fn unlink(
+    fn setxattr(
         &mut self,
         _req: &fuser::Request<'_>,
-        parent: u64,
+        ino: u64,
         name: &OsStr,
+        value: &[u8],
+        flags: i32,
+        position: u32,
         reply: fuser::ReplyEmpty,
     ) {
         self.debug_sleep();
-        info!("unlink(parent={}, name={:?})", parent, name);
-        let mut path = self.ino_to_path.get(&parent).unwrap().clone();
-        path.push(name.to_str().unwrap().to_string());
+        info!(
+            "setxattr(ino={}, name={:?}, flags={}, position={})",
+            ino, name, flags, position
+        );
 
-        let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
+        if name.to_str().unwrap().starts_with(VCS_XATTR_PREFIX) {
+            reply.error(EPERM);
+            return;
+        }
 
-        let a = self.get_file_attr(&path[..path.len() - 1], &path[path.len() - 1]);
-        if a.is_none() {
-            reply.error(ENOENT);
+        let path = self.ino_to_path.get(&ino).unwrap().clone();
+        let Some(workspace_id) = self.prepare_xattr_write(&path) else {
+            reply.error(EPERM);
+            return;
+        };
+
+        let name = name.to_str().unwrap();
+        let exists = self
+            .local_system_workspace_manager
+            .get_cow_xattrs(&workspace_id, &path[1..])
+            .unwrap()
+            .contains_key(name);
+        if flags & XATTR_CREATE != 0 && exists {
+            reply.error(EEXIST);
             return;
         }
-        let a = a.unwrap();
-        if a.kind == FileType::Directory {
-            self.local_system_workspace_manager
-                .delete_cow_dir(&workspace_id, &path[1..])
-                .unwrap();
+        if flags & XATTR_REPLACE != 0 && !exists {
+            reply.error(ENODATA);
             return;
-        } else if a.kind == FileType::RegularFile {
-            self.local_system_workspace_manager
-                .delete_cow_file(&workspace_id, &path[1..])
-                .unwrap();
         }
 
+        let res = self.local_system_workspace_manager.set_cow_xattr(
+            &workspace_id,
+            &path[1..],
+            name,
+            value,
+        );
+        if res.is_err() {
+            reply.error(EINVAL);
+            return;
+        }
+        if let Err(errno) = self.append_journal(JournalOp::Setattr, &path, None) {
+            reply.error(errno);
+            return;
+        }
         reply.ok();
     }
 
-    fn write(
+    fn statfs(&mut self, _req: &fuser::Request<'_>, ino: u64, reply: fuser::ReplyStatfs) {
+        self.debug_sleep();
+        info!("statfs(ino={})", ino);
+
+        // branches and tags live in the server's blob store, which is full by definition
+        let path = self.ino_to_path.get(&ino).cloned().unwrap_or_default();
+        if !path.is_empty() && self.is_read_only_path(&path) {
+            let stats = self.client.v2_stats(V2StatsRequest {}).unwrap();
+            reply.statfs(
+                stats.blob_total_size.div_ceil(STATFS_BLOCK_SIZE),
+                0,
+                0,
+                stats.blob_count,
+                0,
+                STATFS_BLOCK_SIZE as u32,
+                255,
+                STATFS_BLOCK_SIZE as u32,
+            );
+            return;
+        }
+
+        // writes to workspaces end up in the local cow storage
+        match statvfs(&self.config.local_system_workspace_base_path) {
+            Some(stat) => reply.statfs(
+                stat.f_blocks,
+                stat.f_bfree,
+                stat.f_bavail,
+                stat.f_files,
+                stat.f_ffree,
+                stat.f_bsize as u32,
+                stat.f_namemax as u32,
+                stat.f_frsize as u32,
+            ),
+            None => reply.error(EIO),
+        }
+    }
+
+    fn symlink(
         &mut self,
         _req: &fuser::Request<'_>,
-        ino: u64,
-        fh: u64,
-        offset: i64,
-        data: &[u8],
-        write_flags: u32,
-        flags: i32,
-        lock_owner: Option<u64>,
-        reply: ReplyWrite,
+        parent: u64,
+        link_name: &OsStr,
+        target: &std::path::Path,
+        reply: fuser::ReplyEntry,
     ) {
         self.debug_sleep();
         info!(
-            "write(ino={}, fh={}, offset={}, write_flags={}, flags={}, lock_owner={:?})",
-            ino, fh, offset, write_flags, flags, lock_owner
+            "symlink(parent={}, link_name={:?}, target={:?})",
+            parent, link_name, target
         );
-        // info!("data: {:?}", data);
 
-        let path = self.ino_to_path.get(&ino).unwrap().clone();
-        let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
+        let parent_path = self.ino_to_path.get(&parent).unwrap().clone();
+        let mut file_path = parent_path.clone();
+        file_path.push(link_name.to_str().unwrap().to_string());
+
+        if self.is_read_only_path(&file_path) {
+            reply.error(EPERM);
+            return;
+        }
+
+        let workspace_id = self.get_workspace_id_from_name(&file_path[0]).unwrap();
         self.local_system_workspace_manager
-            .write_cow_file(&workspace_id, &path[1..], offset, data)
+            .create_cow_symlink(&workspace_id, &file_path[1..], target.to_str().unwrap())
             .unwrap();
+        if let Err(errno) = self.append_journal(JournalOp::Create, &file_path, None) {
+            reply.error(errno);
+            return;
+        }
+
+        let attr = self.get_file_attr(
+            &file_path[..file_path.len() - 1],
+            &file_path[file_path.len() - 1],
+        );
+        let attr = attr.unwrap();
+        *self.lookup_count.entry(attr.ino).or_insert(0) += 1;
+        reply.entry(&Duration::from_secs(0), &attr, 0);
+    }
+
+    fn unlink(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        reply: fuser::ReplyEmpty,
+    ) {
+        self.debug_sleep();
+        info!("unlink(parent={}, name={:?})", parent, name);
+        let mut path = self.ino_to_path.get(&parent).unwrap().clone();
+        path.push(name.to_str().unwrap().to_string());
+
+        if self.is_read_only_path(&path) {
+            reply.error(EPERM);
+            return;
+        }
+
+        let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
+
+        let a = self.get_file_attr(&path[..path.len() - 1], &path[path.len() - 1]);
+        if a.is_none() {
+            reply.error(ENOENT);
+            return;
+        }
+        let a = a.unwrap();
+        if a.kind == FileType::Directory {
+            self.local_system_workspace_manager
+                .delete_cow_dir(&workspace_id, &path[1..])
+                .unwrap();
+            return;
+        } else if a.kind == FileType::RegularFile || a.kind == FileType::Symlink {
+            self.local_system_workspace_manager
+                .delete_cow_file(&workspace_id, &path[1..])
+                .unwrap();
+            self.remove_hard_link_path(&path);
+        }
 
+        if let Err(errno) = self.append_journal(JournalOp::Unlink, &path, None) {
+            reply.error(errno);
+            return;
+        }
+        reply.ok();
+    }
+
+    fn write(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        write_flags: u32,
+        flags: i32,
+        lock_owner: Option<u64>,
+        reply: ReplyWrite,
+    ) {
+        self.debug_sleep();
+        info!(
+            "write(ino={}, fh={}, offset={}, write_flags={}, flags={}, lock_owner={:?})",
+            ino, fh, offset, write_flags, flags, lock_owner
+        );
+        // info!("data: {:?}", data);
+
+        // only handles opened for writing on a workspace have a writable cow file
+        if let Err(errno) = self.ensure_cow_handle(fh) {
+            reply.error(errno);
+            return;
+        }
+        let Some(FileHandle {
+            source: FileHandleSource::Cow { file },
+            ..
+        }) = self.file_handles.get(&fh)
+        else {
+            reply.error(EBADF);
+            return;
+        };
+        file.write_all_at(data, offset as u64).unwrap();
+
+        let path = self.ino_to_path.get(&ino).unwrap().clone();
+        if let Err(errno) = self.append_journal(JournalOp::Write, &path, None) {
+            reply.error(errno);
+            return;
+        }
         reply.written(data.len() as u32);
     }
 }
@@ -942,8 +1612,261 @@ impl SagittaFS {
                 local_system_workspace_base_path,
             ),
             next_fh: 1,
+            file_handles: HashMap::new(),
             workspace_name_to_id: HashMap::new(),
+            branch_names: HashSet::from(["trunk".to_string()]),
+            tag_name_to_commit: HashMap::new(),
             lookup_count: HashMap::new(),
+            cow_ino_to_ino: HashMap::new(),
+            hard_link_paths: HashMap::new(),
+            locks: LockTable::new(),
+        }
+    }
+
+    pub fn insert_file_handle(&mut self, handle: FileHandle) -> u64 {
+        let fh = self.next_fh;
+        self.next_fh += 1;
+        self.file_handles.insert(fh, handle);
+        fh
+    }
+
+    /// Resolves what `ino` reads from and registers a handle for it.
+    /// Files of a workspace opened for writing get a cow copy first; `O_TRUNC` empties it.
+    pub fn open_file_handle(&mut self, ino: u64, flags: i32) -> Result<u64, i32> {
+        let path = self.ino_to_path.get(&ino).ok_or(ENOENT)?.clone();
+        let (view, view_path) = self.resolve_view(&path).ok_or(ENOENT)?;
+        let write = flags & O_ACCMODE != O_RDONLY;
+        let truncate = flags & O_TRUNC != 0;
+
+        let workspace_id = match view {
+            SagittaView::Workspace { workspace_id } if !view_path.is_empty() => workspace_id,
+            view => {
+                if write || truncate {
+                    return Err(EPERM);
+                }
+                let blob_id = self.fetch_blob_id(&view, view_path).ok_or(ENOENT)?;
+                let file = self.open_blob_file(&blob_id)?;
+                return Ok(self.insert_file_handle(FileHandle {
+                    ino,
+                    flags,
+                    source: FileHandleSource::Blob { blob_id, file },
+                }));
+            }
+        };
+
+        if truncate {
+            let blob_ref = self.reference_file_blob(&workspace_id, &view_path);
+            self.local_system_workspace_manager
+                .create_cow_file(
+                    &workspace_id,
+                    &view_path,
+                    &[],
+                    blob_ref.map(|blob_ref| blob_ref.mode),
+                )
+                .unwrap();
+        } else if write && !self.copy_file_to_cow(&workspace_id, &view_path) {
+            return Err(ENOENT);
+        }
+
+        let cow_file_exists = self
+            .local_system_workspace_manager
+            .check_cow_file(&workspace_id, &view_path)
+            .unwrap();
+        let source = if cow_file_exists {
+            let file = self
+                .local_system_workspace_manager
+                .open_cow_file(&workspace_id, &view_path, write, flags & O_APPEND != 0)
+                .unwrap();
+            FileHandleSource::Cow { file }
+        } else if let Some(blob_ref) = self
+            .local_system_workspace_manager
+            .get_cow_blob_ref(&workspace_id, &view_path)
+            .unwrap()
+        {
+            let file = self.open_blob_file(&blob_ref.blob_id)?;
+            FileHandleSource::Blob {
+                blob_id: blob_ref.blob_id,
+                file,
+            }
+        } else {
+            let view = SagittaView::Workspace { workspace_id };
+            let blob_id = self.fetch_blob_id(&view, view_path).ok_or(ENOENT)?;
+            let file = self.open_blob_file(&blob_id)?;
+            FileHandleSource::Blob { blob_id, file }
+        };
+        Ok(self.insert_file_handle(FileHandle { ino, flags, source }))
+    }
+
+    /// Opens the local copy of a blob for a new handle, reusing the one of a handle that
+    /// already pins it and downloading the blob otherwise.
+    fn open_blob_file(&mut self, blob_id: &str) -> Result<std::fs::File, i32> {
+        let pinned = self
+            .file_handles
+            .values()
+            .find_map(|handle| match &handle.source {
+                FileHandleSource::Blob { blob_id: id, file } if id == blob_id => Some(file),
+                _ => None,
+            });
+        if let Some(file) = pinned {
+            return file.try_clone().map_err(|_| EIO);
+        }
+        let blob = self.read_blob_by_id(blob_id).ok_or(ENOENT)?;
+        self.local_system_workspace_manager
+            .spill_blob(blob_id, &blob)
+            .map_err(errno_of)
+    }
+
+    /// Gives a handle opened for writing a cow file, materializing the blob it was reading.
+    pub fn ensure_cow_handle(&mut self, fh: u64) -> Result<(), i32> {
+        let handle = self.file_handles.get(&fh).ok_or(EBADF)?;
+        match handle.source {
+            FileHandleSource::Cow { .. } => return Ok(()),
+            FileHandleSource::Dir => return Err(EISDIR),
+            FileHandleSource::Blob { .. } => {}
+        }
+        if handle.flags & O_ACCMODE == O_RDONLY {
+            return Err(EBADF);
+        }
+        let (ino, flags) = (handle.ino, handle.flags);
+
+        let path = self.ino_to_path.get(&ino).ok_or(ENOENT)?.clone();
+        let workspace_id = self.get_workspace_id_from_name(&path[0]).ok_or(EBADF)?;
+        if !self.copy_file_to_cow(&workspace_id, &path[1..]) {
+            return Err(ENOENT);
+        }
+        let file = self
+            .local_system_workspace_manager
+            .open_cow_file(&workspace_id, &path[1..], true, flags & O_APPEND != 0)
+            .unwrap();
+        self.file_handles.get_mut(&fh).unwrap().source = FileHandleSource::Cow { file };
+        Ok(())
+    }
+
+    /// Makes the empty workspace file behind `fh_out` a reference to `blob_id` instead of
+    /// copying its bytes. Returns `false` if the file already has data.
+    pub fn copy_blob_by_reference(
+        &mut self,
+        ino_out: u64,
+        fh_out: u64,
+        blob_id: &str,
+        blob_file: &std::fs::File,
+    ) -> Result<bool, i32> {
+        let handle = self.file_handles.get(&fh_out).ok_or(EBADF)?;
+        if handle.flags & O_ACCMODE == O_RDONLY {
+            return Err(EBADF);
+        }
+        let FileHandleSource::Cow { file } = &handle.source else {
+            return Ok(false);
+        };
+        let size = blob_file.metadata().map_err(|_| EIO)?.len();
+        if size == 0 || file.metadata().unwrap().len() != 0 {
+            return Ok(false);
+        }
+
+        let path = self.ino_to_path.get(&ino_out).ok_or(ENOENT)?.clone();
+        let workspace_id = self.get_workspace_id_from_name(&path[0]).ok_or(EBADF)?;
+        let (_, _, _, mode) = self
+            .local_system_workspace_manager
+            .get_len_ctime_mtime_and_perm_of_cow_file(&workspace_id, &path[1..])
+            .unwrap();
+        let blob_ref = CowBlobRef {
+            blob_id: blob_id.to_string(),
+            size,
+            mode: mode as u32,
+            modified_at: self.clock.now(),
+        };
+        self.local_system_workspace_manager
+            .create_cow_blob_ref(&workspace_id, &path[1..], &blob_ref)
+            .unwrap();
+        let file = blob_file.try_clone().map_err(|_| EIO)?;
+        self.file_handles.get_mut(&fh_out).unwrap().source = FileHandleSource::Blob {
+            blob_id: blob_id.to_string(),
+            file,
+        };
+        Ok(true)
+    }
+
+    /// Copies up to `len` bytes of the file behind `fh_in` into the one behind `fh_out`,
+    /// returning how many were copied.
+    pub fn copy_range(
+        &mut self,
+        fh_in: u64,
+        offset_in: i64,
+        ino_out: u64,
+        fh_out: u64,
+        offset_out: i64,
+        len: u64,
+    ) -> Result<u32, i32> {
+        // both a cow file and a blob are read from the file the handle pinned
+        let (blob_id, file_in) = match &self.file_handles.get(&fh_in).ok_or(EBADF)?.source {
+            FileHandleSource::Cow { file } => (None, file.try_clone().map_err(|_| EIO)?),
+            FileHandleSource::Blob { blob_id, file } => {
+                (Some(blob_id.clone()), file.try_clone().map_err(|_| EIO)?)
+            }
+            FileHandleSource::Dir => return Err(EISDIR),
+        };
+
+        // copying a whole blob into an empty file only records a reference to the blob
+        if let Some(blob_id) = &blob_id {
+            let size = file_in.metadata().map_err(|_| EIO)?.len();
+            if offset_in == 0
+                && offset_out == 0
+                && len >= size
+                && self.copy_blob_by_reference(ino_out, fh_out, blob_id, &file_in)?
+            {
+                return Ok(size as u32);
+            }
+        }
+
+        self.ensure_cow_handle(fh_out)?;
+        let Some(FileHandle {
+            source: FileHandleSource::Cow { file: file_out },
+            ..
+        }) = self.file_handles.get(&fh_out)
+        else {
+            return Err(EBADF);
+        };
+        let copied = LocalSystemWorkspaceManager::copy_file_range(
+            &file_in, offset_in, file_out, offset_out, len,
+        )
+        .map_err(errno_of)?;
+        Ok(copied as u32)
+    }
+
+    /// Looks up the blob id of a synced or committed file.
+    pub fn fetch_blob_id(&mut self, view: &SagittaView, view_path: Vec<String>) -> Option<String> {
+        let blob_id = self
+            .client
+            .v2_get_file_blob_id(V2GetFileBlobIdRequest {
+                workspace_id: view.workspace_id(),
+                branch_name: view.branch_name(),
+                commit_rank: view.commit_rank(),
+                path: self.remote_view_path(view, view_path),
+            })
+            .unwrap();
+        match blob_id {
+            V2GetFileBlobIdResponse::Found { blob_id } => Some(blob_id),
+            V2GetFileBlobIdResponse::NotFound => None,
+        }
+    }
+
+    pub fn read_blob_by_id(&mut self, blob_id: &str) -> Option<Vec<u8>> {
+        let blob = self
+            .client
+            .v2_read_blob_request(V2ReadBlobRequest {
+                blob_id: blob_id.to_string(),
+            })
+            .unwrap();
+        match blob {
+            V2ReadBlobResponse::Direct { blob } => Some(blob),
+            V2ReadBlobResponse::NotFound => None,
+        }
+    }
+
+    /// Grants the blocking lock requests that no longer conflict.
+    pub fn wake_lock_waiters(&mut self) {
+        for reply in self.locks.grant_waiters() {
+            reply.ok();
         }
     }
 
@@ -959,10 +1882,108 @@ impl SagittaFS {
         ino
     }
 
+    /// Hard linked cow files share the inode of the first path seen for them.
+    pub fn record_hard_link_ino(&mut self, path: &Vec<String>, cow_ino: u64) -> u64 {
+        if let Some(ino) = self.cow_ino_to_ino.get(&cow_ino) {
+            let ino = *ino;
+            // the cow file may have been archived by a sync and its inode number reused
+            if self.is_cow_ino_of(ino, cow_ino) {
+                self.add_hard_link_path(ino, path);
+                return ino;
+            }
+            self.cow_ino_to_ino.remove(&cow_ino);
+            self.hard_link_paths.remove(&ino);
+        }
+        let ino = self.record_ino(path);
+        self.cow_ino_to_ino.insert(cow_ino, ino);
+        ino
+    }
+
+    /// Returns `true` if the path `ino` resolves to is still the cow file `cow_ino`.
+    fn is_cow_ino_of(&mut self, ino: u64, cow_ino: u64) -> bool {
+        let Some(path) = self.ino_to_path.get(&ino).cloned() else {
+            return false;
+        };
+        let Some(workspace_id) = self.get_workspace_id_from_name(&path[0]) else {
+            return false;
+        };
+        self.local_system_workspace_manager
+            .get_ino_and_nlink_of_cow_file(&workspace_id, &path[1..])
+            .is_ok_and(|(ino, _)| ino == cow_ino)
+    }
+
+    /// Records `path` as another link of `ino`.
+    pub fn add_hard_link_path(&mut self, ino: u64, path: &[String]) {
+        self.path_to_ino.insert(path.to_vec(), ino);
+        let paths = self
+            .hard_link_paths
+            .entry(ino)
+            .or_insert_with(|| self.ino_to_path.get(&ino).cloned().into_iter().collect());
+        if !paths.iter().any(|linked_path| linked_path == path) {
+            paths.push(path.to_vec());
+        }
+    }
+
+    /// Forgets a removed link. The inode moves on to one of its remaining paths, and once
+    /// the last one is gone its cow inode number is no longer tied to it.
+    pub fn remove_hard_link_path(&mut self, path: &[String]) {
+        let Some(&ino) = self.path_to_ino.get(path) else {
+            return;
+        };
+        let Some(paths) = self.hard_link_paths.get_mut(&ino) else {
+            self.cow_ino_to_ino
+                .retain(|_, linked_ino| *linked_ino != ino);
+            return;
+        };
+        paths.retain(|linked_path| linked_path != path);
+        self.path_to_ino.remove(path);
+        if self
+            .ino_to_path
+            .get(&ino)
+            .is_some_and(|ino_path| ino_path == path)
+        {
+            if let Some(linked_path) = paths.first() {
+                self.ino_to_path.insert(ino, linked_path.clone());
+            }
+        }
+        if paths.len() <= 1 {
+            self.hard_link_paths.remove(&ino);
+        }
+    }
+
     pub fn ino_change_path(&mut self, old_path: &Vec<String>, new_path: &[String]) {
+        // a file replaced by the rename loses its link
+        if self.path_to_ino.get(new_path) != self.path_to_ino.get(old_path) {
+            self.remove_hard_link_path(new_path);
+        }
+        for paths in self.hard_link_paths.values_mut() {
+            for path in paths.iter_mut() {
+                if path.starts_with(old_path) {
+                    let mut moved_path = new_path.to_owned();
+                    moved_path.extend_from_slice(&path[old_path.len()..]);
+                    *path = moved_path;
+                }
+            }
+        }
+
         let ino = self.path_to_ino.remove(old_path).unwrap();
         self.ino_to_path.insert(ino, new_path.to_owned());
         self.path_to_ino.insert(new_path.to_owned(), ino);
+
+        // the inodes under a moved dir keep their numbers
+        let children = self
+            .path_to_ino
+            .keys()
+            .filter(|path| path.len() > old_path.len() && path.starts_with(old_path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for old_child_path in children {
+            let ino = self.path_to_ino.remove(&old_child_path).unwrap();
+            let mut new_child_path = new_path.to_owned();
+            new_child_path.extend_from_slice(&old_child_path[old_path.len()..]);
+            self.ino_to_path.insert(ino, new_child_path.clone());
+            self.path_to_ino.insert(new_child_path, ino);
+        }
     }
 
     pub fn debug_sleep(&self) {
@@ -1012,6 +2033,512 @@ impl SagittaFS {
         }
     }
 
+    /// Records a mutating operation on a workspace path in the journal of its workspace,
+    /// so the next sync only has to look at the touched paths. Fails with `EIO` when the
+    /// journal can't be written, after the operation itself was done.
+    pub fn append_journal(
+        &mut self,
+        op: JournalOp,
+        path: &[String],
+        to_path: Option<&[String]>,
+    ) -> Result<(), i32> {
+        let Some((SagittaView::Workspace { workspace_id }, view_path)) = self.resolve_view(path)
+        else {
+            return Ok(());
+        };
+        if view_path.is_empty() {
+            return Ok(());
+        }
+        self.local_system_workspace_manager
+            .append_journal(&workspace_id, op, &view_path, to_path.map(|p| &p[1..]))
+            .map_err(|e| {
+                error!(
+                    "failed to append to the journal of {}: {:?}",
+                    workspace_id, e
+                );
+                EIO
+            })
+    }
+
+    pub fn get_dir_file_attr(&self, ino: u64, perm: u16) -> FileAttr {
+        FileAttr {
+            ino,
+            size: 0,
+            blocks: 0,
+            atime: self.clock.now(),
+            mtime: self.clock.now(),
+            ctime: self.clock.now(),
+            crtime: self.clock.now(),
+            kind: FileType::Directory,
+            perm,
+            nlink: 2,
+            uid: self.config.uid,
+            gid: self.config.gid,
+            rdev: 0,
+            flags: 0,
+            blksize: 512,
+        }
+    }
+
+    /// Reloads the tag list from the remote and returns the tag names.
+    pub fn refresh_tags(&mut self) -> Vec<String> {
+        let tags = self.client.v2_get_tags(V2GetTagsRequest {}).unwrap();
+        match tags {
+            V2GetTagsResponse::Ok { items } => {
+                self.tag_name_to_commit = items
+                    .iter()
+                    .map(|item| {
+                        (
+                            item.name.clone(),
+                            (item.branch_name.clone(), item.commit_rank),
+                        )
+                    })
+                    .collect();
+                items.into_iter().map(|item| item.name).collect()
+            }
+        }
+    }
+
+    pub fn get_tag_commit(&mut self, tag_name: &str) -> Option<(String, i64)> {
+        if let Some(commit) = self.tag_name_to_commit.get(tag_name) {
+            return Some(commit.clone());
+        }
+
+        self.refresh_tags();
+        self.tag_name_to_commit.get(tag_name).cloned()
+    }
+
+    /// Reloads the branch list from the remote and returns the branch names.
+    pub fn refresh_branches(&mut self) -> Vec<String> {
+        let branches = self
+            .client
+            .v2_get_branches(V2GetBranchesRequest {})
+            .unwrap();
+        match branches {
+            V2GetBranchesResponse::Ok { items } => {
+                self.branch_names = items.iter().map(|item| item.name.clone()).collect();
+                items.into_iter().map(|item| item.name).collect()
+            }
+        }
+    }
+
+    /// Splits a mounted path into the view serving it and the path inside that view.
+    pub fn resolve_view(&mut self, path: &[String]) -> Option<(SagittaView, Vec<String>)> {
+        if path[0] == TAGS_DIR_NAME {
+            if path.len() < 2 {
+                return None;
+            }
+            let (branch_name, commit_rank) = self.get_tag_commit(&path[1])?;
+            let view = SagittaView::Tag {
+                branch_name,
+                commit_rank,
+            };
+            return Some((view, path[2..].to_vec()));
+        }
+
+        if !self.branch_names.contains(&path[0]) {
+            if let Some(workspace_id) = self.get_workspace_id_from_name(&path[0]) {
+                return Some((SagittaView::Workspace { workspace_id }, path[1..].to_vec()));
+            }
+            self.refresh_branches();
+        }
+
+        if self.branch_names.contains(&path[0]) {
+            let view = SagittaView::Branch {
+                branch_name: path[0].clone(),
+            };
+            return Some((view, path[1..].to_vec()));
+        }
+
+        None
+    }
+
+    /// Only workspaces are writable; branches and tags are read-only.
+    pub fn is_read_only_path(&mut self, path: &[String]) -> bool {
+        !matches!(
+            self.resolve_view(path),
+            Some((SagittaView::Workspace { .. }, _))
+        )
+    }
+
+    pub fn get_xattrs(&mut self, path: &[String]) -> BTreeMap<String, Vec<u8>> {
+        let Some((view, view_path)) = self.resolve_view(path) else {
+            return BTreeMap::new();
+        };
+        if view_path.is_empty() {
+            return BTreeMap::new();
+        }
+
+        if let SagittaView::Workspace { workspace_id } = &view {
+            let cow_file_exists = self
+                .local_system_workspace_manager
+                .check_cow_file(workspace_id, &view_path)
+                .unwrap();
+            let blob_ref_exists = self
+                .local_system_workspace_manager
+                .get_cow_blob_ref(workspace_id, &view_path)
+                .unwrap()
+                .is_some();
+            if cow_file_exists || blob_ref_exists {
+                return self
+                    .local_system_workspace_manager
+                    .get_cow_xattrs(workspace_id, &view_path)
+                    .unwrap();
+            }
+        }
+
+        let xattrs = self
+            .client
+            .v2_get_xattrs(V2GetXattrsRequest {
+                workspace_id: view.workspace_id(),
+                branch_name: view.branch_name(),
+                commit_rank: view.commit_rank(),
+                path: self.remote_view_path(&view, view_path),
+            })
+            .unwrap();
+        match xattrs {
+            V2GetXattrsResponse::Found { xattrs } => xattrs,
+            V2GetXattrsResponse::NotFound => BTreeMap::new(),
+        }
+    }
+
+    /// Serves the `user.sagitta.*` attributes: `blob_id`, `commit_rank`, `sha256` and `state`.
+    pub fn get_vcs_xattr(&mut self, path: &[String], name: &str) -> Option<Vec<u8>> {
+        let (view, view_path) = self.resolve_view(path)?;
+        if view_path.is_empty() {
+            return None;
+        }
+        let field = &name[VCS_XATTR_PREFIX.len()..];
+        // only these need the content of a local file
+        let needs_hash = matches!(field, "sha256" | "state");
+
+        let file_state = self
+            .client
+            .v2_get_file_state(V2GetFileStateRequest {
+                workspace_id: view.workspace_id(),
+                branch_name: view.branch_name(),
+                commit_rank: view.commit_rank(),
+                path: self.remote_view_path(&view, view_path.clone()),
+            })
+            .unwrap();
+        let (mut blob_id, mut sha256, commit_rank, mut state) = match file_state {
+            V2GetFileStateResponse::Found {
+                blob_id,
+                sha256,
+                commit_rank,
+                state,
+            } => (blob_id, sha256, commit_rank, Some(state)),
+            V2GetFileStateResponse::NotFound => (None, None, None, None),
+        };
+
+        // a cow file has not been synced yet, so its content is hashed locally
+        if let SagittaView::Workspace { workspace_id } = &view {
+            let cow_file_exists = self
+                .local_system_workspace_manager
+                .check_cow_file(workspace_id, &view_path)
+                .unwrap();
+            if cow_file_exists {
+                // the blob of a cow file is only known once it is synced
+                blob_id = None;
+                if needs_hash {
+                    let file = self
+                        .local_system_workspace_manager
+                        .open_cow_file(workspace_id, &view_path, false, false)
+                        .ok()?;
+                    let local_sha256 = calc_sha256_from_reader(file).ok()?;
+                    let unchanged = state == Some(V2FileState::Clean)
+                        && sha256.as_deref() == Some(local_sha256.as_str());
+                    if !unchanged {
+                        state = Some(if commit_rank.is_some() {
+                            V2FileState::Modified
+                        } else {
+                            V2FileState::New
+                        });
+                    }
+                    sha256 = Some(local_sha256);
+                }
+            } else if let Some(blob_ref) = self
+                .local_system_workspace_manager
+                .get_cow_blob_ref(workspace_id, &view_path)
+                .unwrap()
+            {
+                if blob_id.as_deref() != Some(blob_ref.blob_id.as_str()) {
+                    sha256 = if field == "sha256" {
+                        self.read_blob_by_id(&blob_ref.blob_id)
+                            .map(|blob| calc_sha256_from_slice(&blob))
+                    } else {
+                        None
+                    };
+                    blob_id = Some(blob_ref.blob_id);
+                    state = Some(if commit_rank.is_some() {
+                        V2FileState::Modified
+                    } else {
+                        V2FileState::New
+                    });
+                }
+            }
+        }
+
+        state?;
+        let value = match field {
+            "blob_id" => blob_id?,
+            "commit_rank" => commit_rank?.to_string(),
+            "sha256" => sha256?,
+            "state" => match state? {
+                V2FileState::Clean => "clean".to_string(),
+                V2FileState::Modified => "modified".to_string(),
+                V2FileState::New => "new".to_string(),
+            },
+            _ => return None,
+        };
+        Some(value.into_bytes())
+    }
+
+    /// Copies the extended attributes of a synced or committed file into its cow file.
+    pub fn copy_xattrs_to_cow(&mut self, workspace_id: &str, path: &[String]) {
+        let xattrs = self
+            .client
+            .v2_get_xattrs(V2GetXattrsRequest {
+                workspace_id: Some(workspace_id.to_string()),
+                branch_name: None,
+                commit_rank: None,
+                path: self.synced_path(workspace_id, path).unwrap(),
+            })
+            .unwrap();
+        if let V2GetXattrsResponse::Found { xattrs } = xattrs {
+            for (name, value) in xattrs {
+                self.local_system_workspace_manager
+                    .set_cow_xattr(workspace_id, path, &name, &value)
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Makes sure a synced or committed file has a cow copy. Returns `false` if it does not exist.
+    pub fn copy_file_to_cow(&mut self, workspace_id: &str, path: &[String]) -> bool {
+        let cow_file_exists = self
+            .local_system_workspace_manager
+            .check_cow_file(workspace_id, path)
+            .unwrap();
+        if cow_file_exists {
+            return true;
+        }
+
+        // the reference carries the mode, mtime and extended attributes of the file
+        let Some(blob_ref) = self.reference_file_blob(workspace_id, path) else {
+            return false;
+        };
+        let Some(blob) = self.read_blob_by_id(&blob_ref.blob_id) else {
+            return false;
+        };
+        self.local_system_workspace_manager
+            .create_cow_file(workspace_id, path, &blob, Some(blob_ref.mode))
+            .unwrap();
+
+        // copying alone must not look like a modification to build tools
+        self.local_system_workspace_manager
+            .set_cow_file_mtime(workspace_id, path, blob_ref.modified_at)
+            .unwrap();
+        true
+    }
+
+    /// Extended attributes can only be written on regular files of a workspace.
+    /// Returns the workspace id once the file has a cow copy or a blob reference to hold them.
+    pub fn prepare_xattr_write(&mut self, path: &[String]) -> Option<String> {
+        if path.len() < 2 || self.is_read_only_path(path) {
+            return None;
+        }
+        let attr = self.get_file_attr(&path[..path.len() - 1], &path[path.len() - 1])?;
+        if attr.kind != FileType::RegularFile {
+            return None;
+        }
+        let workspace_id = self.get_workspace_id_from_name(&path[0])?;
+        let cow_file_exists = self
+            .local_system_workspace_manager
+            .check_cow_file(&workspace_id, &path[1..])
+            .unwrap();
+        if !cow_file_exists
+            && self
+                .reference_file_blob(&workspace_id, &path[1..])
+                .is_none()
+        {
+            return None;
+        }
+        Some(workspace_id)
+    }
+
+    /// Returns `true` if a dir of a workspace has no entries, neither local nor remote.
+    pub fn is_cow_dir_empty(&mut self, workspace_id: &str, path: &[String]) -> bool {
+        let local_entries = self
+            .local_system_workspace_manager
+            .read_cow_dir(workspace_id, path);
+        if local_entries.is_ok_and(|entries| !entries.is_empty()) {
+            return false;
+        }
+        let items = self
+            .client
+            .v2_read_dir(V2ReadDirRequest {
+                path: self.synced_path(workspace_id, path).unwrap(),
+                workspace_id: Some(workspace_id.to_string()),
+                branch_name: None,
+                commit_rank: None,
+                include_deleted: false,
+            })
+            .unwrap();
+        let V2ReadDirResponse::Found { items } = items else {
+            return true;
+        };
+        items.into_iter().all(|item| {
+            let mut path = path.to_vec();
+            path.push(item.name);
+            self.local_system_workspace_manager
+                .check_cow_deleted(workspace_id, &path)
+                .unwrap()
+        })
+    }
+
+    /// Gives `path` a local entry that can be moved within the workspace: synced files
+    /// become blob references, symlinks are copied and dirs get an empty cow dir. The
+    /// contents of a dir are left on the remote, where they are found through its rename.
+    pub fn reference_entry(&mut self, workspace_id: &str, path: &[String]) -> Result<(), i32> {
+        if self.reference_file_blob(workspace_id, path).is_some() {
+            return Ok(());
+        }
+        let attr = self
+            .client
+            .v2_get_attr(V2GetAttrRequest {
+                path: self.synced_path(workspace_id, path)?,
+                workspace_id: Some(workspace_id.to_string()),
+                branch_name: None,
+                commit_rank: None,
+            })
+            .map_err(|_| EIO)?;
+        match attr {
+            V2GetAttrResponse::Found {
+                symlink_target: Some(target),
+                ..
+            } => {
+                let cow_symlink_exists = self
+                    .local_system_workspace_manager
+                    .check_cow_symlink(workspace_id, path)
+                    .map_err(errno_of)?;
+                if !cow_symlink_exists {
+                    self.local_system_workspace_manager
+                        .create_cow_symlink(workspace_id, path, &target)
+                        .map_err(errno_of)?;
+                    self.local_system_workspace_manager
+                        .record_cow_copy(workspace_id, path)
+                        .map_err(errno_of)?;
+                }
+            }
+            V2GetAttrResponse::Found { is_dir: true, .. } => {
+                let cow_dir_exists = self
+                    .local_system_workspace_manager
+                    .check_cow_dir(workspace_id, path)
+                    .map_err(errno_of)?;
+                if !cow_dir_exists {
+                    self.local_system_workspace_manager
+                        .create_cow_dir(workspace_id, path, None)
+                        .map_err(errno_of)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the path a workspace file was synced at, following renames not synced yet.
+    pub fn synced_path(&self, workspace_id: &str, path: &[String]) -> Result<Vec<String>, i32> {
+        self.local_system_workspace_manager
+            .get_synced_path(workspace_id, path)
+            .map_err(errno_of)
+    }
+
+    /// Returns the path to read from the remote for `view_path` of `view`.
+    pub fn remote_view_path(&self, view: &SagittaView, view_path: Vec<String>) -> Vec<String> {
+        match view {
+            SagittaView::Workspace { workspace_id } => {
+                self.synced_path(workspace_id, &view_path).unwrap()
+            }
+            _ => view_path,
+        }
+    }
+
+    /// Returns the blob reference of a workspace file that has no cow copy, recording one
+    /// for a synced or committed file first. Returns `None` for cow files, symlinks and dirs.
+    pub fn reference_file_blob(
+        &mut self,
+        workspace_id: &str,
+        path: &[String],
+    ) -> Option<CowBlobRef> {
+        let blob_ref = self
+            .local_system_workspace_manager
+            .get_cow_blob_ref(workspace_id, path)
+            .unwrap();
+        if blob_ref.is_some() {
+            return blob_ref;
+        }
+        let cow_exists = self
+            .local_system_workspace_manager
+            .check_cow_file(workspace_id, path)
+            .unwrap()
+            || self
+                .local_system_workspace_manager
+                .check_cow_symlink(workspace_id, path)
+                .unwrap()
+            || self
+                .local_system_workspace_manager
+                .check_cow_dir(workspace_id, path)
+                .unwrap();
+        if cow_exists {
+            return None;
+        }
+
+        let attr = self
+            .client
+            .v2_get_attr(V2GetAttrRequest {
+                path: self.synced_path(workspace_id, path).unwrap(),
+                workspace_id: Some(workspace_id.to_string()),
+                branch_name: None,
+                commit_rank: None,
+            })
+            .unwrap();
+        let V2GetAttrResponse::Found {
+            is_dir: false,
+            size,
+            modified_at,
+            permission,
+            symlink_target: None,
+        } = attr
+        else {
+            return None;
+        };
+        let blob_id = self
+            .client
+            .v2_get_file_blob_id(V2GetFileBlobIdRequest {
+                workspace_id: Some(workspace_id.to_string()),
+                branch_name: None,
+                commit_rank: None,
+                path: self.synced_path(workspace_id, path).unwrap(),
+            })
+            .unwrap();
+        let V2GetFileBlobIdResponse::Found { blob_id } = blob_id else {
+            return None;
+        };
+
+        let blob_ref = CowBlobRef {
+            blob_id,
+            size,
+            mode: permission as u32,
+            modified_at,
+        };
+        self.local_system_workspace_manager
+            .create_cow_blob_ref(workspace_id, path, &blob_ref)
+            .unwrap();
+        self.copy_xattrs_to_cow(workspace_id, path);
+        Some(blob_ref)
+    }
+
     pub fn get_file_attr(&mut self, parent: &[String], file_name: &str) -> Option<FileAttr> {
         if parent.is_empty() {
             let mut path = parent.to_vec();
@@ -1031,8 +2558,14 @@ impl SagittaFS {
                     }
 
                     // return attr
-                    if items.iter().any(|item| item.name == path[0]) || &path[0] == "trunk" {
-                        let perm = if file_name == "trunk" { 0o555 } else { 0o755 };
+                    let is_workspace = items.iter().any(|item| item.name == path[0]);
+                    let is_read_only = path[0] == TAGS_DIR_NAME
+                        || matches!(
+                            self.resolve_view(&path),
+                            Some((SagittaView::Branch { .. }, _))
+                        );
+                    if is_workspace || is_read_only {
+                        let perm = if is_workspace { 0o755 } else { 0o555 };
                         let attr = FileAttr {
                             ino,
                             size: 0,
@@ -1065,17 +2598,63 @@ impl SagittaFS {
         let mut path = parent.to_vec();
         path.push(file_name.to_string());
 
-        if path[0] != "trunk" {
-            let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
+        let (view, view_path) = self.resolve_view(&path)?;
+
+        // root of a tag
+        if view_path.is_empty() {
+            let ino = self.record_ino(&path);
+            return Some(self.get_dir_file_attr(ino, 0o555));
+        }
+
+        if let SagittaView::Workspace { workspace_id } = &view {
+            let cow_symlink_exists = self
+                .local_system_workspace_manager
+                .check_cow_symlink(workspace_id, &view_path)
+                .unwrap();
+            if cow_symlink_exists {
+                let ino = self.record_ino(&path);
+                let target = self
+                    .local_system_workspace_manager
+                    .read_cow_symlink(workspace_id, &view_path)
+                    .unwrap();
+                let len = target.len() as u64;
+                let attr = FileAttr {
+                    ino,
+                    size: len,
+                    blocks: len.div_ceil(512),
+                    atime: self.clock.now(),
+                    mtime: self.clock.now(),
+                    ctime: self.clock.now(),
+                    crtime: self.clock.now(),
+                    kind: FileType::Symlink,
+                    perm: 0o777,
+                    nlink: 1,
+                    uid: self.config.uid,
+                    gid: self.config.gid,
+                    rdev: 0,
+                    flags: 0,
+                    blksize: 512,
+                };
+                return Some(attr);
+            }
+
             let cow_file_exists = self
                 .local_system_workspace_manager
-                .check_cow_file(&workspace_id, &path[1..])
+                .check_cow_file(workspace_id, &view_path)
                 .unwrap();
             if cow_file_exists {
-                let ino = self.record_ino(&path);
+                let (cow_ino, nlink) = self
+                    .local_system_workspace_manager
+                    .get_ino_and_nlink_of_cow_file(workspace_id, &view_path)
+                    .unwrap();
+                let ino = if nlink > 1 {
+                    self.record_hard_link_ino(&path, cow_ino)
+                } else {
+                    self.record_ino(&path)
+                };
                 let (len, mut ctime, mut mtime, perm) = self
                     .local_system_workspace_manager
-                    .get_len_ctime_mtime_and_perm_of_cow_file(&workspace_id, &path[1..])
+                    .get_len_ctime_mtime_and_perm_of_cow_file(workspace_id, &view_path)
                     .unwrap();
                 if self.clock.is_fixed() {
                     ctime = self.clock.now();
@@ -1084,13 +2663,44 @@ impl SagittaFS {
                 let attr = FileAttr {
                     ino,
                     size: len,
-                    blocks: (len + 511) / 512,
+                    blocks: len.div_ceil(512),
                     atime: self.clock.now(),
                     mtime,
                     ctime,
                     crtime: ctime,
                     kind: FileType::RegularFile,
                     perm: perm as u16,
+                    nlink: nlink as u32,
+                    uid: self.config.uid,
+                    gid: self.config.gid,
+                    rdev: 0,
+                    flags: 0,
+                    blksize: 512,
+                };
+                return Some(attr);
+            }
+
+            let blob_ref = self
+                .local_system_workspace_manager
+                .get_cow_blob_ref(workspace_id, &view_path)
+                .unwrap();
+            if let Some(blob_ref) = blob_ref {
+                let ino = self.record_ino(&path);
+                let mtime = if self.clock.is_fixed() {
+                    self.clock.now()
+                } else {
+                    blob_ref.modified_at
+                };
+                let attr = FileAttr {
+                    ino,
+                    size: blob_ref.size,
+                    blocks: blob_ref.size.div_ceil(512),
+                    atime: self.clock.now(),
+                    mtime,
+                    ctime: mtime,
+                    crtime: mtime,
+                    kind: FileType::RegularFile,
+                    perm: blob_ref.mode as u16,
                     nlink: 1,
                     uid: self.config.uid,
                     gid: self.config.gid,
@@ -1103,9 +2713,35 @@ impl SagittaFS {
 
             let cow_dir_exists = self
                 .local_system_workspace_manager
-                .check_cow_dir(&workspace_id, &path[1..])
+                .check_cow_dir(workspace_id, &view_path)
                 .unwrap();
             if cow_dir_exists {
+                // a new or changed dir keeps its mode in the index, a copied one on the remote
+                let mode = self
+                    .local_system_workspace_manager
+                    .get_index_entry(workspace_id, &view_path)
+                    .unwrap()
+                    .and_then(|entry| entry.mode);
+                let perm = match mode {
+                    Some(mode) => mode as u16,
+                    None => match self
+                        .client
+                        .v2_get_attr(V2GetAttrRequest {
+                            workspace_id: view.workspace_id(),
+                            branch_name: view.branch_name(),
+                            commit_rank: view.commit_rank(),
+                            path: self.remote_view_path(&view, view_path.clone()),
+                        })
+                        .unwrap()
+                    {
+                        V2GetAttrResponse::Found {
+                            is_dir: true,
+                            permission,
+                            ..
+                        } => permission as u16,
+                        _ => 0o755,
+                    },
+                };
                 let ino = self.record_ino(&path);
                 let attr = FileAttr {
                     ino,
@@ -1116,7 +2752,7 @@ impl SagittaFS {
                     ctime: self.clock.now(),
                     crtime: self.clock.now(),
                     kind: FileType::Directory,
-                    perm: 0o755,
+                    perm,
                     nlink: 2,
                     uid: self.config.uid,
                     gid: self.config.gid,
@@ -1126,19 +2762,23 @@ impl SagittaFS {
                 };
                 return Some(attr);
             }
+
+            let deleted = self
+                .local_system_workspace_manager
+                .check_cow_deleted(workspace_id, &view_path)
+                .unwrap();
+            if deleted {
+                return None;
+            }
         }
 
-        let workspace_id = if path[0] == "trunk" {
-            None
-        } else {
-            let workspace_id = self.get_workspace_id_from_name(&path[0]).unwrap();
-            Some(workspace_id)
-        };
         let attr = self
             .client
             .v2_get_attr(V2GetAttrRequest {
-                workspace_id,
-                path: path[1..].to_vec(),
+                workspace_id: view.workspace_id(),
+                branch_name: view.branch_name(),
+                commit_rank: view.commit_rank(),
+                path: self.remote_view_path(&view, view_path),
             })
             .unwrap();
 
@@ -1149,8 +2789,9 @@ impl SagittaFS {
                 size,
                 modified_at,
                 permission,
+                symlink_target,
             } => {
-                let perm = if path[0] == "trunk" {
+                let perm = if view.is_read_only() {
                     permission as u16 & 0o555
                 } else {
                     permission as u16
@@ -1167,13 +2808,15 @@ impl SagittaFS {
                 let nlink = if is_dir { 2 } else { 1 };
                 let kind = if is_dir {
                     FileType::Directory
+                } else if symlink_target.is_some() {
+                    FileType::Symlink
                 } else {
                     FileType::RegularFile
                 };
                 FileAttr {
                     ino,
                     size,
-                    blocks: (size + 511) / 512,
+                    blocks: size.div_ceil(512),
                     atime: modified_at,
                     mtime: modified_at,
                     ctime: modified_at,
@@ -1207,6 +2850,32 @@ pub struct SagittaConfig {
     pub debug_sleep_duration: Option<Duration>,
 }
 
+fn statvfs(path: &Path) -> Option<libc::statvfs> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    let res = unsafe { libc::statvfs(path.as_ptr(), &mut stat) };
+    (res == 0).then_some(stat)
+}
+
+fn reply_xattr(size: u32, data: &[u8], reply: fuser::ReplyXattr) {
+    if size == 0 {
+        reply.size(data.len() as u32);
+    } else if data.len() > size as usize {
+        reply.error(ERANGE);
+    } else {
+        reply.data(data);
+    }
+}
+
+/// The errno of a failed cow file operation.
+fn errno_of(e: sagitta_local_system_workspace::Error) -> i32 {
+    match e {
+        sagitta_local_system_workspace::Error::IOError(e) => e.raw_os_error().unwrap_or(EIO),
+        sagitta_local_system_workspace::Error::IndexError(_)
+        | sagitta_local_system_workspace::Error::JournalError(_) => EIO,
+    }
+}
+
 pub fn run_fs(config: SagittaConfig) {
     let mountpoint = std::path::Path::new(&config.mountpoint).to_path_buf();
     if !mountpoint.exists() {
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use libc::{F_UNLCK, F_WRLCK};
+
+/// An advisory lock over the inclusive byte range `start..=end` of a file.
+/// `flock` locks arrive from the kernel as whole-file locks owned by the open file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLock {
+    pub fh: u64,
+    pub lock_owner: u64,
+    pub start: u64,
+    pub end: u64,
+    pub typ: i32,
+    pub pid: u32,
+}
+
+impl FileLock {
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start <= end && start <= self.end
+    }
+}
+
+/// Advisory locks held on the files of one mount, by inode, and the blocking requests
+/// waiting for them. `W` is what is kept to answer a waiting request.
+#[derive(Debug)]
+pub struct LockTable<W> {
+    locks: HashMap<u64, Vec<FileLock>>,
+    waiters: Vec<(u64, FileLock, W)>,
+}
+
+impl<W> Default for LockTable<W> {
+    fn default() -> Self {
+        Self {
+            locks: HashMap::new(),
+            waiters: vec![],
+        }
+    }
+}
+
+impl<W> LockTable<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a lock of another owner that prevents `lock` from being taken.
+    pub fn find_conflict(&self, ino: u64, lock: &FileLock) -> Option<&FileLock> {
+        self.locks.get(&ino)?.iter().find(|held| {
+            held.lock_owner != lock.lock_owner
+                && held.overlaps(lock.start, lock.end)
+                && (held.typ == F_WRLCK || lock.typ == F_WRLCK)
+        })
+    }
+
+    /// Takes or, with `F_UNLCK`, releases `lock`. Locks of the same owner over the range
+    /// are replaced, splitting them when the range falls in their middle.
+    /// Returns `false` without changing anything if another owner holds a conflicting lock.
+    pub fn set_lock(&mut self, ino: u64, lock: FileLock) -> bool {
+        if lock.typ != F_UNLCK && self.find_conflict(ino, &lock).is_some() {
+            return false;
+        }
+
+        let held = self.locks.remove(&ino).unwrap_or_default();
+        let mut locks = vec![];
+        for held in held {
+            if held.lock_owner != lock.lock_owner || !held.overlaps(lock.start, lock.end) {
+                locks.push(held);
+                continue;
+            }
+            if held.start < lock.start {
+                locks.push(FileLock {
+                    end: lock.start - 1,
+                    ..held.clone()
+                });
+            }
+            if held.end > lock.end {
+                locks.push(FileLock {
+                    start: lock.end + 1,
+                    ..held
+                });
+            }
+        }
+        if lock.typ != F_UNLCK {
+            locks.push(lock);
+        }
+        if !locks.is_empty() {
+            self.locks.insert(ino, locks);
+        }
+        true
+    }
+
+    /// Drops the locks matching `f` on `ino`. Returns `true` if any was dropped.
+    fn release_by(&mut self, ino: u64, f: impl Fn(&FileLock) -> bool) -> bool {
+        let Some(locks) = self.locks.get_mut(&ino) else {
+            return false;
+        };
+        let len = locks.len();
+        locks.retain(|lock| !f(lock));
+        let released = locks.len() != len;
+        if locks.is_empty() {
+            self.locks.remove(&ino);
+        }
+        released
+    }
+
+    /// Drops every lock of `lock_owner` on `ino`, as when the owner closes the file.
+    pub fn release_owner(&mut self, ino: u64, lock_owner: u64) -> bool {
+        self.release_by(ino, |lock| lock.lock_owner == lock_owner)
+    }
+
+    /// Drops every lock taken through the file handle `fh` of `ino`.
+    pub fn release_handle(&mut self, ino: u64, fh: u64) -> bool {
+        self.release_by(ino, |lock| lock.fh == fh)
+    }
+
+    /// Queues `lock` until it can be taken, keeping `waiter` to answer the request.
+    pub fn wait(&mut self, ino: u64, lock: FileLock, waiter: W) {
+        self.waiters.push((ino, lock, waiter));
+    }
+
+    /// Takes the waiting locks that no longer conflict and returns their waiters.
+    pub fn grant_waiters(&mut self) -> Vec<W> {
+        let mut granted = vec![];
+        for (ino, lock, waiter) in std::mem::take(&mut self.waiters) {
+            if self.set_lock(ino, lock.clone()) {
+                granted.push(waiter);
+            } else {
+                self.waiters.push((ino, lock, waiter));
+            }
+        }
+        granted
+    }
+
+    /// Removes the requests waiting through the file handle `fh` of `ino` or, if given,
+    /// by `lock_owner`, as when the file is released, and returns their waiters.
+    pub fn cancel_waiters(&mut self, ino: u64, fh: u64, lock_owner: Option<u64>) -> Vec<W> {
+        let mut canceled = vec![];
+        for (waiter_ino, lock, waiter) in std::mem::take(&mut self.waiters) {
+            if waiter_ino == ino && (lock.fh == fh || Some(lock.lock_owner) == lock_owner) {
+                canceled.push(waiter);
+            } else {
+                self.waiters.push((waiter_ino, lock, waiter));
+            }
+        }
+        canceled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libc::F_RDLCK;
+
+    fn lock(lock_owner: u64, start: u64, end: u64, typ: i32) -> FileLock {
+        FileLock {
+            fh: lock_owner,
+            lock_owner,
+            start,
+            end,
+            typ,
+            pid: lock_owner as u32,
+        }
+    }
+
+    #[test]
+    fn test_lock_table_conflicts() {
+        let mut table = LockTable::<()>::new();
+        assert!(table.set_lock(1, lock(1, 0, 9, F_RDLCK)));
+        assert!(table.set_lock(1, lock(2, 5, 14, F_RDLCK)));
+        assert!(!table.set_lock(1, lock(3, 9, 9, F_WRLCK)));
+        assert!(table.set_lock(1, lock(3, 15, 20, F_WRLCK)));
+        assert!(table.set_lock(2, lock(3, 0, 9, F_WRLCK)));
+
+        assert_eq!(
+            table.find_conflict(1, &lock(4, 0, 0, F_WRLCK)),
+            Some(&lock(1, 0, 9, F_RDLCK))
+        );
+        assert_eq!(table.find_conflict(1, &lock(1, 0, 4, F_WRLCK)), None);
+    }
+
+    #[test]
+    fn test_lock_table_unlock_splits_range() {
+        let mut table = LockTable::<()>::new();
+        assert!(table.set_lock(1, lock(1, 0, 99, F_WRLCK)));
+        assert!(table.set_lock(1, lock(1, 10, 19, F_UNLCK)));
+
+        assert!(table.set_lock(1, lock(2, 10, 19, F_WRLCK)));
+        assert!(!table.set_lock(1, lock(2, 9, 9, F_RDLCK)));
+        assert!(!table.set_lock(1, lock(2, 20, 20, F_RDLCK)));
+    }
+
+    #[test]
+    fn test_lock_table_release() {
+        let mut table = LockTable::<()>::new();
+        assert!(table.set_lock(1, lock(1, 0, u64::MAX, F_WRLCK)));
+        assert!(!table.set_lock(1, lock(2, 0, 0, F_RDLCK)));
+
+        assert!(!table.release_owner(1, 2));
+        assert!(table.release_owner(1, 1));
+        assert!(table.set_lock(1, lock(2, 0, 0, F_RDLCK)));
+
+        assert!(table.release_handle(1, 2));
+        assert!(table.set_lock(1, lock(1, 0, 0, F_WRLCK)));
+    }
+
+    #[test]
+    fn test_lock_table_waiters() {
+        let mut table = LockTable::new();
+        assert!(table.set_lock(1, lock(1, 0, 9, F_WRLCK)));
+        table.wait(1, lock(2, 0, 0, F_RDLCK), "owner 2");
+        table.wait(1, lock(3, 5, 5, F_WRLCK), "owner 3");
+        table.wait(2, lock(2, 0, 0, F_WRLCK), "owner 2 on ino 2");
+        assert_eq!(table.grant_waiters(), vec!["owner 2 on ino 2"]);
+
+        // the waiter of a released file is dropped instead of being granted later
+        assert_eq!(table.cancel_waiters(1, 2, None), vec!["owner 2"]);
+        assert_eq!(table.cancel_waiters(1, 4, Some(2)), Vec::<&str>::new());
+
+        assert!(table.release_owner(1, 1));
+        assert_eq!(table.grant_waiters(), vec!["owner 3"]);
+        assert_eq!(table.grant_waiters(), Vec::<&str>::new());
+    }
+}